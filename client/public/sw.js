@@ -0,0 +1,25 @@
+// Web Push: shows new-chapter notifications sent by the server (see /api/push/subscriptions).
+self.addEventListener('push', (event) => {
+  const data = event.data ? event.data.json() : {}
+  event.waitUntil(
+    self.registration.showNotification(data.title || '新着話', {
+      body: data.body,
+      tag: data.tag,
+      icon: 'favicon.svg',
+      data: { url: data.url },
+    }),
+  )
+})
+
+self.addEventListener('notificationclick', (event) => {
+  event.notification.close()
+  const url = event.notification.data?.url
+  if (!url) return
+  event.waitUntil(
+    self.clients.matchAll({ type: 'window', includeUncontrolled: true }).then((clients) => {
+      const client = clients.find((c) => 'focus' in c)
+      if (client) return client.navigate(url).then((c) => (c || client).focus())
+      return self.clients.openWindow(url)
+    }),
+  )
+})
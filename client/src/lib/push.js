@@ -0,0 +1,50 @@
+import config from '$lib/config.js'
+import fetcher from '$lib/fetcher.js'
+import { getBasePath } from '$lib/router.svelte.js'
+
+export const pushSupported = () =>
+  'serviceWorker' in navigator && 'PushManager' in window && 'Notification' in window
+
+function decodeKey(base64url) {
+  const base64 = base64url.replace(/-/g, '+').replace(/_/g, '/')
+  const raw = atob(base64 + '='.repeat((4 - (base64.length % 4)) % 4))
+  return Uint8Array.from(raw, (c) => c.charCodeAt(0))
+}
+
+function register() {
+  const base = getBasePath()
+  return navigator.serviceWorker.register(`${base}/sw.js`, { scope: `${base}/` })
+}
+
+export async function getSubscription() {
+  if (!pushSupported()) return null
+  const registration = await register()
+  return registration.pushManager.getSubscription()
+}
+
+export async function subscribe() {
+  if ((await Notification.requestPermission()) !== 'granted') {
+    throw new Error('通知が許可されていません')
+  }
+  const registration = await register()
+  const { public_key } = await fetcher(`${config.path.api}/push/vapid-public-key`)
+  const subscription = await registration.pushManager.subscribe({
+    userVisibleOnly: true,
+    applicationServerKey: decodeKey(public_key),
+  })
+  await fetcher(`${config.path.api}/push/subscriptions`, {
+    method: 'POST',
+    headers: { 'Content-Type': 'application/json' },
+    body: JSON.stringify(subscription.toJSON()),
+  })
+  return subscription
+}
+
+export async function unsubscribe(subscription) {
+  await fetcher(`${config.path.api}/push/subscriptions`, {
+    method: 'DELETE',
+    headers: { 'Content-Type': 'application/json' },
+    body: JSON.stringify({ endpoint: subscription.endpoint }),
+  }).catch(() => {})
+  await subscription.unsubscribe()
+}
@@ -1,15 +1,17 @@
+use crate::db::Db;
+use crate::repo::UsersRepo;
 use axum::extract::{Request, State};
 use axum::http::HeaderMap;
 use axum::middleware::Next;
 use axum::response::Response;
-use rusqlite::Connection;
-use std::sync::{Arc, Mutex};
+use rand::RngCore;
+use sha2::{Digest, Sha256};
 
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, Debug, PartialEq)]
 pub struct UserId(pub i64);
 
 pub async fn resolve_user(
-    State(db): State<Arc<Mutex<Connection>>>,
+    State(db): State<Db>,
     headers: HeaderMap,
     mut request: Request,
     next: Next,
@@ -18,23 +20,41 @@ pub async fn resolve_user(
         .get("x-forwarded-email")
         .and_then(|v| v.to_str().ok())
     {
-        Some(email) => get_or_create_user(&db, email),
+        Some(email) => get_or_create_user(&db, email).await,
         None => UserId(1), // guest
     };
     request.extensions_mut().insert(user_id);
     next.run(request).await
 }
 
-fn get_or_create_user(db: &Arc<Mutex<Connection>>, email: &str) -> UserId {
-    let conn = db.lock().unwrap();
-    conn.execute("INSERT OR IGNORE INTO users (email) VALUES (?1)", [email])
-        .ok();
-    let id = conn
-        .query_row(
-            "SELECT id FROM users WHERE email = ?1",
-            [email],
-            |row| row.get::<_, i64>(0),
-        )
-        .unwrap_or(1); // fallback to guest
-    UserId(id)
+/// Known users only need a read; the writer is used the first time an email is seen.
+async fn get_or_create_user(db: &Db, email: &str) -> UserId {
+    let lookup = email.to_string();
+    let found = db
+        .read(move |conn| UsersRepo::new(conn).find_by_email(&lookup))
+        .await;
+    let result = match found {
+        Ok(Some(id)) => Ok(id),
+        _ => {
+            let email = email.to_string();
+            db.write(move |conn| UsersRepo::new(conn).get_or_create(&email))
+                .await
+        }
+    };
+    result.unwrap_or_else(|e| {
+        tracing::error!("[auth] user lookup failed: {}", e);
+        UserId(1) // fallback to guest
+    })
+}
+
+/// Random 256-bit secret, hex encoded. Used for webhook signing secrets and feed tokens.
+pub fn random_token() -> String {
+    let mut bytes = [0u8; 32];
+    rand::thread_rng().fill_bytes(&mut bytes);
+    hex::encode(bytes)
+}
+
+/// Feed tokens are stored as SHA-256 digests so a leaked database doesn't expose usable URLs.
+pub fn hash_token(token: &str) -> String {
+    hex::encode(Sha256::digest(token.as_bytes()))
 }
@@ -5,6 +5,73 @@ pub struct Config {
     pub port: u16,
     pub base_path: String,
     pub db_path: String,
+    /// Base64url-encoded P-256 private key for Web Push (VAPID).
+    /// When unset, a key is generated on first use and kept in the database.
+    pub vapid_private_key: Option<String>,
+    /// Contact URI sent to push services in the VAPID JWT (`mailto:` or `https:`)
+    pub vapid_subject: String,
+    /// Externally reachable URL including `BASE_PATH` (e.g. `https://example.com/novels`).
+    /// Used for links in messages sent outside a request, such as digest emails.
+    pub public_url: Option<String>,
+    /// Outgoing mail server; digest emails are disabled when `SMTP_HOST` is unset.
+    pub smtp: Option<SmtpConfig>,
+    /// Owner of favorites when migrating a database from before multi-user support
+    pub migration_owner_email: Option<String>,
+    /// Let webhooks target loopback, private and link-local addresses (`WEBHOOK_ALLOW_PRIVATE`).
+    /// Off by default so users can't make the server call internal services.
+    pub webhook_allow_private: bool,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SmtpTls {
+    /// Plain connection upgraded with STARTTLS (port 587)
+    StartTls,
+    /// Implicit TLS (port 465)
+    Tls,
+    /// No encryption — only for local relays and test sinks
+    None,
+}
+
+#[derive(Clone)]
+pub struct SmtpConfig {
+    pub host: String,
+    pub port: u16,
+    pub tls: SmtpTls,
+    pub username: Option<String>,
+    pub password: Option<String>,
+    pub from: String,
+}
+
+impl SmtpConfig {
+    fn from_env() -> Option<Self> {
+        let host = env::var("SMTP_HOST").ok().filter(|h| !h.is_empty())?;
+        let tls = match env::var("SMTP_TLS").unwrap_or_default().as_str() {
+            "" | "starttls" => SmtpTls::StartTls,
+            "tls" => SmtpTls::Tls,
+            "none" => SmtpTls::None,
+            other => panic!(
+                "Invalid SMTP_TLS: {} (expected starttls, tls or none)",
+                other
+            ),
+        };
+        let port = env::var("SMTP_PORT")
+            .ok()
+            .and_then(|p| p.parse().ok())
+            .unwrap_or(match tls {
+                SmtpTls::Tls => 465,
+                SmtpTls::StartTls => 587,
+                SmtpTls::None => 25,
+            });
+        let from = env::var("SMTP_FROM").expect("SMTP_FROM is required when SMTP_HOST is set");
+        Some(Self {
+            host,
+            port,
+            tls,
+            username: env::var("SMTP_USERNAME").ok().filter(|u| !u.is_empty()),
+            password: env::var("SMTP_PASSWORD").ok(),
+            from,
+        })
+    }
 }
 
 impl Config {
@@ -28,10 +95,32 @@ impl Config {
 
         let db_path = env::var("DATABASE_PATH").unwrap_or_else(|_| "/data/novel.db".to_string());
 
+        let vapid_private_key = env::var("VAPID_PRIVATE_KEY")
+            .ok()
+            .filter(|k| !k.trim().is_empty());
+        let vapid_subject =
+            env::var("VAPID_SUBJECT").unwrap_or_else(|_| "mailto:admin@localhost".to_string());
+
+        let public_url = env::var("PUBLIC_URL")
+            .ok()
+            .map(|u| u.trim_end_matches('/').to_string())
+            .filter(|u| !u.is_empty());
+
         Self {
             port,
             base_path,
             db_path,
+            vapid_private_key,
+            vapid_subject,
+            public_url,
+            smtp: SmtpConfig::from_env(),
+            migration_owner_email: env::var("MIGRATION_OWNER_EMAIL")
+                .ok()
+                .filter(|e| !e.trim().is_empty()),
+            webhook_allow_private: matches!(
+                env::var("WEBHOOK_ALLOW_PRIVATE").as_deref(),
+                Ok("1" | "true")
+            ),
         }
     }
 }
@@ -1,5 +1,14 @@
-use rusqlite::Connection;
+use crate::error::AppError;
+use rusqlite::{Connection, OpenFlags};
+use std::ops::Deref;
+use std::sync::{Arc, Mutex, PoisonError};
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
 
+/// Read-only connections opened next to the writer. WAL lets them read while
+/// a sync transaction is in progress.
+const READER_COUNT: usize = 4;
+
+/// Schema as of migration 3. Later changes go into new migrations, not here.
 const SCHEMA: &str = "
     CREATE TABLE IF NOT EXISTS users (
         id INTEGER PRIMARY KEY AUTOINCREMENT,
@@ -7,24 +16,407 @@ const SCHEMA: &str = "
     );
     INSERT OR IGNORE INTO users (id, email) VALUES (1, 'guest');
 
-    CREATE TABLE IF NOT EXISTS favorites (
-        user_id INTEGER NOT NULL DEFAULT 1,
+    CREATE TABLE IF NOT EXISTS novels (
         type TEXT NOT NULL,
         id TEXT NOT NULL,
         title TEXT NOT NULL,
         novelupdated_at TEXT,
         page INTEGER NOT NULL,
+        missing_count INTEGER NOT NULL DEFAULT 0,
+        gone_at TEXT,
+        PRIMARY KEY (type, id)
+    );
+    CREATE INDEX IF NOT EXISTS idx_novels_updated
+        ON novels (novelupdated_at DESC);
+
+    CREATE TABLE IF NOT EXISTS favorites (
+        user_id INTEGER NOT NULL DEFAULT 1,
+        type TEXT NOT NULL,
+        id TEXT NOT NULL,
         read INTEGER NOT NULL DEFAULT 0,
+        added_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP,
+        notes TEXT,
         PRIMARY KEY (user_id, type, id),
+        FOREIGN KEY (user_id) REFERENCES users(id),
+        FOREIGN KEY (type, id) REFERENCES novels(type, id)
+    );
+
+    CREATE TABLE IF NOT EXISTS webhooks (
+        id INTEGER PRIMARY KEY AUTOINCREMENT,
+        user_id INTEGER NOT NULL,
+        url TEXT NOT NULL,
+        secret TEXT NOT NULL,
+        events TEXT NOT NULL,
+        created_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP,
+        FOREIGN KEY (user_id) REFERENCES users(id)
+    );
+    CREATE INDEX IF NOT EXISTS idx_webhooks_user ON webhooks (user_id);
+
+    CREATE TABLE IF NOT EXISTS webhook_deliveries (
+        id INTEGER PRIMARY KEY AUTOINCREMENT,
+        webhook_id INTEGER NOT NULL,
+        event TEXT NOT NULL,
+        payload TEXT NOT NULL,
+        status INTEGER,
+        attempts INTEGER NOT NULL,
+        success INTEGER NOT NULL,
+        error TEXT,
+        created_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP,
+        FOREIGN KEY (webhook_id) REFERENCES webhooks(id) ON DELETE CASCADE
+    );
+    CREATE INDEX IF NOT EXISTS idx_webhook_deliveries_webhook
+        ON webhook_deliveries (webhook_id, id DESC);
+
+    CREATE TABLE IF NOT EXISTS settings (
+        key TEXT PRIMARY KEY,
+        value TEXT NOT NULL
+    );
+
+    CREATE TABLE IF NOT EXISTS push_subscriptions (
+        id INTEGER PRIMARY KEY AUTOINCREMENT,
+        user_id INTEGER NOT NULL,
+        endpoint TEXT NOT NULL UNIQUE,
+        p256dh TEXT NOT NULL,
+        auth TEXT NOT NULL,
+        created_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP,
+        FOREIGN KEY (user_id) REFERENCES users(id)
+    );
+    CREATE INDEX IF NOT EXISTS idx_push_subscriptions_user ON push_subscriptions (user_id);
+
+    CREATE TABLE IF NOT EXISTS feed_tokens (
+        user_id INTEGER PRIMARY KEY,
+        token_hash TEXT NOT NULL UNIQUE,
+        created_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP,
+        FOREIGN KEY (user_id) REFERENCES users(id)
+    );
+
+    CREATE TABLE IF NOT EXISTS digest_settings (
+        user_id INTEGER PRIMARY KEY,
+        enabled INTEGER NOT NULL DEFAULT 0,
+        frequency TEXT NOT NULL DEFAULT 'daily',
+        hour INTEGER NOT NULL DEFAULT 0,
+        weekday INTEGER NOT NULL DEFAULT 0,
+        last_sent_at TEXT,
         FOREIGN KEY (user_id) REFERENCES users(id)
     );
-    CREATE INDEX IF NOT EXISTS idx_favorites_updated
-        ON favorites (user_id, novelupdated_at DESC);
+
+    CREATE TABLE IF NOT EXISTS chapter_updates (
+        type TEXT NOT NULL,
+        id TEXT NOT NULL,
+        old_page INTEGER NOT NULL,
+        new_page INTEGER NOT NULL,
+        detected_at TEXT NOT NULL,
+        FOREIGN KEY (type, id) REFERENCES novels(type, id)
+    );
+    CREATE INDEX IF NOT EXISTS idx_chapter_updates_detected ON chapter_updates (detected_at);
 ";
 
-pub fn open(path: &str) -> Connection {
+/// Options for migrations that need deployment-specific input.
+#[derive(Default)]
+pub struct MigrationOptions {
+    /// Owner of favorites in a pre-multi-user database. The guest user when unset.
+    pub owner_email: Option<String>,
+}
+
+struct Migration {
+    version: i64,
+    name: &'static str,
+    apply: fn(&Connection, &MigrationOptions) -> rusqlite::Result<()>,
+}
+
+/// Schema changes in order; `PRAGMA user_version` holds the last one applied.
+/// Append new migrations here and never edit released ones. The first three are
+/// written to also accept databases created before versioning, which are all at 0.
+const MIGRATIONS: &[Migration] = &[
+    Migration {
+        version: 1,
+        name: "assign single-user favorites to an owner",
+        apply: add_users,
+    },
+    Migration {
+        version: 2,
+        name: "move novel metadata out of favorites",
+        apply: split_novels,
+    },
+    Migration {
+        version: 3,
+        name: "baseline schema",
+        apply: |conn, _| conn.execute_batch(SCHEMA),
+    },
+    Migration {
+        version: 4,
+        name: "in-chapter reading position",
+        apply: |conn, _| {
+            conn.execute_batch(
+                "ALTER TABLE favorites ADD COLUMN read_paragraph INTEGER;
+                 ALTER TABLE favorites ADD COLUMN read_fraction REAL;
+                 ALTER TABLE favorites ADD COLUMN progress_at TEXT;",
+            )
+        },
+    },
+    Migration {
+        version: 5,
+        name: "reading history",
+        apply: |conn, _| {
+            conn.execute_batch(
+                "CREATE TABLE reading_events (
+                    id INTEGER PRIMARY KEY AUTOINCREMENT,
+                    user_id INTEGER NOT NULL,
+                    type TEXT NOT NULL,
+                    novel_id TEXT NOT NULL,
+                    page INTEGER NOT NULL,
+                    device TEXT,
+                    read_at TEXT NOT NULL,
+                    FOREIGN KEY (user_id) REFERENCES users(id)
+                );
+                CREATE INDEX idx_reading_events_user ON reading_events (user_id, id);
+                CREATE INDEX idx_reading_events_novel
+                    ON reading_events (user_id, type, novel_id, id);",
+            )
+        },
+    },
+    Migration {
+        version: 6,
+        name: "chapter lengths",
+        apply: |conn, _| {
+            conn.execute_batch(
+                "CREATE TABLE chapter_lengths (
+                    type TEXT NOT NULL,
+                    id TEXT NOT NULL,
+                    page INTEGER NOT NULL,
+                    chars INTEGER NOT NULL,
+                    PRIMARY KEY (type, id, page)
+                );",
+            )
+        },
+    },
+    Migration {
+        version: 7,
+        name: "recently read novels",
+        apply: |conn, _| {
+            conn.execute_batch(
+                "CREATE TABLE recent_reads (
+                    user_id INTEGER NOT NULL,
+                    type TEXT NOT NULL,
+                    id TEXT NOT NULL,
+                    title TEXT,
+                    page INTEGER,
+                    read INTEGER NOT NULL,
+                    read_paragraph INTEGER,
+                    read_fraction REAL,
+                    progress_at TEXT NOT NULL,
+                    PRIMARY KEY (user_id, type, id),
+                    FOREIGN KEY (user_id) REFERENCES users(id)
+                );
+                CREATE INDEX idx_recent_reads_progress ON recent_reads (user_id, progress_at);",
+            )
+        },
+    },
+    Migration {
+        version: 8,
+        name: "favorite tags and manual order",
+        apply: |conn, _| {
+            conn.execute_batch(
+                "CREATE TABLE tags (
+                    id INTEGER PRIMARY KEY AUTOINCREMENT,
+                    user_id INTEGER NOT NULL,
+                    name TEXT NOT NULL,
+                    created_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP,
+                    UNIQUE (user_id, name),
+                    FOREIGN KEY (user_id) REFERENCES users(id)
+                );
+                CREATE TABLE favorite_tags (
+                    user_id INTEGER NOT NULL,
+                    type TEXT NOT NULL,
+                    id TEXT NOT NULL,
+                    tag_id INTEGER NOT NULL,
+                    PRIMARY KEY (user_id, type, id, tag_id),
+                    FOREIGN KEY (user_id, type, id)
+                        REFERENCES favorites(user_id, type, id) ON DELETE CASCADE,
+                    FOREIGN KEY (tag_id) REFERENCES tags(id) ON DELETE CASCADE
+                );
+                CREATE INDEX idx_favorite_tags_tag ON favorite_tags (tag_id);
+                ALTER TABLE favorites ADD COLUMN sort_order INTEGER;",
+            )
+        },
+    },
+    Migration {
+        version: 9,
+        name: "favorite ratings",
+        apply: |conn, _| conn.execute_batch("ALTER TABLE favorites ADD COLUMN rating INTEGER;"),
+    },
+    Migration {
+        version: 10,
+        name: "bookmarks",
+        apply: |conn, _| {
+            conn.execute_batch(
+                "CREATE TABLE bookmarks (
+                    id INTEGER PRIMARY KEY AUTOINCREMENT,
+                    user_id INTEGER NOT NULL,
+                    type TEXT NOT NULL,
+                    novel_id TEXT NOT NULL,
+                    page INTEGER NOT NULL,
+                    paragraph INTEGER NOT NULL,
+                    quote TEXT NOT NULL,
+                    note TEXT,
+                    created_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP,
+                    updated_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP,
+                    FOREIGN KEY (user_id) REFERENCES users(id)
+                );
+                CREATE INDEX idx_bookmarks_novel ON bookmarks (user_id, type, novel_id, page);",
+            )
+        },
+    },
+];
+
+/// Handle to the database shared by requests and background tasks.
+///
+/// Writes go through a single connection; reads use a small pool of read-only
+/// connections. Both run on the blocking thread pool, so SQLite never stalls
+/// the async runtime and a slow write doesn't hold up reads.
+#[derive(Clone)]
+pub struct Db {
+    writer: Arc<Mutex<Connection>>,
+    readers: Option<Arc<Readers>>,
+}
+
+struct Readers {
+    idle: Mutex<Vec<Connection>>,
+    permits: Arc<Semaphore>,
+}
+
+/// A reader checked out of the pool, returned on drop even if the query panics.
+/// The permit is released only after that, so a request cancelled while its
+/// query still runs can't let another reader find the pool empty.
+struct ReaderGuard<'a> {
+    readers: &'a Readers,
+    conn: Option<Connection>,
+    _permit: OwnedSemaphorePermit,
+}
+
+impl Deref for ReaderGuard<'_> {
+    type Target = Connection;
+
+    fn deref(&self) -> &Connection {
+        self.conn.as_ref().unwrap()
+    }
+}
+
+impl Drop for ReaderGuard<'_> {
+    fn drop(&mut self) {
+        if let Some(conn) = self.conn.take() {
+            lock(&self.readers.idle).push(conn);
+        }
+    }
+}
+
+/// A panic while holding a connection leaves the mutex poisoned. The connection
+/// itself is still usable (an open transaction rolls back when dropped), so
+/// carry on instead of failing every later request.
+fn lock<T>(mutex: &Mutex<T>) -> std::sync::MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+impl Db {
+    /// Open the database file, migrate it, and open the reader pool.
+    pub fn open(path: &str, options: &MigrationOptions) -> Db {
+        let writer = open_writer(path, options);
+        let readers = (0..READER_COUNT)
+            .map(|_| open_reader(path))
+            .collect::<Vec<_>>();
+        Db {
+            writer: Arc::new(Mutex::new(writer)),
+            readers: Some(Arc::new(Readers {
+                permits: Arc::new(Semaphore::new(readers.len())),
+                idle: Mutex::new(readers),
+            })),
+        }
+    }
+
+    /// Wrap a single connection that serves both reads and writes, for tests on
+    /// in-memory databases, which can't be shared between connections.
+    #[cfg(test)]
+    pub fn from_connection(conn: Connection) -> Db {
+        Db {
+            writer: Arc::new(Mutex::new(conn)),
+            readers: None,
+        }
+    }
+
+    /// Run `f` on a read-only connection.
+    pub async fn read<T, E, F>(&self, f: F) -> Result<T, AppError>
+    where
+        F: FnOnce(&Connection) -> Result<T, E> + Send + 'static,
+        T: Send + 'static,
+        E: Into<AppError> + Send + 'static,
+    {
+        let Some(readers) = &self.readers else {
+            return self.write(move |conn| f(conn)).await;
+        };
+        let permit = readers
+            .permits
+            .clone()
+            .acquire_owned()
+            .await
+            .map_err(|e| AppError::Internal(e.to_string()))?;
+        let readers = readers.clone();
+        blocking(move || {
+            // Holding a permit guarantees an idle connection
+            let conn = lock(&readers.idle).pop();
+            let guard = ReaderGuard {
+                readers: &readers,
+                conn,
+                _permit: permit,
+            };
+            f(&guard)
+        })
+        .await
+    }
+
+    /// Run `f` on the writer connection. Writes are serialized.
+    pub async fn write<T, E, F>(&self, f: F) -> Result<T, AppError>
+    where
+        F: FnOnce(&mut Connection) -> Result<T, E> + Send + 'static,
+        T: Send + 'static,
+        E: Into<AppError> + Send + 'static,
+    {
+        let writer = self.writer.clone();
+        blocking(move || f(&mut lock(&writer))).await
+    }
+}
+
+async fn blocking<T, E, F>(f: F) -> Result<T, AppError>
+where
+    F: FnOnce() -> Result<T, E> + Send + 'static,
+    T: Send + 'static,
+    E: Into<AppError> + Send + 'static,
+{
+    match tokio::task::spawn_blocking(f).await {
+        Ok(result) => result.map_err(Into::into),
+        Err(e) => {
+            tracing::error!("[db] query task failed: {}", e);
+            Err(AppError::Internal("database task failed".into()))
+        }
+    }
+}
+
+fn open_reader(path: &str) -> Connection {
+    let conn = Connection::open_with_flags(
+        path,
+        OpenFlags::SQLITE_OPEN_READ_ONLY | OpenFlags::SQLITE_OPEN_NO_MUTEX,
+    )
+    .expect("Failed to open database reader");
+    conn.execute_batch(
+        "PRAGMA cache_size = -16000;
+         PRAGMA temp_store = MEMORY;",
+    )
+    .expect("Failed to set PRAGMA");
+    conn
+}
+
+fn open_writer(path: &str, options: &MigrationOptions) -> Connection {
     tracing::info!("Database: {}", path);
-    let conn = Connection::open(path).expect("Failed to open database");
+    let mut conn = Connection::open(path).expect("Failed to open database");
 
     conn.execute_batch(
         "PRAGMA journal_mode = WAL;
@@ -35,8 +427,196 @@ pub fn open(path: &str) -> Connection {
     )
     .expect("Failed to set PRAGMA");
 
-    conn.execute_batch(SCHEMA).expect("Failed to create tables");
+    let version = user_version(&conn).expect("Failed to read schema version");
+    check_version(version).unwrap_or_else(|e| panic!("{}", e));
+    if pending(version).next().is_some() && has_tables(&conn).expect("Failed to inspect database") {
+        let backup = backup_path(path, version);
+        conn.execute("VACUUM INTO ?1", [&backup])
+            .expect("Failed to back up database before migrating");
+        tracing::info!("[db] backed up schema version {} to {}", version, backup);
+    }
+    migrate(&mut conn, options).expect("Failed to migrate database");
+
+    conn
+}
+
+fn user_version(conn: &Connection) -> rusqlite::Result<i64> {
+    conn.query_row("PRAGMA user_version", [], |row| row.get(0))
+}
+
+/// Refuse to run against a database migrated by a newer server.
+fn check_version(version: i64) -> Result<(), String> {
+    let latest = MIGRATIONS.last().map_or(0, |m| m.version);
+    if version > latest {
+        return Err(format!(
+            "Database schema version {} is newer than this server supports ({})",
+            version, latest
+        ));
+    }
+    Ok(())
+}
+
+fn pending(version: i64) -> impl Iterator<Item = &'static Migration> {
+    MIGRATIONS.iter().filter(move |m| m.version > version)
+}
+
+fn has_tables(conn: &Connection) -> rusqlite::Result<bool> {
+    conn.query_row(
+        "SELECT COUNT(*) > 0 FROM sqlite_master WHERE type = 'table'",
+        [],
+        |row| row.get(0),
+    )
+}
+
+fn backup_path(path: &str, version: i64) -> String {
+    format!(
+        "{}.v{}-{}.bak",
+        path,
+        version,
+        chrono::Utc::now().format("%Y%m%d%H%M%S")
+    )
+}
+
+/// Apply pending migrations, each in its own transaction together with the
+/// `user_version` bump, so a failure leaves the database at the last good version.
+fn migrate(conn: &mut Connection, options: &MigrationOptions) -> rusqlite::Result<()> {
+    let version = user_version(conn)?;
+    for migration in pending(version) {
+        let tx = conn.transaction()?;
+        (migration.apply)(&tx, options)?;
+        tx.pragma_update(None, "user_version", migration.version)?;
+        tx.commit()?;
+        tracing::info!(
+            "[db] applied migration {}: {}",
+            migration.version,
+            migration.name
+        );
+    }
+    Ok(())
+}
+
+/// Databases from before multi-user support have a `favorites` table without
+/// `user_id` and no `users` table. Their favorites go to `owner_email`, or to the
+/// guest user, which is who served every request back then.
+fn add_users(conn: &Connection, options: &MigrationOptions) -> rusqlite::Result<()> {
+    if !has_table(conn, "favorites")? || has_column(conn, "favorites", "user_id")? {
+        return Ok(());
+    }
+    conn.execute_batch(
+        "CREATE TABLE IF NOT EXISTS users (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            email TEXT NOT NULL UNIQUE
+        );
+        INSERT OR IGNORE INTO users (id, email) VALUES (1, 'guest');
+        DROP INDEX IF EXISTS idx_favorites_updated;
+        ALTER TABLE favorites ADD COLUMN user_id INTEGER NOT NULL DEFAULT 1;",
+    )?;
+    if let Some(email) = &options.owner_email {
+        conn.execute("INSERT OR IGNORE INTO users (email) VALUES (?1)", [email])?;
+        conn.execute(
+            "UPDATE favorites SET user_id = (SELECT id FROM users WHERE email = ?1)",
+            [email],
+        )?;
+    }
+    let owner = options.owner_email.as_deref().unwrap_or("guest");
+    tracing::info!("[db] assigned existing favorites to {}", owner);
+    Ok(())
+}
+
+/// Move shared novel metadata out of the per-user `favorites` rows into `novels`.
+///
+/// Before the split every user had their own copy of title/page/updated_at, so
+/// the copy with the most pages wins. `added_at` is unknown for existing rows
+/// and is set to the migration time.
+fn split_novels(conn: &Connection, _: &MigrationOptions) -> rusqlite::Result<()> {
+    if !has_column(conn, "favorites", "title")? {
+        return Ok(());
+    }
+    // Databases created before gone detection lack these columns
+    add_column_if_missing(
+        conn,
+        "favorites",
+        "missing_count",
+        "INTEGER NOT NULL DEFAULT 0",
+    )?;
+    add_column_if_missing(conn, "favorites", "gone_at", "TEXT")?;
+
+    conn.execute_batch(
+        "CREATE TABLE IF NOT EXISTS novels (
+            type TEXT NOT NULL,
+            id TEXT NOT NULL,
+            title TEXT NOT NULL,
+            novelupdated_at TEXT,
+            page INTEGER NOT NULL,
+            missing_count INTEGER NOT NULL DEFAULT 0,
+            gone_at TEXT,
+            PRIMARY KEY (type, id)
+        );
+        -- Bare columns take their values from the row holding MAX(page)
+        INSERT OR IGNORE INTO novels (type, id, title, novelupdated_at, page, missing_count, gone_at)
+            SELECT type, id, title, novelupdated_at, MAX(page), missing_count, gone_at
+            FROM favorites GROUP BY type, id;
+
+        DROP INDEX IF EXISTS idx_favorites_updated;
+        ALTER TABLE favorites RENAME TO favorites_old;
+        CREATE TABLE favorites (
+            user_id INTEGER NOT NULL DEFAULT 1,
+            type TEXT NOT NULL,
+            id TEXT NOT NULL,
+            read INTEGER NOT NULL DEFAULT 0,
+            added_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP,
+            notes TEXT,
+            PRIMARY KEY (user_id, type, id),
+            FOREIGN KEY (user_id) REFERENCES users(id),
+            FOREIGN KEY (type, id) REFERENCES novels(type, id)
+        );
+        INSERT INTO favorites (user_id, type, id, read)
+            SELECT user_id, type, id, read FROM favorites_old;
+        DROP TABLE favorites_old;",
+    )?;
+    tracing::info!("[db] migrated favorites metadata into novels table");
+    Ok(())
+}
+
+fn has_table(conn: &Connection, table: &str) -> rusqlite::Result<bool> {
+    conn.query_row(
+        "SELECT COUNT(*) > 0 FROM sqlite_master WHERE type = 'table' AND name = ?1",
+        [table],
+        |row| row.get(0),
+    )
+}
+
+fn has_column(conn: &Connection, table: &str, column: &str) -> rusqlite::Result<bool> {
+    conn.query_row(
+        &format!(
+            "SELECT COUNT(*) > 0 FROM pragma_table_info('{}') WHERE name = ?1",
+            table
+        ),
+        [column],
+        |row| row.get(0),
+    )
+}
+
+fn add_column_if_missing(
+    conn: &Connection,
+    table: &str,
+    column: &str,
+    decl: &str,
+) -> rusqlite::Result<()> {
+    if !has_column(conn, table, column)? {
+        conn.execute_batch(&format!(
+            "ALTER TABLE {} ADD COLUMN {} {}",
+            table, column, decl
+        ))?;
+    }
+    Ok(())
+}
 
+#[cfg(test)]
+pub fn open_memory() -> Connection {
+    let mut conn = Connection::open_in_memory().unwrap();
+    conn.execute_batch("PRAGMA foreign_keys = ON;").unwrap();
+    migrate(&mut conn, &MigrationOptions::default()).unwrap();
     conn
 }
 
@@ -44,16 +624,264 @@ pub fn open(path: &str) -> Connection {
 mod tests {
     use super::*;
 
-    fn open_memory() -> Connection {
-        let conn = Connection::open_in_memory().unwrap();
-        conn.execute_batch(SCHEMA).unwrap();
-        conn
+    fn insert_novel(conn: &Connection, id: &str, title: &str, page: i64) {
+        conn.execute(
+            "INSERT INTO novels (type, id, title, page) VALUES ('narou', ?1, ?2, ?3)",
+            (id, title, page),
+        )
+        .unwrap();
     }
 
     #[test]
     fn schema_is_idempotent() {
+        let mut conn = open_memory();
+        conn.execute_batch(SCHEMA).unwrap();
+        migrate(&mut conn, &MigrationOptions::default()).unwrap();
+    }
+
+    #[test]
+    fn fresh_database_is_at_latest_version() {
         let conn = open_memory();
+        assert_eq!(
+            user_version(&conn).unwrap(),
+            MIGRATIONS.last().unwrap().version
+        );
+        assert!(check_version(user_version(&conn).unwrap()).is_ok());
+        assert!(check_version(MIGRATIONS.last().unwrap().version + 1).is_err());
+    }
+
+    #[test]
+    fn unversioned_current_database_is_adopted() {
+        // Created by a server from before versioning: full schema, user_version 0
+        let mut conn = Connection::open_in_memory().unwrap();
         conn.execute_batch(SCHEMA).unwrap();
+        conn.execute_batch(
+            "INSERT INTO novels (type, id, title, page) VALUES ('narou', 'n1', 'Novel', 10);
+             INSERT INTO favorites (user_id, type, id, read) VALUES (1, 'narou', 'n1', 4);",
+        )
+        .unwrap();
+        migrate(&mut conn, &MigrationOptions::default()).unwrap();
+        let read: i64 = conn
+            .query_row("SELECT read FROM favorites", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(read, 4);
+        assert_eq!(
+            user_version(&conn).unwrap(),
+            MIGRATIONS.last().unwrap().version
+        );
+    }
+
+    fn single_user_database() -> Connection {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch(
+            "CREATE TABLE favorites (
+                type TEXT NOT NULL,
+                id TEXT NOT NULL,
+                title TEXT NOT NULL,
+                novelupdated_at TEXT,
+                page INTEGER NOT NULL,
+                read INTEGER NOT NULL DEFAULT 0,
+                PRIMARY KEY (type, id)
+             );
+             CREATE INDEX idx_favorites_updated ON favorites (novelupdated_at DESC);
+             INSERT INTO favorites (type, id, title, page, read) VALUES
+                ('narou', 'n1', 'One', 10, 3),
+                ('kakuyomu', 'k1', 'Two', 5, 5);",
+        )
+        .unwrap();
+        conn
+    }
+
+    fn favorite_owners(conn: &Connection) -> Vec<(String, String, i64)> {
+        conn.prepare(
+            "SELECT u.email, f.id, f.read FROM favorites f
+             JOIN users u ON u.id = f.user_id ORDER BY f.id",
+        )
+        .unwrap()
+        .query_map([], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)))
+        .unwrap()
+        .collect::<Result<_, _>>()
+        .unwrap()
+    }
+
+    #[test]
+    fn migrate_single_user_database_to_owner() {
+        let mut conn = single_user_database();
+        let options = MigrationOptions {
+            owner_email: Some("alice@example.com".into()),
+        };
+        migrate(&mut conn, &options).unwrap();
+        assert_eq!(
+            favorite_owners(&conn),
+            [
+                ("alice@example.com".to_string(), "k1".to_string(), 5),
+                ("alice@example.com".to_string(), "n1".to_string(), 3),
+            ]
+        );
+        let guest: String = conn
+            .query_row("SELECT email FROM users WHERE id = 1", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(guest, "guest");
+        let title: String = conn
+            .query_row("SELECT title FROM novels WHERE id = 'n1'", [], |row| {
+                row.get(0)
+            })
+            .unwrap();
+        assert_eq!(title, "One");
+    }
+
+    #[test]
+    fn migrate_single_user_database_to_guest() {
+        let mut conn = single_user_database();
+        migrate(&mut conn, &MigrationOptions::default()).unwrap();
+        let owners: Vec<String> = favorite_owners(&conn).into_iter().map(|o| o.0).collect();
+        assert_eq!(owners, ["guest", "guest"]);
+    }
+
+    #[test]
+    fn failed_migration_rolls_back() {
+        let mut conn = single_user_database();
+        // A novels table without the expected columns makes the split fail
+        conn.execute_batch("CREATE TABLE novels (type TEXT, id TEXT, PRIMARY KEY (type, id));")
+            .unwrap();
+        assert!(migrate(&mut conn, &MigrationOptions::default()).is_err());
+        assert_eq!(user_version(&conn).unwrap(), 1);
+        assert!(has_column(&conn, "favorites", "title").unwrap());
+    }
+
+    #[test]
+    fn open_backs_up_before_migrating() {
+        let dir = std::env::temp_dir().join(format!("novel-db-test-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("novel.db");
+        let path = path.to_str().unwrap();
+        single_user_database()
+            .execute("VACUUM INTO ?1", [path])
+            .unwrap();
+
+        let conn = open_writer(path, &MigrationOptions::default());
+        assert_eq!(
+            user_version(&conn).unwrap(),
+            MIGRATIONS.last().unwrap().version
+        );
+        drop(conn);
+        let backups: Vec<_> = std::fs::read_dir(&dir)
+            .unwrap()
+            .filter_map(|e| e.ok())
+            .map(|e| e.file_name().into_string().unwrap())
+            .filter(|name| name.starts_with("novel.db.v0-") && name.ends_with(".bak"))
+            .collect();
+        assert_eq!(backups.len(), 1);
+        let backup = Connection::open(dir.join(&backups[0])).unwrap();
+        assert!(has_column(&backup, "favorites", "title").unwrap());
+
+        // Already current: no further backup
+        drop(open_writer(path, &MigrationOptions::default()));
+        assert_eq!(
+            std::fs::read_dir(&dir)
+                .unwrap()
+                .filter(|e| e
+                    .as_ref()
+                    .unwrap()
+                    .file_name()
+                    .to_str()
+                    .unwrap()
+                    .ends_with(".bak"))
+                .count(),
+            1
+        );
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn readers_see_committed_writes() {
+        let dir = std::env::temp_dir().join(format!("novel-db-pool-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("novel.db");
+        let db = Db::open(path.to_str().unwrap(), &MigrationOptions::default());
+
+        db.write(|conn| conn.execute("INSERT INTO users (email) VALUES ('alice@example.com')", []))
+            .await
+            .unwrap();
+        let counts = futures::future::join_all((0..READER_COUNT * 2).map(|_| {
+            db.read(|conn| {
+                conn.query_row("SELECT COUNT(*) FROM users", [], |row| row.get::<_, i64>(0))
+            })
+        }))
+        .await;
+        assert!(counts.into_iter().all(|c| c.unwrap() == 2));
+
+        // Readers are read-only
+        let result = db.read(|conn| conn.execute("DELETE FROM users", [])).await;
+        assert!(result.is_err());
+        drop(db);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn cancelled_read_keeps_its_connection_until_done() {
+        let dir = std::env::temp_dir().join(format!("novel-db-cancel-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("novel.db");
+        let db = Db::open(path.to_str().unwrap(), &MigrationOptions::default());
+        let count = |conn: &Connection| {
+            conn.query_row("SELECT COUNT(*) FROM users", [], |row| row.get::<_, i64>(0))
+        };
+
+        // Occupy every reader with a query that waits, then drop the requests
+        let (started_tx, mut started) = tokio::sync::mpsc::unbounded_channel();
+        let mut releases = Vec::new();
+        let mut tasks = Vec::new();
+        for _ in 0..READER_COUNT {
+            let (release, wait) = std::sync::mpsc::channel::<()>();
+            releases.push(release);
+            let started_tx = started_tx.clone();
+            let db = db.clone();
+            tasks.push(tokio::spawn(async move {
+                db.read(move |conn| {
+                    started_tx.send(()).unwrap();
+                    let _ = wait.recv();
+                    count(conn)
+                })
+                .await
+            }));
+        }
+        for _ in 0..READER_COUNT {
+            started.recv().await.unwrap();
+        }
+        for task in tasks {
+            task.abort();
+            assert!(task.await.unwrap_err().is_cancelled());
+        }
+
+        // A new read waits for a connection instead of finding none
+        let next = tokio::spawn({
+            let db = db.clone();
+            async move { db.read(count).await }
+        });
+        tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+        drop(releases);
+        assert_eq!(next.await.unwrap().unwrap(), 1);
+        drop(db);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn panic_in_query_does_not_poison_later_requests() {
+        let db = Db::from_connection(open_memory());
+        let result = db
+            .write(|_| -> rusqlite::Result<()> { panic!("query bug") })
+            .await;
+        assert!(matches!(result, Err(AppError::Internal(_))));
+        let email = db
+            .read(|conn| {
+                conn.query_row("SELECT email FROM users WHERE id = 1", [], |row| {
+                    row.get::<_, String>(0)
+                })
+            })
+            .await
+            .unwrap();
+        assert_eq!(email, "guest");
     }
 
     #[test]
@@ -68,15 +896,18 @@ mod tests {
     #[test]
     fn insert_and_select() {
         let conn = open_memory();
+        insert_novel(&conn, "n1234ab", "Test Novel", 100);
         conn.execute(
-            "INSERT INTO favorites (user_id, type, id, title, page) VALUES (?1, ?2, ?3, ?4, ?5)",
-            (1, "narou", "n1234ab", "Test Novel", 100),
+            "INSERT INTO favorites (user_id, type, id) VALUES (?1, ?2, ?3)",
+            (1, "narou", "n1234ab"),
         )
         .unwrap();
 
         let (title, page): (String, i64) = conn
             .query_row(
-                "SELECT title, page FROM favorites WHERE user_id = ?1 AND type = ?2 AND id = ?3",
+                "SELECT n.title, n.page FROM favorites f
+                 JOIN novels n ON n.type = f.type AND n.id = f.id
+                 WHERE f.user_id = ?1 AND f.type = ?2 AND f.id = ?3",
                 (1, "narou", "n1234ab"),
                 |row| Ok((row.get(0)?, row.get(1)?)),
             )
@@ -88,16 +919,17 @@ mod tests {
     #[test]
     fn primary_key_is_user_type_and_id() {
         let conn = open_memory();
+        insert_novel(&conn, "n1234ab", "Novel 1", 10);
         conn.execute(
-            "INSERT INTO favorites (user_id, type, id, title, page) VALUES (?1, ?2, ?3, ?4, ?5)",
-            (1, "narou", "n1234ab", "Novel 1", 10),
+            "INSERT INTO favorites (user_id, type, id) VALUES (?1, ?2, ?3)",
+            (1, "narou", "n1234ab"),
         )
         .unwrap();
 
         // Same user+type+id should conflict
         let result = conn.execute(
-            "INSERT INTO favorites (user_id, type, id, title, page) VALUES (?1, ?2, ?3, ?4, ?5)",
-            (1, "narou", "n1234ab", "Novel 1 dup", 20),
+            "INSERT INTO favorites (user_id, type, id) VALUES (?1, ?2, ?3)",
+            (1, "narou", "n1234ab"),
         );
         assert!(result.is_err());
 
@@ -108,43 +940,51 @@ mod tests {
         )
         .unwrap();
         conn.execute(
-            "INSERT INTO favorites (user_id, type, id, title, page) VALUES (?1, ?2, ?3, ?4, ?5)",
-            (2, "narou", "n1234ab", "Novel 1", 10),
+            "INSERT INTO favorites (user_id, type, id) VALUES (?1, ?2, ?3)",
+            (2, "narou", "n1234ab"),
         )
         .unwrap();
     }
 
+    #[test]
+    fn favorite_requires_novel() {
+        let conn = open_memory();
+        let result = conn.execute(
+            "INSERT INTO favorites (user_id, type, id) VALUES (1, 'narou', 'unknown')",
+            [],
+        );
+        assert!(result.is_err());
+    }
+
     #[test]
     fn read_defaults_to_zero() {
         let conn = open_memory();
+        insert_novel(&conn, "n1", "Novel", 50);
         conn.execute(
-            "INSERT INTO favorites (user_id, type, id, title, page) VALUES (?1, ?2, ?3, ?4, ?5)",
-            (1, "narou", "n1", "Novel", 50),
+            "INSERT INTO favorites (user_id, type, id) VALUES (?1, ?2, ?3)",
+            (1, "narou", "n1"),
         )
         .unwrap();
 
-        let read: i64 = conn
+        let (read, added_at): (i64, String) = conn
             .query_row(
-                "SELECT read FROM favorites WHERE user_id = 1 AND type = ?1 AND id = ?2",
+                "SELECT read, added_at FROM favorites WHERE user_id = 1 AND type = ?1 AND id = ?2",
                 ("narou", "n1"),
-                |row| row.get(0),
+                |row| Ok((row.get(0)?, row.get(1)?)),
             )
             .unwrap();
         assert_eq!(read, 0);
+        assert!(!added_at.is_empty());
     }
 
     #[test]
     fn novelupdated_at_is_nullable() {
         let conn = open_memory();
-        conn.execute(
-            "INSERT INTO favorites (user_id, type, id, title, page) VALUES (?1, ?2, ?3, ?4, ?5)",
-            (1, "narou", "n1", "Novel", 50),
-        )
-        .unwrap();
+        insert_novel(&conn, "n1", "Novel", 50);
 
         let updated: Option<String> = conn
             .query_row(
-                "SELECT novelupdated_at FROM favorites WHERE user_id = 1 AND type = ?1 AND id = ?2",
+                "SELECT novelupdated_at FROM novels WHERE type = ?1 AND id = ?2",
                 ("narou", "n1"),
                 |row| row.get(0),
             )
@@ -157,7 +997,7 @@ mod tests {
         let conn = open_memory();
         let index_exists: bool = conn
             .query_row(
-                "SELECT COUNT(*) > 0 FROM sqlite_master WHERE type = 'index' AND name = 'idx_favorites_updated'",
+                "SELECT COUNT(*) > 0 FROM sqlite_master WHERE type = 'index' AND name = 'idx_novels_updated'",
                 [],
                 |row| row.get(0),
             )
@@ -165,4 +1005,58 @@ mod tests {
         assert!(index_exists);
     }
 
+    #[test]
+    fn migrate_splits_old_favorites_into_novels() {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch(
+            "CREATE TABLE users (id INTEGER PRIMARY KEY AUTOINCREMENT, email TEXT NOT NULL UNIQUE);
+             INSERT INTO users (id, email) VALUES (1, 'guest'), (2, 'alice@example.com');
+             CREATE TABLE favorites (
+                user_id INTEGER NOT NULL DEFAULT 1,
+                type TEXT NOT NULL,
+                id TEXT NOT NULL,
+                title TEXT NOT NULL,
+                novelupdated_at TEXT,
+                page INTEGER NOT NULL,
+                read INTEGER NOT NULL DEFAULT 0,
+                PRIMARY KEY (user_id, type, id)
+             );
+             CREATE INDEX idx_favorites_updated ON favorites (user_id, novelupdated_at DESC);
+             INSERT INTO favorites (user_id, type, id, title, novelupdated_at, page, read) VALUES
+                (1, 'narou', 'n1', 'Stale', '2026-01-01 00:00:00', 10, 3),
+                (2, 'narou', 'n1', 'Fresh', '2026-02-01 00:00:00', 12, 7),
+                (2, 'kakuyomu', 'k1', 'Other', NULL, 5, 5);",
+        )
+        .unwrap();
+        let mut conn = conn;
+        migrate(&mut conn, &MigrationOptions::default()).unwrap();
+        // Running twice must be a no-op
+        migrate(&mut conn, &MigrationOptions::default()).unwrap();
+
+        let novels: i64 = conn
+            .query_row("SELECT COUNT(*) FROM novels", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(novels, 2);
+
+        let (title, page, updated): (String, i64, Option<String>) = conn
+            .query_row(
+                "SELECT title, page, novelupdated_at FROM novels WHERE type = 'narou' AND id = 'n1'",
+                [],
+                |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+            )
+            .unwrap();
+        assert_eq!(title, "Fresh", "the copy with the most pages wins");
+        assert_eq!(page, 12);
+        assert_eq!(updated.as_deref(), Some("2026-02-01 00:00:00"));
+
+        let reads: Vec<(i64, i64)> = conn
+            .prepare("SELECT user_id, read FROM favorites WHERE id = 'n1' ORDER BY user_id")
+            .unwrap()
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))
+            .unwrap()
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert_eq!(reads, vec![(1, 3), (2, 7)], "read positions stay per user");
+        assert!(!has_column(&conn, "favorites", "title").unwrap());
+    }
 }
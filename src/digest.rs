@@ -0,0 +1,528 @@
+use crate::auth::UserId;
+use crate::config::{Config, SmtpConfig, SmtpTls};
+use crate::db::Db;
+use crate::repo::{ChapterUpdatesRepo, DigestEntry, DigestSettingsRepo};
+use crate::state::AppState;
+use crate::sync::NovelUpdate;
+use chrono::{DateTime, Datelike, Duration as ChronoDuration, NaiveDateTime, Utc};
+use lettre::message::{Mailbox, MultiPart};
+use lettre::transport::smtp::authentication::Credentials;
+use lettre::{AsyncSmtpTransport, AsyncTransport, Message, Tokio1Executor};
+use rusqlite::Connection;
+use std::time::Duration;
+
+pub const FREQUENCIES: &[&str] = &["daily", "weekly"];
+const TIME_FORMAT: &str = "%Y-%m-%d %H:%M:%S";
+/// How often the scheduler looks for users whose digest slot has passed
+const CHECK_INTERVAL: Duration = Duration::from_secs(600);
+/// Chapter updates older than this are dropped; comfortably longer than the weekly window.
+const RETENTION_DAYS: i64 = 35;
+
+pub struct Mailer {
+    transport: AsyncSmtpTransport<Tokio1Executor>,
+    from: Mailbox,
+    public_url: Option<String>,
+}
+
+impl Mailer {
+    pub fn new(smtp: &SmtpConfig, public_url: Option<String>) -> Result<Self, String> {
+        let builder = match smtp.tls {
+            SmtpTls::StartTls => AsyncSmtpTransport::<Tokio1Executor>::starttls_relay(&smtp.host)
+                .map_err(|e| e.to_string())?,
+            SmtpTls::Tls => AsyncSmtpTransport::<Tokio1Executor>::relay(&smtp.host)
+                .map_err(|e| e.to_string())?,
+            SmtpTls::None => AsyncSmtpTransport::<Tokio1Executor>::builder_dangerous(&smtp.host),
+        };
+        let mut builder = builder.port(smtp.port);
+        if let Some(username) = &smtp.username {
+            builder = builder.credentials(Credentials::new(
+                username.clone(),
+                smtp.password.clone().unwrap_or_default(),
+            ));
+        }
+        let from = smtp
+            .from
+            .parse()
+            .map_err(|e| format!("invalid SMTP_FROM: {}", e))?;
+        Ok(Self {
+            transport: builder.build(),
+            from,
+            public_url,
+        })
+    }
+}
+
+/// Start the digest scheduler. Does nothing when SMTP is not configured.
+pub fn start(state: AppState) {
+    let Some(mailer) = mailer_from_config(&state.config) else {
+        tracing::info!("[digest] SMTP_HOST not set, email digests disabled");
+        return;
+    };
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(CHECK_INTERVAL);
+        loop {
+            ticker.tick().await;
+            run_due(&state.db, &mailer, Utc::now()).await;
+        }
+    });
+}
+
+fn mailer_from_config(config: &Config) -> Option<Mailer> {
+    let smtp = config.smtp.as_ref()?;
+    match Mailer::new(smtp, config.public_url.clone()) {
+        Ok(m) => Some(m),
+        Err(e) => {
+            tracing::error!("[digest] {}", e);
+            None
+        }
+    }
+}
+
+/// Remember new chapters found by sync so the next digest can list them.
+pub fn record_updates(
+    conn: &Connection,
+    updates: &[NovelUpdate],
+    now: DateTime<Utc>,
+) -> rusqlite::Result<()> {
+    let repo = ChapterUpdatesRepo::new(conn);
+    repo.record(updates, &now.format(TIME_FORMAT).to_string())?;
+    let cutoff = now - ChronoDuration::days(RETENTION_DAYS);
+    repo.prune(&cutoff.format(TIME_FORMAT).to_string())
+}
+
+fn period(frequency: &str) -> ChronoDuration {
+    if frequency == "weekly" {
+        ChronoDuration::weeks(1)
+    } else {
+        ChronoDuration::days(1)
+    }
+}
+
+/// Most recent scheduled send time at or before `now` (hour in UTC, weekday 0 = Monday).
+fn last_slot(now: DateTime<Utc>, frequency: &str, hour: u32, weekday: u32) -> DateTime<Utc> {
+    let mut slot = now
+        .date_naive()
+        .and_hms_opt(hour.min(23), 0, 0)
+        .expect("valid hour")
+        .and_utc();
+    if slot > now {
+        slot -= ChronoDuration::days(1);
+    }
+    if frequency == "weekly" {
+        while slot.weekday().num_days_from_monday() != weekday.min(6) {
+            slot -= ChronoDuration::days(1);
+        }
+    }
+    slot
+}
+
+fn parse_time(s: &str) -> Option<DateTime<Utc>> {
+    NaiveDateTime::parse_from_str(s, TIME_FORMAT)
+        .ok()
+        .map(|t| t.and_utc())
+}
+
+struct DueUser {
+    user_id: i64,
+    email: String,
+    /// Updates detected after this time go into the digest
+    since: String,
+}
+
+fn due_users(conn: &Connection, now: DateTime<Utc>) -> rusqlite::Result<Vec<DueUser>> {
+    let mut due = Vec::new();
+    for schedule in DigestSettingsRepo::new(conn).enabled()? {
+        let slot = last_slot(now, &schedule.frequency, schedule.hour, schedule.weekday);
+        let last_sent = schedule.last_sent_at.as_deref().and_then(parse_time);
+        if last_sent.is_some_and(|t| t >= slot) {
+            continue;
+        }
+        let since = last_sent.unwrap_or(slot - period(&schedule.frequency));
+        due.push(DueUser {
+            user_id: schedule.user_id,
+            email: schedule.email,
+            since: since.format(TIME_FORMAT).to_string(),
+        });
+    }
+    Ok(due)
+}
+
+fn escape_html(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+fn entry_summary(e: &DigestEntry) -> String {
+    format!(
+        "第{}話〜第{}話（未読{}話）",
+        e.from_page + 1,
+        e.page,
+        (e.page - e.read).max(0)
+    )
+}
+
+fn entry_link(e: &DigestEntry, public_url: &str) -> String {
+    let next = (e.read + 1).min(e.page.max(1));
+    format!("{}/novel/{}/{}/{}", public_url, e.type_str, e.id, next)
+}
+
+/// Plain-text and HTML bodies listing each updated novel.
+fn render_bodies(entries: &[DigestEntry], public_url: Option<&str>) -> (String, String) {
+    let mut text = String::from("お気に入り小説に新しい話が追加されました。\n\n");
+    let mut html = String::from("<p>お気に入り小説に新しい話が追加されました。</p>\n<ul>\n");
+    for e in entries {
+        text.push_str(&format!("■ {}\n  {}\n", e.title, entry_summary(e)));
+        let title = escape_html(&e.title);
+        match public_url {
+            Some(base) => {
+                let link = entry_link(e, base);
+                text.push_str(&format!("  {}\n", link));
+                html.push_str(&format!(
+                    "<li><a href=\"{}\">{}</a><br>{}</li>\n",
+                    escape_html(&link),
+                    title,
+                    entry_summary(e)
+                ));
+            }
+            None => html.push_str(&format!("<li>{}<br>{}</li>\n", title, entry_summary(e))),
+        }
+        text.push('\n');
+    }
+    html.push_str("</ul>\n");
+    (text, html)
+}
+
+fn build_message(
+    from: &Mailbox,
+    to: &str,
+    entries: &[DigestEntry],
+    public_url: Option<&str>,
+) -> Result<Message, String> {
+    let to: Mailbox = to.parse().map_err(|e| format!("invalid address: {}", e))?;
+    let (text, html) = render_bodies(entries, public_url);
+    Message::builder()
+        .from(from.clone())
+        .to(to)
+        .subject(format!("お気に入りの更新（{}作品）", entries.len()))
+        .multipart(MultiPart::alternative_plain_html(text, html))
+        .map_err(|e| e.to_string())
+}
+
+/// Send every digest whose slot has passed. A user with nothing new gets no email, but their
+/// window still advances. Failed sends keep `last_sent_at` so the next check retries; a
+/// message that can't be built (an unusable address) disables the digest instead.
+pub async fn run_due(db: &Db, mailer: &Mailer, now: DateTime<Utc>) {
+    let due = db
+        .read(move |conn| {
+            due_users(conn, now).and_then(|users| {
+                users
+                    .into_iter()
+                    .map(|u| {
+                        ChapterUpdatesRepo::new(conn)
+                            .digest_entries(u.user_id, &u.since)
+                            .map(|e| (u, e))
+                    })
+                    .collect::<rusqlite::Result<Vec<_>>>()
+            })
+        })
+        .await;
+    let due = match due {
+        Ok(d) => d,
+        Err(e) => {
+            tracing::error!("[digest] db error: {}", e);
+            return;
+        }
+    };
+
+    let sent_at = now.format(TIME_FORMAT).to_string();
+    for (user, entries) in due {
+        if !entries.is_empty() {
+            let message = match build_message(
+                &mailer.from,
+                &user.email,
+                &entries,
+                mailer.public_url.as_deref(),
+            ) {
+                Ok(m) => m,
+                Err(e) => {
+                    // Retrying can't fix a bad address, so stop instead of failing every check
+                    tracing::warn!("[digest] user {}: {}, disabling digest", user.user_id, e);
+                    let user_id = UserId(user.user_id);
+                    if let Err(e) = db
+                        .write(move |conn| DigestSettingsRepo::new(conn).disable(user_id))
+                        .await
+                    {
+                        tracing::error!("[digest] failed to disable digest: {}", e);
+                    }
+                    continue;
+                }
+            };
+            if let Err(e) = mailer.transport.send(message).await {
+                tracing::error!("[digest] send to user {} failed: {}", user.user_id, e);
+                continue;
+            }
+            tracing::info!(
+                "[digest] sent {} updates to user {}",
+                entries.len(),
+                user.user_id
+            );
+        }
+        let sent_at = sent_at.clone();
+        let user_id = UserId(user.user_id);
+        if let Err(e) = db
+            .write(move |conn| DigestSettingsRepo::new(conn).mark_sent(user_id, &sent_at))
+            .await
+        {
+            tracing::error!("[digest] failed to record send: {}", e);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+    use std::sync::{Arc, Mutex};
+    use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+
+    fn at(s: &str) -> DateTime<Utc> {
+        parse_time(s).unwrap()
+    }
+
+    /// Minimal SMTP server that accepts everything and keeps each message's DATA.
+    async fn spawn_smtp_sink() -> (u16, Arc<Mutex<Vec<String>>>) {
+        let messages = Arc::new(Mutex::new(Vec::new()));
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let store = messages.clone();
+        tokio::spawn(async move {
+            loop {
+                let (socket, _) = listener.accept().await.unwrap();
+                let store = store.clone();
+                tokio::spawn(async move {
+                    let (read, mut write) = socket.into_split();
+                    let mut lines = BufReader::new(read).lines();
+                    write.write_all(b"220 sink ESMTP\r\n").await.unwrap();
+                    while let Ok(Some(line)) = lines.next_line().await {
+                        let command = line.to_ascii_uppercase();
+                        let reply: &[u8] =
+                            if command.starts_with("EHLO") || command.starts_with("HELO") {
+                                b"250 sink\r\n"
+                            } else if command.starts_with("DATA") {
+                                write.write_all(b"354 go ahead\r\n").await.unwrap();
+                                let mut data = String::new();
+                                while let Ok(Some(l)) = lines.next_line().await {
+                                    if l == "." {
+                                        break;
+                                    }
+                                    data.push_str(&l);
+                                    data.push('\n');
+                                }
+                                store.lock().unwrap().push(data);
+                                b"250 queued\r\n"
+                            } else if command.starts_with("QUIT") {
+                                write.write_all(b"221 bye\r\n").await.unwrap();
+                                break;
+                            } else {
+                                b"250 OK\r\n"
+                            };
+                        write.write_all(reply).await.unwrap();
+                    }
+                });
+            }
+        });
+        (port, messages)
+    }
+
+    fn sink_mailer(port: u16) -> Mailer {
+        let smtp = SmtpConfig {
+            host: "127.0.0.1".into(),
+            port,
+            tls: SmtpTls::None,
+            username: None,
+            password: None,
+            from: "Novel Server <novels@example.com>".into(),
+        };
+        Mailer::new(&smtp, Some("https://novels.example.com".into())).unwrap()
+    }
+
+    fn seed(conn: &Connection) {
+        conn.execute_batch(
+            "INSERT INTO users (id, email) VALUES (2, 'alice@example.com'), (3, 'bob@example.com');
+             INSERT INTO novels (type, id, title, page) VALUES
+                ('narou', 'n1', 'Novel <One>', 12), ('narou', 'n2', 'Other', 5);
+             INSERT INTO favorites (user_id, type, id, read) VALUES
+                (2, 'narou', 'n1', 9), (3, 'narou', 'n2', 0);
+             INSERT INTO chapter_updates (type, id, old_page, new_page, detected_at) VALUES
+                ('narou', 'n1', 8, 10, '2026-03-13 12:00:00'),
+                ('narou', 'n1', 10, 12, '2026-03-13 18:00:00'),
+                ('narou', 'n2', 4, 5, '2026-03-13 18:00:00'),
+                ('narou', 'n1', 7, 8, '2026-03-10 00:00:00');",
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn last_slot_daily_and_weekly() {
+        let now = at("2026-03-14 08:30:00"); // Saturday
+        assert_eq!(last_slot(now, "daily", 7, 0), at("2026-03-14 07:00:00"));
+        assert_eq!(last_slot(now, "daily", 9, 0), at("2026-03-13 09:00:00"));
+        // weekday 0 = Monday
+        assert_eq!(last_slot(now, "weekly", 7, 0), at("2026-03-09 07:00:00"));
+        assert_eq!(last_slot(now, "weekly", 7, 5), at("2026-03-14 07:00:00"));
+        assert_eq!(last_slot(now, "weekly", 9, 5), at("2026-03-07 09:00:00"));
+    }
+
+    #[test]
+    fn due_users_respects_last_sent_and_schedule() {
+        let conn = crate::db::open_memory();
+        seed(&conn);
+        conn.execute_batch(
+            "INSERT INTO digest_settings (user_id, enabled, frequency, hour, last_sent_at) VALUES
+                (2, 1, 'daily', 0, '2026-03-13 00:00:05'),
+                (3, 1, 'daily', 0, '2026-03-14 00:00:05');",
+        )
+        .unwrap();
+        let due = due_users(&conn, at("2026-03-14 01:00:00")).unwrap();
+        assert_eq!(due.len(), 1);
+        assert_eq!(due[0].user_id, 2);
+        assert_eq!(due[0].since, "2026-03-13 00:00:05");
+
+        conn.execute(
+            "UPDATE digest_settings SET enabled = 0 WHERE user_id = 2",
+            [],
+        )
+        .unwrap();
+        assert!(due_users(&conn, at("2026-03-14 01:00:00"))
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn entry_summary_and_link_point_at_next_unread() {
+        let entry = DigestEntry {
+            type_str: "narou".into(),
+            id: "n1".into(),
+            title: "Novel".into(),
+            from_page: 8,
+            page: 12,
+            read: 9,
+        };
+        assert_eq!(entry_summary(&entry), "第9話〜第12話（未読3話）");
+        assert_eq!(
+            entry_link(&entry, "https://x"),
+            "https://x/novel/narou/n1/10"
+        );
+    }
+
+    #[test]
+    fn record_updates_prunes_old_rows() {
+        let conn = crate::db::open_memory();
+        seed(&conn);
+        let now = Utc.with_ymd_and_hms(2026, 4, 20, 0, 0, 0).unwrap();
+        let update = NovelUpdate {
+            type_str: "narou".into(),
+            id: "n2".into(),
+            title: "Other".into(),
+            old_page: 5,
+            new_page: 6,
+        };
+        record_updates(&conn, &[update], now).unwrap();
+        let count: i64 = conn
+            .query_row("SELECT COUNT(*) FROM chapter_updates", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(count, 1);
+    }
+
+    #[tokio::test]
+    async fn run_due_sends_digest_to_smtp_sink_once() {
+        let conn = crate::db::open_memory();
+        seed(&conn);
+        conn.execute(
+            "INSERT INTO digest_settings (user_id, enabled, frequency, hour, last_sent_at)
+             VALUES (2, 1, 'daily', 0, '2026-03-13 00:00:00')",
+            [],
+        )
+        .unwrap();
+        let db = Db::from_connection(conn);
+        let (port, messages) = spawn_smtp_sink().await;
+        let mailer = sink_mailer(port);
+        let now = at("2026-03-14 00:05:00");
+
+        run_due(&db, &mailer, now).await;
+        {
+            let messages = messages.lock().unwrap();
+            assert_eq!(messages.len(), 1);
+            let data = &messages[0];
+            assert!(data.contains("To: alice@example.com"));
+            assert!(data.contains("multipart/alternative"));
+            assert!(data.contains("text/plain"));
+            assert!(data.contains("text/html"));
+        }
+        let last_sent = db
+            .read(|conn| DigestSettingsRepo::new(conn).get(UserId(2)))
+            .await
+            .unwrap()
+            .last_sent_at
+            .unwrap();
+        assert_eq!(last_sent, "2026-03-14 00:05:00");
+
+        // Same slot: nothing more to send
+        run_due(&db, &mailer, at("2026-03-14 00:15:00")).await;
+        assert_eq!(messages.lock().unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn run_due_disables_digest_for_unusable_address() {
+        let conn = crate::db::open_memory();
+        seed(&conn);
+        conn.execute_batch(
+            "UPDATE users SET email = 'not an address' WHERE id = 2;
+             INSERT INTO digest_settings (user_id, enabled, frequency, hour, last_sent_at)
+             VALUES (2, 1, 'daily', 0, '2026-03-13 00:00:00');",
+        )
+        .unwrap();
+        let db = Db::from_connection(conn);
+        let (port, messages) = spawn_smtp_sink().await;
+        let mailer = sink_mailer(port);
+
+        run_due(&db, &mailer, at("2026-03-14 00:05:00")).await;
+        assert!(messages.lock().unwrap().is_empty());
+        let settings = db
+            .read(|conn| DigestSettingsRepo::new(conn).get(UserId(2)))
+            .await
+            .unwrap();
+        assert!(!settings.enabled);
+    }
+
+    #[test]
+    fn render_bodies_escapes_html_and_links_when_public_url_set() {
+        let entries = vec![DigestEntry {
+            type_str: "narou".into(),
+            id: "n1".into(),
+            title: "A <b>&</b>".into(),
+            from_page: 1,
+            page: 2,
+            read: 1,
+        }];
+        let (text, html) = render_bodies(&entries, Some("https://x"));
+        assert!(text.contains("■ A <b>&</b>"));
+        assert!(text.contains("https://x/novel/narou/n1/2"));
+        assert!(
+            html.contains("<a href=\"https://x/novel/narou/n1/2\">A &lt;b&gt;&amp;&lt;/b&gt;</a>")
+        );
+
+        let (text, html) = render_bodies(&entries, None);
+        assert!(!text.contains("/novel/"));
+        assert!(!html.contains("<a "));
+    }
+
+    #[test]
+    fn build_message_rejects_non_address_emails() {
+        let from: Mailbox = "novels@example.com".parse().unwrap();
+        assert!(build_message(&from, "alice@example.com", &[], None).is_ok());
+        assert!(build_message(&from, "guest", &[], None).is_err());
+    }
+}
@@ -2,18 +2,22 @@ mod auth;
 mod cache;
 mod config;
 mod db;
+mod digest;
 mod error;
 mod modules;
 mod openapi;
+mod push;
+mod repo;
 mod routes;
 mod sanitize;
 mod spa;
 mod state;
 mod sync;
+mod webhook;
 
 use config::Config;
 use state::AppState;
-use std::sync::{Arc, Mutex};
+use std::sync::Arc;
 
 #[tokio::main]
 async fn main() {
@@ -22,7 +26,12 @@ async fn main() {
     tracing_subscriber::fmt::init();
 
     let config = Config::from_env();
-    let conn = db::open(&config.db_path);
+    let db = db::Db::open(
+        &config.db_path,
+        &db::MigrationOptions {
+            owner_email: config.migration_owner_email.clone(),
+        },
+    );
     let cache = Arc::new(cache::Cache::new());
     let http = reqwest::Client::builder()
         .user_agent("Mozilla/5.0 (X11; Linux x86_64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/120.0.0.0 Safari/537.36")
@@ -30,7 +39,7 @@ async fn main() {
         .expect("Failed to build HTTP client");
 
     let state = AppState {
-        db: Arc::new(Mutex::new(conn)),
+        db,
         cache: cache.clone(),
         config: config.clone(),
         http,
@@ -38,6 +47,7 @@ async fn main() {
 
     cache::start_sweep(cache);
     sync::start_sync(state.clone());
+    digest::start(state.clone());
 
     let app = routes::build_router(state);
 
@@ -1,3 +1,4 @@
+use super::BulkData;
 use crate::error::AppError;
 use chrono::{DateTime, Utc};
 use scraper::{Html, Selector};
@@ -15,6 +16,12 @@ const RANKING_GENRES: &[(&str, &str)] = &[
     ("ホラー", "horror"),
 ];
 
+/// Validate a work id (all digits) taken from a URL path.
+pub fn parse_work_id(segment: &str) -> Option<String> {
+    (!segment.is_empty() && segment.chars().all(|c| c.is_ascii_digit()))
+        .then(|| segment.to_string())
+}
+
 fn parse_apollo_state(html: &str) -> Result<Value, AppError> {
     let doc = Html::parse_document(html);
     let sel =
@@ -207,6 +214,12 @@ pub async fn fetch_search(client: &reqwest::Client, word: &str) -> Result<Value,
 async fn fetch_work(client: &reqwest::Client, id: &str) -> Result<Value, AppError> {
     let url = format!("https://kakuyomu.jp/works/{}", id);
     let res = client.get(&url).send().await?;
+    if res.status() == reqwest::StatusCode::NOT_FOUND {
+        return Err(AppError::NotFound(format!(
+            "kakuyomu work {} not found",
+            id
+        )));
+    }
     if !res.status().is_success() {
         return Err(AppError::Upstream(format!(
             "kakuyomu work error: {}",
@@ -269,13 +282,39 @@ pub async fn fetch_datum(client: &reqwest::Client, id: &str) -> Result<Value, Ap
     Ok(Value::Object(result))
 }
 
-pub async fn fetch_data(client: &reqwest::Client, ids: &[String]) -> Result<Vec<Value>, AppError> {
-    let mut results = Vec::new();
+pub async fn fetch_data(client: &reqwest::Client, ids: &[String]) -> Result<BulkData, AppError> {
+    let mut bulk = BulkData::default();
     for id in ids {
-        results.push(fetch_datum(client, id).await?);
+        match fetch_datum(client, id).await {
+            Ok(datum) => bulk.data.push(datum),
+            Err(AppError::NotFound(_)) => bulk.missing.push(id.clone()),
+            Err(e) => return Err(e),
+        }
         tokio::time::sleep(std::time::Duration::from_millis(500)).await;
     }
-    Ok(results)
+    Ok(bulk)
+}
+
+/// Page ids below this are sequential chapter numbers; anything larger is an episode id.
+const MAX_PAGE_NUMBER: u64 = 100_000;
+
+async fn fetch_episodes(client: &reqwest::Client, id: &str) -> Result<Vec<EpisodeInfo>, AppError> {
+    let apollo = fetch_work(client, id).await?;
+    Ok(extract_episodes(&apollo, id))
+}
+
+/// Sequential chapter number of an episode id, or `None` if the work doesn't list it.
+pub async fn episode_number(
+    client: &reqwest::Client,
+    id: &str,
+    episode_id: &str,
+) -> Result<Option<u64>, AppError> {
+    let episodes = fetch_episodes(client, id).await?;
+    Ok(find_episode(&episodes, episode_id))
+}
+
+fn find_episode(episodes: &[EpisodeInfo], episode_id: &str) -> Option<u64> {
+    episodes.iter().find(|e| e.id == episode_id).map(|e| e.num)
 }
 
 pub async fn fetch_page(
@@ -287,9 +326,8 @@ pub async fn fetch_page(
 
     // Small numbers are sequential page numbers that need resolution
     if let Ok(num) = page_id.parse::<u64>() {
-        if num < 100_000 {
-            let apollo = fetch_work(client, id).await?;
-            let episodes = extract_episodes(&apollo, id);
+        if num < MAX_PAGE_NUMBER {
+            let episodes = fetch_episodes(client, id).await?;
             let ep = episodes
                 .get((num as usize).wrapping_sub(1))
                 .ok_or_else(|| AppError::Upstream(format!("Episode {} not found", page_id)))?;
@@ -427,6 +465,20 @@ mod tests {
         assert_eq!(episodes[1].id, "ep2");
     }
 
+    #[test]
+    fn find_episode_maps_id_to_number() {
+        let apollo = json!({
+            "TableOfContentsChapter:ch1": {
+                "episodeUnions": [{"__ref": "Episode:ep1"}, {"__ref": "Episode:ep2"}]
+            },
+            "Episode:ep1": {"id": "111", "title": "Ep 1"},
+            "Episode:ep2": {"id": "222", "title": "Ep 2"}
+        });
+        let episodes = extract_episodes(&apollo, "abc");
+        assert_eq!(find_episode(&episodes, "222"), Some(2));
+        assert_eq!(find_episode(&episodes, "333"), None);
+    }
+
     #[test]
     fn extract_episodes_sorted_by_chapter_key() {
         let apollo = json!({
@@ -4,9 +4,79 @@ pub mod syosetu;
 use crate::error::AppError;
 use serde_json::Value;
 
+/// Result of a bulk metadata fetch.
+#[derive(Debug, Default)]
+pub struct BulkData {
+    /// Datum for every novel the site returned
+    pub data: Vec<Value>,
+    /// Requested ids the site did not return (deleted, hidden or unknown)
+    pub missing: Vec<String>,
+}
+
+/// Where a site URL points within a novel.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NovelLocation {
+    pub module: ModuleType,
+    pub id: String,
+    /// Position of the chapter, when the URL names one by number
+    pub page: Option<u64>,
+    /// Kakuyomu episode id, which needs the work's TOC to become a `page`
+    pub episode_id: Option<String>,
+}
+
+/// Parse a work, chapter or TOC page URL on one of the supported sites.
+/// A chapter part that can't be read leaves `page`/`episode_id` empty.
+pub fn parse_location(url: &str) -> Option<NovelLocation> {
+    let url = reqwest::Url::parse(url.trim()).ok()?;
+    if !matches!(url.scheme(), "http" | "https") {
+        return None;
+    }
+    let host = url.host_str()?;
+    let host = host.strip_prefix("www.").unwrap_or(host);
+    let mut segments = url.path_segments()?.filter(|s| !s.is_empty());
+    let (module, id, page, episode_id) = match host {
+        "ncode.syosetu.com" | "novel18.syosetu.com" => {
+            let module = if host == "ncode.syosetu.com" {
+                ModuleType::Narou
+            } else {
+                ModuleType::Nocturne
+            };
+            let id = syosetu::parse_ncode(segments.next()?)?;
+            // A trailing segment that isn't a chapter number still names the novel
+            let page = match segments.next() {
+                Some(num) => num.parse().ok().filter(|n| *n > 0),
+                None => url
+                    .query_pairs()
+                    .find(|(k, _)| k == "p")
+                    .and_then(|(_, p)| p.parse().ok())
+                    .and_then(syosetu::first_page_of_toc_page),
+            };
+            (module, id, page, None)
+        }
+        "kakuyomu.jp" => {
+            if segments.next()? != "works" {
+                return None;
+            }
+            let id = kakuyomu::parse_work_id(segments.next()?)?;
+            let episode_id = match (segments.next(), segments.next()) {
+                (Some("episodes"), Some(ep)) => kakuyomu::parse_work_id(ep),
+                _ => None,
+            };
+            (ModuleType::Kakuyomu, id, None, episode_id)
+        }
+        _ => return None,
+    };
+    Some(NovelLocation {
+        module,
+        id,
+        page,
+        episode_id,
+    })
+}
+
 /// Site type enum dispatch — simpler and more type-safe than trait objects.
 /// Each method's match arm delegates to a site-specific module.
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum ModuleType {
     Narou,
     Nocturne,
@@ -31,6 +101,36 @@ impl ModuleType {
         }
     }
 
+    /// Resolve a novel URL on one of the supported sites to its type and id.
+    /// Chapter URLs and trailing paths are accepted; anything else yields `None`.
+    pub fn parse_url(url: &str) -> Option<(Self, String)> {
+        parse_location(url).map(|loc| (loc.module, loc.id))
+    }
+
+    /// Novel top page on the source site.
+    pub fn site_url(&self, id: &str) -> String {
+        match self {
+            Self::Narou => format!("{}/{}/", syosetu::NAROU.base_url, id),
+            Self::Nocturne => format!("{}/{}/", syosetu::NOCTURNE.base_url, id),
+            Self::Kakuyomu => format!("https://kakuyomu.jp/works/{}", id),
+        }
+    }
+
+    /// Chapter number for a location, looking up Kakuyomu episode ids upstream.
+    /// `Ok(None)` when the URL names no chapter or the episode isn't in the work.
+    pub async fn locate_page(
+        &self,
+        client: &reqwest::Client,
+        location: &NovelLocation,
+    ) -> Result<Option<u64>, AppError> {
+        match (self, &location.episode_id) {
+            (Self::Kakuyomu, Some(episode_id)) => {
+                kakuyomu::episode_number(client, &location.id, episode_id).await
+            }
+            _ => Ok(location.page),
+        }
+    }
+
     pub async fn fetch_ranking_list(
         &self,
         client: &reqwest::Client,
@@ -97,7 +197,7 @@ impl ModuleType {
         &self,
         client: &reqwest::Client,
         ids: &[String],
-    ) -> Result<Vec<Value>, AppError> {
+    ) -> Result<BulkData, AppError> {
         match self {
             Self::Narou => syosetu::fetch_data(&syosetu::NAROU, client, ids).await,
             Self::Nocturne => syosetu::fetch_data(&syosetu::NOCTURNE, client, ids).await,
@@ -113,3 +213,108 @@ impl ModuleType {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_url_recognises_each_site() {
+        assert_eq!(
+            ModuleType::parse_url("https://ncode.syosetu.com/n1234AB/"),
+            Some((ModuleType::Narou, "n1234ab".into()))
+        );
+        assert_eq!(
+            ModuleType::parse_url("http://ncode.syosetu.com/n1234ab/15/"),
+            Some((ModuleType::Narou, "n1234ab".into()))
+        );
+        assert_eq!(
+            ModuleType::parse_url("https://ncode.syosetu.com/n1234ab/0/"),
+            Some((ModuleType::Narou, "n1234ab".into()))
+        );
+        assert_eq!(
+            ModuleType::parse_url("https://ncode.syosetu.com/n1234ab/abc/"),
+            Some((ModuleType::Narou, "n1234ab".into()))
+        );
+        assert_eq!(
+            ModuleType::parse_url("https://novel18.syosetu.com/n9876zz"),
+            Some((ModuleType::Nocturne, "n9876zz".into()))
+        );
+        assert_eq!(
+            ModuleType::parse_url(
+                "https://kakuyomu.jp/works/1177354054881234567/episodes/1177354054881234999"
+            ),
+            Some((ModuleType::Kakuyomu, "1177354054881234567".into()))
+        );
+        assert_eq!(
+            ModuleType::parse_url("https://kakuyomu.jp/works/1177354054881234567/episodes/x"),
+            Some((ModuleType::Kakuyomu, "1177354054881234567".into()))
+        );
+    }
+
+    #[test]
+    fn parse_url_rejects_other_urls() {
+        for url in [
+            "https://ncode.syosetu.com/",
+            "https://ncode.syosetu.com/novelview/infotop/ncode/n1234ab/",
+            "https://kakuyomu.jp/users/someone",
+            "https://kakuyomu.jp/works/abc",
+            "https://example.com/n1234ab/",
+            "ftp://ncode.syosetu.com/n1234ab/",
+            "not a url",
+        ] {
+            assert_eq!(ModuleType::parse_url(url), None, "{}", url);
+        }
+    }
+
+    #[test]
+    fn parse_location_finds_chapters() {
+        let page = |url: &str| parse_location(url).and_then(|l| l.page);
+        assert_eq!(page("https://ncode.syosetu.com/n1234ab/"), None);
+        assert_eq!(page("https://ncode.syosetu.com/n1234ab/15/"), Some(15));
+        assert_eq!(page("https://novel18.syosetu.com/n1234ab/?p=1"), Some(1));
+        assert_eq!(page("https://ncode.syosetu.com/n1234ab/?p=3"), Some(201));
+        assert_eq!(page("https://ncode.syosetu.com/n1234ab/?p=x"), None);
+        assert_eq!(page("https://ncode.syosetu.com/n1234ab/0/"), None);
+        assert_eq!(page("https://ncode.syosetu.com/n1234ab/abc/"), None);
+        assert_eq!(
+            parse_location("https://ncode.syosetu.com/n1234ab/abc/")
+                .unwrap()
+                .id,
+            "n1234ab"
+        );
+
+        let loc =
+            parse_location("https://kakuyomu.jp/works/1177354054881234567/episodes/16816452219")
+                .unwrap();
+        assert_eq!(loc.module, ModuleType::Kakuyomu);
+        assert_eq!(loc.page, None);
+        assert_eq!(loc.episode_id.as_deref(), Some("16816452219"));
+        assert_eq!(
+            parse_location("https://kakuyomu.jp/works/1177354054881234567")
+                .unwrap()
+                .episode_id,
+            None
+        );
+        assert_eq!(
+            parse_location("https://kakuyomu.jp/works/1177354054881234567/episodes/abc")
+                .unwrap()
+                .episode_id,
+            None
+        );
+    }
+
+    #[test]
+    fn site_url_round_trips_through_parse_url() {
+        for (module, id) in [
+            (ModuleType::Narou, "n1234ab"),
+            (ModuleType::Nocturne, "n5678cd"),
+            (ModuleType::Kakuyomu, "1177354054881234567"),
+        ] {
+            assert_eq!(
+                ModuleType::parse_url(&module.site_url(id)),
+                Some((module, id.to_string()))
+            );
+        }
+    }
+}
@@ -1,7 +1,10 @@
+use super::BulkData;
 use crate::error::AppError;
 use reqwest::header::{HeaderMap, HeaderValue, COOKIE};
 use scraper::{Html, Selector};
 use serde_json::{json, Map, Value};
+use std::sync::Arc;
+use tokio::sync::Semaphore;
 
 /// Normalize a single API response item: ncode->id, title->trim, general_all_no->page
 pub fn map_item(obj: &Value) -> Value {
@@ -218,6 +221,31 @@ const OF_DATUM: &str = "n-t-ga-s-nu";
 /// Output fields for detail (title, story, general_all_no)
 const OF_DETAIL: &str = "t-s-ga";
 
+/// ncodes per bulk request. The API caps `lim` at 500, but 500 ncodes also push
+/// the query string past common URL length limits, so stay well below both.
+const BULK_CHUNK_SIZE: usize = 100;
+/// Bulk requests in flight at once
+const BULK_CONCURRENCY: usize = 3;
+
+/// Episodes listed per TOC page (`?p=N`)
+const TOC_PAGE_SIZE: u64 = 100;
+
+/// First episode listed on TOC page `p` (1-based).
+pub fn first_page_of_toc_page(p: u64) -> Option<u64> {
+    p.checked_sub(1)?.checked_mul(TOC_PAGE_SIZE)?.checked_add(1)
+}
+
+/// Validate an ncode (`n` + digits + letters) taken from a URL path, lowercased.
+pub fn parse_ncode(segment: &str) -> Option<String> {
+    let ncode = segment.to_ascii_lowercase();
+    let rest = ncode.strip_prefix('n')?;
+    let letters = rest.trim_start_matches(|c: char| c.is_ascii_digit());
+    let valid = letters.len() < rest.len()
+        && !letters.is_empty()
+        && letters.chars().all(|c| c.is_ascii_lowercase());
+    valid.then_some(ncode)
+}
+
 fn with_headers(site: &SyosetuSite, req: reqwest::RequestBuilder) -> reqwest::RequestBuilder {
     if site.over18 {
         let mut headers = HeaderMap::new();
@@ -368,30 +396,80 @@ pub async fn fetch_datum(
     .await?;
     data.first()
         .map(|d| to_datum(site, d))
-        .ok_or_else(|| AppError::Upstream("Novel not found".to_string()))
+        .ok_or_else(|| AppError::NotFound("Novel not found".to_string()))
 }
 
+/// Fetch metadata for many novels, split into API-sized chunks.
+///
+/// A failed chunk is logged and its ids are neither returned nor reported as
+/// missing, so a transient error is never mistaken for a deleted novel.
+/// Only when every chunk fails is the error returned.
 pub async fn fetch_data(
-    site: &SyosetuSite,
+    site: &'static SyosetuSite,
     client: &reqwest::Client,
     ids: &[String],
-) -> Result<Vec<Value>, AppError> {
-    let mut all = Vec::new();
-    for chunk in ids.chunks(500) {
-        let ncode_str = chunk.join("-");
-        let data = site_api(
-            site,
-            client,
-            &[
+) -> Result<BulkData, AppError> {
+    let semaphore = Arc::new(Semaphore::new(BULK_CONCURRENCY));
+    let mut handles = Vec::new();
+    for chunk in ids.chunks(BULK_CHUNK_SIZE) {
+        let chunk = chunk.to_vec();
+        let client = client.clone();
+        let semaphore = semaphore.clone();
+        handles.push(tokio::spawn(async move {
+            let _permit = semaphore.acquire_owned().await;
+            let params = [
                 ("of", OF_DATUM.to_string()),
-                ("ncode", ncode_str),
+                ("ncode", chunk.join("-")),
                 ("lim", chunk.len().to_string()),
-            ],
-        )
-        .await?;
-        all.extend(data.iter().map(|d| to_datum(site, d)));
+            ];
+            let res = site_api(site, &client, &params).await;
+            (chunk, res)
+        }));
+    }
+
+    let mut bulk = BulkData::default();
+    let mut last_err = None;
+    let mut succeeded = 0usize;
+    for handle in handles {
+        let (chunk, res) = handle
+            .await
+            .map_err(|e| AppError::Internal(e.to_string()))?;
+        match res {
+            Ok(data) => {
+                succeeded += 1;
+                bulk.missing.extend(missing_ids(&chunk, &data));
+                bulk.data.extend(data.iter().map(|d| to_datum(site, d)));
+            }
+            Err(e) => {
+                tracing::error!(
+                    "{} bulk fetch of {} ids failed: {}",
+                    site.type_str,
+                    chunk.len(),
+                    e
+                );
+                last_err = Some(e);
+            }
+        }
+    }
+    match last_err {
+        Some(e) if succeeded == 0 => Err(e),
+        _ => Ok(bulk),
     }
-    Ok(all)
+}
+
+/// Ids that were requested but absent from the API response.
+/// The API returns ncodes in upper case; `map_item` lowercases them.
+fn missing_ids(requested: &[String], data: &[Value]) -> Vec<String> {
+    let returned: std::collections::HashSet<String> = data
+        .iter()
+        .filter_map(|d| d["id"].as_str())
+        .map(|id| id.to_lowercase())
+        .collect();
+    requested
+        .iter()
+        .filter(|id| !returned.contains(&id.to_lowercase()))
+        .cloned()
+        .collect()
 }
 
 pub async fn fetch_detail(
@@ -627,6 +705,36 @@ mod tests {
         );
     }
 
+    // ── Bulk fetch chunking ──
+
+    #[test]
+    fn missing_ids_reports_unreturned_ncodes() {
+        let requested = vec![
+            "n1111aa".to_string(),
+            "n2222bb".to_string(),
+            "n3333cc".to_string(),
+        ];
+        let data = process_api_response(vec![
+            json!({"allcount": 2}),
+            json!({"ncode": "N1111AA", "title": "One", "general_all_no": 1}),
+            json!({"ncode": "N3333CC", "title": "Three", "general_all_no": 3}),
+        ]);
+        assert_eq!(missing_ids(&requested, &data), vec!["n2222bb".to_string()]);
+    }
+
+    #[test]
+    fn missing_ids_is_case_insensitive() {
+        let requested = vec!["N1111AA".to_string()];
+        let data = vec![json!({"id": "n1111aa"})];
+        assert!(missing_ids(&requested, &data).is_empty());
+    }
+
+    #[test]
+    fn missing_ids_all_missing_on_empty_response() {
+        let requested = vec!["n1".to_string(), "n2".to_string()];
+        assert_eq!(missing_ids(&requested, &[]), requested);
+    }
+
     #[test]
     fn of_detail_uses_hyphens() {
         assert!(
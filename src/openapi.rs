@@ -86,6 +86,115 @@ pub struct Favorite {
     pub page: i64,
     /// 既読ページ番号（0 = 未読）
     pub read: i64,
+    /// 掲載状態（active = 掲載中, gone = 掲載元で削除・非公開）
+    pub status: String,
+    /// 掲載元からの消失を検知した日付（YYYY-MM-DD、掲載中は null）
+    pub gone_at: Option<String>,
+    /// お気に入りに登録した日時
+    pub added_at: String,
+    /// 章内の読書位置（未保存の場合は null）
+    pub position: Option<ReadingPosition>,
+    /// 既読位置を最後に更新した日時（UTC、ミリ秒まで）
+    pub progress_at: Option<String>,
+    /// 手動の並び順（0始まり、未設定の場合は null）
+    pub sort_order: Option<i64>,
+    /// タグ名（名前順）
+    pub tags: Vec<String>,
+    /// 自分だけのメモ
+    pub notes: Option<String>,
+    /// 評価（1〜5）
+    pub rating: Option<i64>,
+}
+
+/// お気に入り一覧（ページング形式）
+#[derive(Serialize, ToSchema)]
+pub struct FavoritesPage {
+    /// このページのお気に入り
+    pub favorites: Vec<Favorite>,
+    /// 次のページを取得する `cursor` の値（最後のページでは null）
+    pub next_cursor: Option<String>,
+    /// 絞り込み条件に一致する総件数
+    pub total: i64,
+}
+
+/// お気に入りのメモ・評価・タグ
+#[derive(Serialize, ToSchema)]
+pub struct Notes {
+    /// メモ（10000文字まで）
+    pub notes: Option<String>,
+    /// 評価（1〜5）
+    pub rating: Option<i64>,
+    /// タグ名
+    pub tags: Vec<String>,
+}
+
+/// タグ
+#[derive(Serialize, ToSchema)]
+pub struct Tag {
+    /// タグID
+    pub id: i64,
+    /// タグ名
+    pub name: String,
+    /// タグが付いたお気に入りの数
+    pub count: i64,
+}
+
+/// タグ作成・名前変更リクエスト
+#[derive(Serialize, ToSchema)]
+pub struct TagRequest {
+    /// タグ名（前後の空白は除去、50文字まで）
+    pub name: String,
+}
+
+/// お気に入りのタグ設定リクエスト
+#[derive(Serialize, ToSchema)]
+pub struct FavoriteTagsRequest {
+    /// タグ名の配列（重複は無視）
+    pub tags: Vec<String>,
+}
+
+/// 並び順の1項目
+#[derive(Serialize, ToSchema)]
+pub struct OrderEntry {
+    /// サイト種別
+    #[serde(rename = "type")]
+    pub type_str: String,
+    /// 小説ID
+    pub id: String,
+}
+
+/// 並び順保存リクエスト
+#[derive(Serialize, ToSchema)]
+pub struct OrderRequest {
+    /// 先頭からの並び順
+    pub favorites: Vec<OrderEntry>,
+}
+
+/// 並び順保存結果
+#[derive(Serialize, ToSchema)]
+pub struct OrderResponse {
+    /// 並び順が保存されたお気に入りの数
+    pub ordered: i64,
+}
+
+/// 章内の読書位置
+#[derive(Serialize, ToSchema)]
+pub struct ReadingPosition {
+    /// 段落番号（0始まり）
+    pub paragraph: Option<i64>,
+    /// スクロール割合（0.0 = 先頭, 1.0 = 末尾）
+    pub fraction: Option<f64>,
+}
+
+/// 既読位置
+#[derive(Serialize, ToSchema)]
+pub struct Progress {
+    /// 既読ページ番号（0 = 未読）
+    pub read: i64,
+    /// 章内の読書位置（未保存の場合は null）
+    pub position: Option<ReadingPosition>,
+    /// 既読位置を最後に更新した日時（UTC、ミリ秒まで）
+    pub progress_at: Option<String>,
 }
 
 /// お気に入り登録リクエスト
@@ -105,6 +214,389 @@ pub struct FavoriteRequest {
 pub struct ProgressRequest {
     /// 既読ページ番号
     pub read: i64,
+    /// 章内の段落番号（0始まり、省略可）
+    pub paragraph: Option<i64>,
+    /// 章内のスクロール割合（0.0〜1.0、省略可）
+    pub fraction: Option<f64>,
+    /// 端末で読んだ日時（RFC 3339、省略時はサーバーの受信日時）
+    pub updated_at: Option<String>,
+    /// 閲覧履歴に記録する端末名（省略可、64文字まで）
+    pub device: Option<String>,
+    /// 小説タイトル（お気に入りでない小説のみ使用、省略可）
+    pub title: Option<String>,
+    /// 総ページ数（お気に入りでない小説のみ使用、省略可）
+    pub page: Option<i64>,
+}
+
+/// 最近読んだ小説（お気に入り以外）
+#[derive(Serialize, ToSchema)]
+pub struct RecentRead {
+    /// サイト種別（narou / nocturne / kakuyomu）
+    #[serde(rename = "type")]
+    pub type_str: String,
+    /// 小説ID
+    pub id: String,
+    /// 小説タイトル（不明な場合は null）
+    pub title: Option<String>,
+    /// 総ページ数（不明な場合は null）
+    pub page: Option<i64>,
+    /// 既読ページ番号
+    pub read: i64,
+    /// 章内の読書位置（未保存の場合は null）
+    pub position: Option<ReadingPosition>,
+    /// 既読位置を最後に更新した日時（UTC、ミリ秒まで）
+    pub progress_at: String,
+}
+
+/// 閲覧履歴の1件
+#[derive(Serialize, ToSchema)]
+pub struct ReadingEvent {
+    /// 履歴ID（ページングの `before` に指定する）
+    pub id: i64,
+    /// サイト種別（narou / nocturne / kakuyomu）
+    #[serde(rename = "type")]
+    pub type_str: String,
+    /// 小説ID
+    pub novel_id: String,
+    /// 小説タイトル（メタデータ未取得の場合は null）
+    pub title: Option<String>,
+    /// 読んだページ番号
+    pub page: i64,
+    /// 端末名（未指定の場合は null）
+    pub device: Option<String>,
+    /// 読んだ日時（UTC、ミリ秒まで）
+    pub read_at: String,
+    /// 現在お気に入りに登録されているか
+    pub favorite: bool,
+}
+
+/// 期間ごとの読書量
+#[derive(Serialize, ToSchema)]
+pub struct PeriodStats {
+    /// 期間（日: YYYY-MM-DD、週: YYYY-Www、月: YYYY-MM）
+    pub period: String,
+    /// 読んだ話数
+    pub chapters: i64,
+    /// 読んだ文字数（文字数が記録済みの話のみ）
+    pub characters: i64,
+}
+
+/// サイト別の読書量
+#[derive(Serialize, ToSchema)]
+pub struct SiteStats {
+    /// サイト種別（narou / nocturne / kakuyomu）
+    #[serde(rename = "type")]
+    pub type_str: String,
+    /// 読んだ話数
+    pub chapters: i64,
+    /// 読んだ文字数
+    pub characters: i64,
+}
+
+/// 作品別の読書量
+#[derive(Serialize, ToSchema)]
+pub struct NovelStats {
+    /// サイト種別
+    #[serde(rename = "type")]
+    pub type_str: String,
+    /// 小説ID
+    pub id: String,
+    /// 小説タイトル（メタデータ未取得の場合は null）
+    pub title: Option<String>,
+    /// 読んだ話数
+    pub chapters: i64,
+    /// 読んだ文字数
+    pub characters: i64,
+    /// 最後に読んだ日時
+    pub last_read_at: String,
+}
+
+/// 未読を読み終えるまでの見積もり
+#[derive(Serialize, ToSchema)]
+pub struct CatchUpEstimate {
+    /// サイト種別
+    #[serde(rename = "type")]
+    pub type_str: String,
+    /// 小説ID
+    pub id: String,
+    /// 小説タイトル
+    pub title: String,
+    /// 未読話数
+    pub unread_chapters: i64,
+    /// 未読の推定文字数（文字数の記録が1話もない場合は null）
+    pub estimated_characters: Option<i64>,
+    /// 読み終えるまでの推定時間（分）
+    pub estimated_minutes: Option<i64>,
+}
+
+/// 読んだ量の合計
+#[derive(Serialize, ToSchema)]
+pub struct ReadTotal {
+    /// 読んだ話数
+    pub chapters: i64,
+    /// 読んだ文字数（文字数が記録済みの話のみ）
+    pub characters: i64,
+}
+
+/// 連続読書日数
+#[derive(Serialize, ToSchema)]
+pub struct Streak {
+    /// 今日または昨日まで続いている日数
+    pub current: i64,
+    /// 最長記録
+    pub longest: i64,
+}
+
+/// セッション集計
+#[derive(Serialize, ToSchema)]
+pub struct SessionStats {
+    /// セッション数
+    pub count: i64,
+    /// 1セッションあたりの平均話数
+    pub average_chapters: f64,
+}
+
+/// 読書速度
+#[derive(Serialize, ToSchema)]
+pub struct ReadingSpeed {
+    /// 1分あたりの文字数
+    pub characters_per_minute: i64,
+    /// 履歴から計測した値か（false なら既定値）
+    pub measured: bool,
+}
+
+/// 追いつくまでの時間
+#[derive(Serialize, ToSchema)]
+pub struct CatchUp {
+    /// 全作品の推定時間の合計（分）
+    pub total_minutes: i64,
+    /// 未読の多い順
+    pub novels: Vec<CatchUpEstimate>,
+}
+
+/// 読書統計
+#[derive(Serialize, ToSchema)]
+pub struct StatsResponse {
+    /// 全期間の合計
+    pub total: ReadTotal,
+    /// 日別（直近30日、読んだ日のみ）
+    pub by_day: Vec<PeriodStats>,
+    /// 週別（直近12週）
+    pub by_week: Vec<PeriodStats>,
+    /// 月別（直近12か月）
+    pub by_month: Vec<PeriodStats>,
+    /// サイト別
+    pub by_site: Vec<SiteStats>,
+    /// 作品別（話数の多い順）
+    pub by_novel: Vec<NovelStats>,
+    /// 連続読書日数
+    pub streak: Streak,
+    /// セッション
+    pub sessions: SessionStats,
+    /// 読書速度
+    pub reading_speed: ReadingSpeed,
+    /// 未読のあるお気に入りを読み終えるまでの見積もり
+    pub catch_up: CatchUp,
+}
+
+/// 閲覧履歴
+#[derive(Serialize, ToSchema)]
+pub struct HistoryResponse {
+    /// 新しい順の履歴
+    pub events: Vec<ReadingEvent>,
+    /// 次のページを取得する `before` の値（最後のページでは null）
+    pub next_before: Option<i64>,
+}
+
+/// しおり
+#[derive(Serialize, ToSchema)]
+pub struct Bookmark {
+    /// しおりID
+    pub id: i64,
+    /// サイト種別（narou / nocturne / kakuyomu）
+    #[serde(rename = "type")]
+    pub type_str: String,
+    /// 小説ID
+    pub novel_id: String,
+    /// 小説タイトル（メタデータ未取得の場合は null）
+    pub title: Option<String>,
+    /// ページ番号
+    pub page: i64,
+    /// 段落番号（本文の `<p>` 要素の0始まりの番号）
+    pub paragraph: i64,
+    /// 引用文
+    pub quote: String,
+    /// メモ
+    pub note: Option<String>,
+    /// 作成日時（UTC）
+    pub created_at: String,
+    /// 更新日時（UTC）
+    pub updated_at: String,
+}
+
+/// 現在の本文で位置を補正したしおり
+#[derive(Serialize, ToSchema)]
+pub struct LocatedBookmark {
+    #[serde(flatten)]
+    pub bookmark: Bookmark,
+    /// 引用文が本文中に見つかったか
+    pub found: bool,
+}
+
+/// しおり一覧
+#[derive(Serialize, ToSchema)]
+pub struct BookmarksResponse {
+    /// 新しい順のしおり
+    pub bookmarks: Vec<Bookmark>,
+    /// 次のページを取得する `before` の値（最後のページでは null）
+    pub next_before: Option<i64>,
+}
+
+/// しおり追加リクエスト
+#[derive(Serialize, ToSchema)]
+pub struct BookmarkRequest {
+    /// ページ番号（1始まり）
+    pub page: i64,
+    /// 段落番号（本文の `<p>` 要素の0始まりの番号）
+    pub paragraph: i64,
+    /// 引用文（500文字まで）
+    pub quote: String,
+    /// メモ（10000文字まで、省略可）
+    pub note: Option<String>,
+}
+
+/// しおり更新リクエスト（省略した項目は変更しない）
+#[derive(Serialize, ToSchema)]
+pub struct BookmarkUpdateRequest {
+    /// 段落番号
+    pub paragraph: Option<i64>,
+    /// 引用文（500文字まで）
+    pub quote: Option<String>,
+    /// メモ（空文字で削除）
+    pub note: Option<String>,
+}
+
+/// バックアップの1作品（CSVの列と同じ）
+#[derive(Serialize, ToSchema)]
+pub struct BackupRecord {
+    /// サイト種別（narou / nocturne / kakuyomu）
+    #[serde(rename = "type")]
+    pub type_str: String,
+    /// 小説ID
+    pub id: String,
+    /// タイトル
+    pub title: Option<String>,
+    /// 総ページ数
+    pub page: Option<i64>,
+    /// 小説更新日時
+    pub novelupdated_at: Option<String>,
+    /// 既読ページ番号
+    pub read: Option<i64>,
+    /// お気に入り登録日時
+    pub added_at: Option<String>,
+    /// メモ
+    pub notes: Option<String>,
+    /// 評価（1〜5）
+    pub rating: Option<i64>,
+    /// タグ名（カンマ区切り）
+    pub tags: Option<String>,
+}
+
+/// お気に入りバックアップ（JSON形式）
+#[derive(Serialize, ToSchema)]
+pub struct BackupFile {
+    /// 形式のバージョン
+    pub version: u32,
+    /// 出力日時
+    pub exported_at: String,
+    pub favorites: Vec<BackupRecord>,
+}
+
+/// リストアで取り込めなかった行
+#[derive(Serialize, ToSchema)]
+pub struct ImportError {
+    /// 0始まりの行番号（CSVはヘッダーを除く）
+    pub index: usize,
+    #[serde(rename = "type")]
+    pub type_str: String,
+    pub id: String,
+    /// 理由
+    pub error: String,
+}
+
+/// リストア結果
+#[derive(Serialize, ToSchema)]
+pub struct ImportSummary {
+    /// 保存せずに結果だけ返したか
+    pub dry_run: bool,
+    /// 適用したstrategy
+    pub strategy: String,
+    /// 追加した件数
+    pub added: usize,
+    /// 既読位置などを更新した件数
+    pub updated: usize,
+    /// 変更しなかった件数
+    pub skipped: usize,
+    pub errors: Vec<ImportError>,
+}
+
+/// URL解決結果
+#[derive(Serialize, ToSchema)]
+pub struct ResolveResponse {
+    /// サイト種別（narou / nocturne / kakuyomu）
+    #[serde(rename = "type")]
+    pub type_str: String,
+    /// 小説ID
+    pub id: String,
+    /// 話数（URLが話を指さない場合はnull）
+    pub page: Option<u64>,
+    /// リーダーのパス
+    pub path: String,
+}
+
+/// 小説の識別子
+#[derive(Serialize, ToSchema)]
+pub struct NovelRef {
+    /// サイト種別（narou / nocturne / kakuyomu）
+    #[serde(rename = "type")]
+    pub type_str: String,
+    /// 小説ID
+    pub id: String,
+}
+
+/// URLからの一括登録結果
+#[derive(Serialize, ToSchema)]
+pub struct UrlImportResult {
+    /// 認識した作品数（重複を除く）
+    pub found: usize,
+    /// 新たに登録した件数
+    pub added: usize,
+    /// 登録済みだった件数
+    pub existing: usize,
+    /// サイトに存在しない作品
+    pub not_found: Vec<NovelRef>,
+    /// メタデータの取得に失敗した作品
+    pub failed: Vec<NovelRef>,
+}
+
+/// OPMLインポートで作品URLとして解釈できなかったoutline
+#[derive(Serialize, ToSchema)]
+pub struct OpmlUnresolved {
+    /// outlineのタイトル
+    pub text: Option<String>,
+    /// outlineのURL
+    pub url: Option<String>,
+}
+
+/// OPMLインポート結果
+#[derive(Serialize, ToSchema)]
+pub struct OpmlImportResult {
+    /// 新たに登録した件数
+    pub added: usize,
+    /// 登録済みだった件数
+    pub existing: usize,
+    /// 解釈できなかったoutline
+    pub unresolved: Vec<OpmlUnresolved>,
 }
 
 /// 成功レスポンス
@@ -119,3 +611,152 @@ pub struct UserInfo {
     /// メールアドレス（guest の場合は "guest"）
     pub email: String,
 }
+
+/// フィードトークンの状態
+#[derive(Serialize, ToSchema)]
+pub struct FeedTokenStatus {
+    /// トークンが発行済みか
+    pub active: bool,
+    /// 発行日時
+    pub created_at: Option<String>,
+}
+
+/// トークン付きフィードURL
+#[derive(Serialize, ToSchema)]
+pub struct FeedUrls {
+    /// RSS 2.0
+    pub rss: String,
+    /// Atom 1.0
+    pub atom: String,
+    /// JSON Feed 1.1
+    pub json: String,
+}
+
+/// 発行されたフィードトークン
+#[derive(Serialize, ToSchema)]
+pub struct FeedToken {
+    /// トークン（このレスポンスでのみ返される）
+    pub token: String,
+    /// 発行日時
+    pub created_at: String,
+    /// トークン付きフィードURL
+    pub feeds: FeedUrls,
+}
+
+/// Webhook
+#[derive(Serialize, ToSchema)]
+pub struct Webhook {
+    /// Webhook ID
+    pub id: i64,
+    /// 送信先URL
+    pub url: String,
+    /// 購読するイベント種別（new_chapters）
+    pub events: Vec<String>,
+    /// 登録日時
+    pub created_at: String,
+    /// 署名用シークレット（登録時のレスポンスのみ）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub secret: Option<String>,
+}
+
+/// Webhook登録リクエスト
+#[derive(Serialize, ToSchema)]
+pub struct WebhookRequest {
+    /// 送信先URL（http / https）
+    pub url: String,
+    /// 署名用シークレット（16文字以上、省略時は自動生成）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub secret: Option<String>,
+    /// 購読するイベント種別（省略時は ["new_chapters"]）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub events: Option<Vec<String>>,
+}
+
+/// Webhook配信ログ
+#[derive(Serialize, ToSchema)]
+pub struct WebhookDelivery {
+    /// 配信ID
+    pub id: i64,
+    /// イベント種別
+    pub event: String,
+    /// 送信したJSON
+    pub payload: String,
+    /// 最後に受信したHTTPステータス（通信エラー時は null）
+    pub status: Option<i64>,
+    /// 送信試行回数
+    pub attempts: i64,
+    /// 2xxを受信したか
+    pub success: bool,
+    /// 失敗理由
+    pub error: Option<String>,
+    /// 配信日時
+    pub created_at: String,
+}
+
+/// VAPID公開鍵
+#[derive(Serialize, ToSchema)]
+pub struct VapidPublicKey {
+    /// 非圧縮形式のP-256公開鍵（base64url）
+    pub public_key: String,
+}
+
+/// Push購読の鍵
+#[derive(Serialize, ToSchema)]
+pub struct PushSubscriptionKeys {
+    /// ブラウザのECDH公開鍵（base64url）
+    pub p256dh: String,
+    /// 認証シークレット（base64url、16バイト）
+    pub auth: String,
+}
+
+/// Push購読登録リクエスト（`PushSubscription.toJSON()` の形式）
+#[derive(Serialize, ToSchema)]
+pub struct PushSubscriptionRequest {
+    /// プッシュサービスのエンドポイントURL（https）
+    pub endpoint: String,
+    /// 暗号化用の鍵
+    pub keys: PushSubscriptionKeys,
+}
+
+/// Push購読解除リクエスト
+#[derive(Serialize, ToSchema)]
+pub struct PushUnsubscribeRequest {
+    /// 解除するエンドポイントURL
+    pub endpoint: String,
+}
+
+/// メールダイジェスト設定
+#[derive(Serialize, ToSchema)]
+pub struct DigestSettings {
+    /// 購読中か
+    pub enabled: bool,
+    /// 送信頻度（daily / weekly）
+    pub frequency: String,
+    /// 送信時刻（UTC、0〜23時）
+    pub hour: u32,
+    /// 送信曜日（weekly のみ、0=月曜〜6=日曜）
+    pub weekday: u32,
+    /// 最終送信日時（集計期間の起点）
+    pub last_sent_at: Option<String>,
+    /// 送信先メールアドレス
+    pub email: String,
+    /// サーバーでメール送信が利用可能か
+    pub available: bool,
+}
+
+/// メールダイジェスト設定更新リクエスト
+#[derive(Serialize, ToSchema)]
+pub struct DigestRequest {
+    /// 購読するか
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub enabled: Option<bool>,
+    /// 送信頻度（daily / weekly）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub frequency: Option<String>,
+    /// 送信時刻（UTC、0〜23時）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub hour: Option<u32>,
+    /// 送信曜日（0=月曜〜6=日曜）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub weekday: Option<u32>,
+}
@@ -0,0 +1,508 @@
+use crate::config::Config;
+use crate::error::AppError;
+use crate::repo::{PushSubscriptionsRepo, SettingsRepo};
+use crate::state::AppState;
+use crate::sync::NovelUpdate;
+use aes_gcm::aead::{Aead, KeyInit};
+use aes_gcm::{Aes128Gcm, Nonce};
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use chrono::Utc;
+use hkdf::Hkdf;
+use p256::ecdsa::signature::Signer;
+use p256::ecdsa::{Signature, SigningKey};
+use p256::elliptic_curve::sec1::ToEncodedPoint;
+use p256::{PublicKey, SecretKey};
+use rand::rngs::OsRng;
+use rand::RngCore;
+use rusqlite::Connection;
+use serde_json::json;
+use sha2::Sha256;
+use std::time::Duration;
+
+/// `settings` key holding the generated VAPID private key
+const VAPID_SETTING: &str = "vapid_private_key";
+/// Record size advertised in the aes128gcm header. Messages are always sent as one record.
+const RECORD_SIZE: u32 = 4096;
+/// salt (16) + rs (4) + idlen (1) + keyid (65)
+const HEADER_LEN: usize = 86;
+/// Largest plaintext that fits in a single record (minus the padding delimiter and GCM tag)
+pub const MAX_PAYLOAD: usize = RECORD_SIZE as usize - HEADER_LEN - 1 - 16;
+/// How long the push service should keep an undelivered message (seconds)
+const TTL_SECS: u32 = 24 * 60 * 60;
+/// Lifetime of the VAPID JWT; push services reject anything over 24 hours
+const JWT_LIFETIME_SECS: i64 = 12 * 60 * 60;
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+pub struct Vapid {
+    key: SigningKey,
+    subject: String,
+}
+
+impl Vapid {
+    /// Uncompressed public key, base64url encoded — the `applicationServerKey` for `PushManager.subscribe`.
+    pub fn public_key(&self) -> String {
+        URL_SAFE_NO_PAD.encode(self.key.verifying_key().to_encoded_point(false).as_bytes())
+    }
+
+    /// `Authorization` header value (RFC 8292) for a request to `endpoint`.
+    pub fn authorization(&self, endpoint: &str) -> Result<String, AppError> {
+        let url = reqwest::Url::parse(endpoint)
+            .map_err(|e| AppError::BadRequest(format!("invalid push endpoint: {}", e)))?;
+        let header = URL_SAFE_NO_PAD.encode(r#"{"typ":"JWT","alg":"ES256"}"#);
+        let claims = URL_SAFE_NO_PAD.encode(
+            json!({
+                "aud": url.origin().ascii_serialization(),
+                "exp": Utc::now().timestamp() + JWT_LIFETIME_SECS,
+                "sub": self.subject,
+            })
+            .to_string(),
+        );
+        let signing_input = format!("{}.{}", header, claims);
+        let signature: Signature = self.key.sign(signing_input.as_bytes());
+        Ok(format!(
+            "vapid t={}.{}, k={}",
+            signing_input,
+            URL_SAFE_NO_PAD.encode(signature.to_bytes()),
+            self.public_key()
+        ))
+    }
+}
+
+pub struct Subscription {
+    pub endpoint: String,
+    pub p256dh: String,
+    pub auth: String,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum SendResult {
+    Delivered,
+    /// The push service no longer knows the subscription (404/410); it should be deleted.
+    Expired,
+    Failed(String),
+}
+
+/// Browsers hand out keys as unpadded base64url, but some libraries pad or use the standard alphabet.
+pub fn decode_key(value: &str) -> Option<Vec<u8>> {
+    let normalized: String = value
+        .trim()
+        .trim_end_matches('=')
+        .chars()
+        .map(|c| match c {
+            '+' => '-',
+            '/' => '_',
+            c => c,
+        })
+        .collect();
+    URL_SAFE_NO_PAD.decode(normalized).ok()
+}
+
+/// VAPID key from `VAPID_PRIVATE_KEY`, else the one stored in `settings`, else a newly generated one.
+/// Keeping the key stable matters: subscriptions are bound to the key they were created with.
+pub fn load_vapid(conn: &Connection, config: &Config) -> Result<Vapid, AppError> {
+    let encoded = match &config.vapid_private_key {
+        Some(key) => key.clone(),
+        None => {
+            let generated = URL_SAFE_NO_PAD.encode(SecretKey::random(&mut OsRng).to_bytes());
+            // Another request may have generated one first; whichever row exists wins.
+            SettingsRepo::new(conn).get_or_insert(VAPID_SETTING, &generated)?
+        }
+    };
+    let key = decode_key(&encoded)
+        .and_then(|bytes| SigningKey::from_slice(&bytes).ok())
+        .ok_or_else(|| AppError::Internal("invalid VAPID private key".into()))?;
+    Ok(Vapid {
+        key,
+        subject: config.vapid_subject.clone(),
+    })
+}
+
+/// Content encryption key and nonce for one message (RFC 8291 section 3.4 / RFC 8188 section 2.2).
+fn derive_key_nonce(
+    ecdh_secret: &[u8],
+    auth_secret: &[u8],
+    ua_public: &[u8],
+    as_public: &[u8],
+    salt: &[u8],
+) -> ([u8; 16], [u8; 12]) {
+    let mut key_info = b"WebPush: info\0".to_vec();
+    key_info.extend_from_slice(ua_public);
+    key_info.extend_from_slice(as_public);
+    let mut ikm = [0u8; 32];
+    Hkdf::<Sha256>::new(Some(auth_secret), ecdh_secret)
+        .expand(&key_info, &mut ikm)
+        .expect("32 bytes is a valid HKDF-SHA256 length");
+
+    let prk = Hkdf::<Sha256>::new(Some(salt), &ikm);
+    let mut cek = [0u8; 16];
+    let mut nonce = [0u8; 12];
+    prk.expand(b"Content-Encoding: aes128gcm\0", &mut cek)
+        .expect("16 bytes is a valid HKDF-SHA256 length");
+    prk.expand(b"Content-Encoding: nonce\0", &mut nonce)
+        .expect("12 bytes is a valid HKDF-SHA256 length");
+    (cek, nonce)
+}
+
+/// Encrypt `payload` for a subscription with a fresh ephemeral key and salt (aes128gcm, one record).
+pub fn encrypt(p256dh: &str, auth: &str, payload: &[u8]) -> Result<Vec<u8>, AppError> {
+    if payload.len() > MAX_PAYLOAD {
+        return Err(AppError::Internal(format!(
+            "push payload too large: {} bytes",
+            payload.len()
+        )));
+    }
+    let ua_public =
+        decode_key(p256dh).ok_or_else(|| AppError::BadRequest("invalid p256dh key".into()))?;
+    let ua_key = PublicKey::from_sec1_bytes(&ua_public)
+        .map_err(|_| AppError::BadRequest("invalid p256dh key".into()))?;
+    let auth_secret = decode_key(auth)
+        .filter(|a| a.len() == 16)
+        .ok_or_else(|| AppError::BadRequest("invalid auth secret".into()))?;
+
+    let as_secret = p256::ecdh::EphemeralSecret::random(&mut OsRng);
+    let as_public = as_secret.public_key().to_encoded_point(false);
+    let shared = as_secret.diffie_hellman(&ua_key);
+    let mut salt = [0u8; 16];
+    OsRng.fill_bytes(&mut salt);
+
+    let (cek, nonce) = derive_key_nonce(
+        shared.raw_secret_bytes(),
+        &auth_secret,
+        &ua_public,
+        as_public.as_bytes(),
+        &salt,
+    );
+    let mut plaintext = payload.to_vec();
+    plaintext.push(0x02); // last-record padding delimiter
+    let ciphertext = Aes128Gcm::new_from_slice(&cek)
+        .expect("16-byte key")
+        .encrypt(Nonce::from_slice(&nonce), plaintext.as_slice())
+        .map_err(|_| AppError::Internal("push encryption failed".into()))?;
+
+    let mut body = Vec::with_capacity(HEADER_LEN + ciphertext.len());
+    body.extend_from_slice(&salt);
+    body.extend_from_slice(&RECORD_SIZE.to_be_bytes());
+    body.push(as_public.as_bytes().len() as u8);
+    body.extend_from_slice(as_public.as_bytes());
+    body.extend_from_slice(&ciphertext);
+    Ok(body)
+}
+
+/// Encrypt and POST one message to the subscription's push service.
+pub async fn send(
+    client: &reqwest::Client,
+    vapid: &Vapid,
+    subscription: &Subscription,
+    payload: &str,
+) -> SendResult {
+    let prepared = vapid
+        .authorization(&subscription.endpoint)
+        .and_then(|auth| {
+            encrypt(&subscription.p256dh, &subscription.auth, payload.as_bytes()).map(|b| (auth, b))
+        });
+    let (authorization, body) = match prepared {
+        Ok(p) => p,
+        Err(e) => return SendResult::Failed(e.to_string()),
+    };
+    let res = client
+        .post(&subscription.endpoint)
+        .timeout(REQUEST_TIMEOUT)
+        .header(reqwest::header::AUTHORIZATION, authorization)
+        .header(reqwest::header::CONTENT_ENCODING, "aes128gcm")
+        .header(reqwest::header::CONTENT_TYPE, "application/octet-stream")
+        .header("TTL", TTL_SECS)
+        .header("Urgency", "normal")
+        .body(body)
+        .send()
+        .await;
+    match res {
+        Ok(res) if res.status().is_success() => SendResult::Delivered,
+        Ok(res)
+            if res.status() == reqwest::StatusCode::GONE
+                || res.status() == reqwest::StatusCode::NOT_FOUND =>
+        {
+            SendResult::Expired
+        }
+        Ok(res) => SendResult::Failed(format!("HTTP {}", res.status())),
+        Err(e) => SendResult::Failed(e.to_string()),
+    }
+}
+
+/// JSON handed to the service worker: notification text plus the reader URL to open on click.
+fn build_payload(update: &NovelUpdate, read: i64, base_path: &str) -> String {
+    let next = (read + 1).min(update.new_page).max(1);
+    json!({
+        "title": update.title,
+        "body": format!(
+            "{}話更新（{}話まで、未読{}話）",
+            update.new_page - update.old_page,
+            update.new_page,
+            (update.new_page - read).max(0)
+        ),
+        "url": format!("{}/novel/{}/{}/{}", base_path, update.type_str, update.id, next),
+        "tag": format!("{}:{}", update.type_str, update.id),
+    })
+    .to_string()
+}
+
+/// Push a notification to every subscribed browser whose user has the novel in favorites.
+/// Subscriptions the push service reports as gone are deleted.
+pub async fn dispatch(state: &AppState, updates: &[NovelUpdate]) {
+    let lookup = updates.to_vec();
+    let config = state.config.clone();
+    let found = state
+        .db
+        .write(move |conn| {
+            let mut batches = Vec::new();
+            for update in lookup {
+                match PushSubscriptionsRepo::new(conn).targets(&update) {
+                    Ok(targets) if !targets.is_empty() => batches.push((update, targets)),
+                    Ok(_) => {}
+                    Err(e) => tracing::error!("[push] target lookup failed: {}", e),
+                }
+            }
+            if batches.is_empty() {
+                return Ok(None);
+            }
+            // Generating the VAPID key on first use needs the writer
+            load_vapid(conn, &config).map(|v| Some((batches, std::sync::Arc::new(v))))
+        })
+        .await;
+    let (batches, vapid) = match found {
+        Ok(Some(found)) => found,
+        Ok(None) => return,
+        Err(e) => {
+            tracing::error!("[push] {}", e);
+            return;
+        }
+    };
+
+    for (update, targets) in batches {
+        for target in targets {
+            let payload = build_payload(&update, target.read, &state.config.base_path);
+            let http = state.http.clone();
+            let db = state.db.clone();
+            let vapid = vapid.clone();
+            tokio::spawn(async move {
+                let endpoint = &target.subscription.endpoint;
+                match send(&http, &vapid, &target.subscription, &payload).await {
+                    SendResult::Delivered => {}
+                    SendResult::Expired => {
+                        tracing::info!("[push] subscription expired, removing: {}", endpoint);
+                        let expired = endpoint.clone();
+                        if let Err(e) = db
+                            .write(move |conn| {
+                                PushSubscriptionsRepo::new(conn).delete_expired(&expired)
+                            })
+                            .await
+                        {
+                            tracing::error!("[push] failed to remove subscription: {}", e);
+                        }
+                    }
+                    SendResult::Failed(e) => {
+                        tracing::warn!("[push] delivery to {} failed: {}", endpoint, e);
+                    }
+                }
+            });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::http::{HeaderMap, StatusCode};
+    use axum::routing::post;
+    use axum::Router;
+    use p256::ecdsa::signature::Verifier;
+    use std::sync::{Arc, Mutex};
+
+    fn test_config(key: Option<String>) -> Config {
+        Config {
+            port: 3000,
+            base_path: String::new(),
+            db_path: String::new(),
+            vapid_private_key: key,
+            vapid_subject: "mailto:test@example.com".into(),
+            public_url: None,
+            smtp: None,
+            migration_owner_email: None,
+            webhook_allow_private: false,
+        }
+    }
+
+    /// Browser side of RFC 8291: returns the plaintext with the padding delimiter removed.
+    fn decrypt(ua_secret: &SecretKey, auth: &[u8], body: &[u8]) -> Vec<u8> {
+        let salt = &body[..16];
+        assert_eq!(&body[16..20], &RECORD_SIZE.to_be_bytes());
+        let idlen = body[20] as usize;
+        let as_public = &body[21..21 + idlen];
+        let ciphertext = &body[21 + idlen..];
+
+        let as_key = PublicKey::from_sec1_bytes(as_public).unwrap();
+        let shared = p256::ecdh::diffie_hellman(ua_secret.to_nonzero_scalar(), as_key.as_affine());
+        let ua_public = ua_secret.public_key().to_encoded_point(false);
+        let (cek, nonce) = derive_key_nonce(
+            shared.raw_secret_bytes(),
+            auth,
+            ua_public.as_bytes(),
+            as_public,
+            salt,
+        );
+        let mut plaintext = Aes128Gcm::new_from_slice(&cek)
+            .unwrap()
+            .decrypt(Nonce::from_slice(&nonce), ciphertext)
+            .unwrap();
+        assert_eq!(plaintext.pop(), Some(0x02));
+        plaintext
+    }
+
+    fn browser_keys() -> (SecretKey, String, [u8; 16], String) {
+        let secret = SecretKey::random(&mut OsRng);
+        let p256dh = URL_SAFE_NO_PAD.encode(secret.public_key().to_encoded_point(false).as_bytes());
+        let mut auth = [0u8; 16];
+        OsRng.fill_bytes(&mut auth);
+        let auth_b64 = URL_SAFE_NO_PAD.encode(auth);
+        (secret, p256dh, auth, auth_b64)
+    }
+
+    #[test]
+    fn encrypt_round_trips_through_browser_decryption() {
+        let (secret, p256dh, auth, auth_b64) = browser_keys();
+        let payload = r#"{"title":"小説","body":"2話更新"}"#;
+
+        let body = encrypt(&p256dh, &auth_b64, payload.as_bytes()).unwrap();
+        assert_eq!(body.len(), HEADER_LEN + payload.len() + 1 + 16);
+        assert_eq!(decrypt(&secret, &auth, &body), payload.as_bytes());
+
+        // Fresh salt and ephemeral key per message
+        let again = encrypt(&p256dh, &auth_b64, payload.as_bytes()).unwrap();
+        assert_ne!(body[..HEADER_LEN], again[..HEADER_LEN]);
+    }
+
+    #[test]
+    fn encrypt_rejects_bad_keys_and_oversized_payloads() {
+        let (_, p256dh, _, auth_b64) = browser_keys();
+        assert!(encrypt("AAAA", &auth_b64, b"x").is_err());
+        assert!(encrypt(&p256dh, "AAAA", b"x").is_err());
+        assert!(encrypt(&p256dh, &auth_b64, &vec![b'x'; MAX_PAYLOAD + 1]).is_err());
+        assert!(encrypt(&p256dh, &auth_b64, &vec![b'x'; MAX_PAYLOAD]).is_ok());
+    }
+
+    #[test]
+    fn decode_key_accepts_padded_and_standard_alphabet() {
+        assert_eq!(decode_key("-_8"), Some(vec![0xfb, 0xff]));
+        assert_eq!(decode_key("+/8="), Some(vec![0xfb, 0xff]));
+        assert_eq!(decode_key("!!"), None);
+    }
+
+    #[test]
+    fn load_vapid_generates_once_and_prefers_config() {
+        let conn = crate::db::open_memory();
+        let first = load_vapid(&conn, &test_config(None)).unwrap();
+        let second = load_vapid(&conn, &test_config(None)).unwrap();
+        assert_eq!(first.public_key(), second.public_key());
+
+        let configured = URL_SAFE_NO_PAD.encode(SecretKey::random(&mut OsRng).to_bytes());
+        let from_env = load_vapid(&conn, &test_config(Some(configured))).unwrap();
+        assert_ne!(from_env.public_key(), first.public_key());
+
+        assert!(load_vapid(&conn, &test_config(Some("bogus".into()))).is_err());
+    }
+
+    #[test]
+    fn authorization_carries_signed_jwt_for_endpoint_origin() {
+        let conn = crate::db::open_memory();
+        let vapid = load_vapid(&conn, &test_config(None)).unwrap();
+        let header = vapid
+            .authorization("https://push.example.com:8443/send/abc?x=1")
+            .unwrap();
+
+        let rest = header.strip_prefix("vapid t=").unwrap();
+        let (jwt, k) = rest.split_once(", k=").unwrap();
+        assert_eq!(k, vapid.public_key());
+        let (signing_input, signature) = jwt.rsplit_once('.').unwrap();
+        let claims: serde_json::Value = serde_json::from_slice(
+            &URL_SAFE_NO_PAD
+                .decode(signing_input.split_once('.').unwrap().1)
+                .unwrap(),
+        )
+        .unwrap();
+        assert_eq!(claims["aud"], "https://push.example.com:8443");
+        assert_eq!(claims["sub"], "mailto:test@example.com");
+        assert!(claims["exp"].as_i64().unwrap() > Utc::now().timestamp());
+
+        let signature = Signature::from_slice(&URL_SAFE_NO_PAD.decode(signature).unwrap()).unwrap();
+        vapid
+            .key
+            .verifying_key()
+            .verify(signing_input.as_bytes(), &signature)
+            .unwrap();
+    }
+
+    async fn spawn_push_service(status: u16) -> (String, Arc<Mutex<Vec<HeaderMap>>>) {
+        let received = Arc::new(Mutex::new(Vec::new()));
+        let recorder = received.clone();
+        let app = Router::new().route(
+            "/push/{id}",
+            post(move |headers: HeaderMap| async move {
+                recorder.lock().unwrap().push(headers);
+                StatusCode::from_u16(status).unwrap()
+            }),
+        );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+        (format!("http://{}/push/abc", addr), received)
+    }
+
+    #[tokio::test]
+    async fn send_reports_delivered_and_expired() {
+        let conn = crate::db::open_memory();
+        let vapid = load_vapid(&conn, &test_config(None)).unwrap();
+        let (_, p256dh, _, auth) = browser_keys();
+        let client = reqwest::Client::new();
+
+        let (endpoint, received) = spawn_push_service(201).await;
+        let sub = Subscription {
+            endpoint,
+            p256dh: p256dh.clone(),
+            auth: auth.clone(),
+        };
+        assert_eq!(
+            send(&client, &vapid, &sub, "{}").await,
+            SendResult::Delivered
+        );
+        let headers = received.lock().unwrap()[0].clone();
+        assert_eq!(headers["content-encoding"], "aes128gcm");
+        assert_eq!(headers["ttl"], TTL_SECS.to_string().as_str());
+        assert!(headers["authorization"]
+            .to_str()
+            .unwrap()
+            .starts_with("vapid t="));
+
+        let (endpoint, _) = spawn_push_service(410).await;
+        let sub = Subscription {
+            endpoint,
+            p256dh,
+            auth,
+        };
+        assert_eq!(send(&client, &vapid, &sub, "{}").await, SendResult::Expired);
+    }
+
+    #[test]
+    fn build_payload_links_to_the_next_unread_chapter() {
+        let update = NovelUpdate {
+            type_str: "narou".into(),
+            id: "n1".into(),
+            title: "Novel".into(),
+            old_page: 10,
+            new_page: 12,
+        };
+        let payload: serde_json::Value =
+            serde_json::from_str(&build_payload(&update, 7, "/novels")).unwrap();
+        assert_eq!(payload["url"], "/novels/novel/narou/n1/8");
+        assert_eq!(payload["tag"], "narou:n1");
+    }
+}
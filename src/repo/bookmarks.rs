@@ -0,0 +1,261 @@
+use crate::auth::UserId;
+use rusqlite::{Connection, OptionalExtension};
+use serde_json::{json, Value};
+
+/// A passage the user marked in a chapter.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Bookmark {
+    pub id: i64,
+    pub type_str: String,
+    pub novel_id: String,
+    /// Title from the shared novel metadata; None if the novel was never fetched
+    pub title: Option<String>,
+    pub page: i64,
+    /// Index of the `<p>` the quote was in, as last seen
+    pub paragraph: i64,
+    /// The marked text, used to find the passage again after the chapter is revised
+    pub quote: String,
+    pub note: Option<String>,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+impl Bookmark {
+    pub fn to_json(&self) -> Value {
+        json!({
+            "id": self.id,
+            "type": self.type_str,
+            "novel_id": self.novel_id,
+            "title": self.title,
+            "page": self.page,
+            "paragraph": self.paragraph,
+            "quote": self.quote,
+            "note": self.note,
+            "created_at": self.created_at,
+            "updated_at": self.updated_at,
+        })
+    }
+}
+
+/// What a bookmark points at and says; shared by `create` and `update`.
+pub struct BookmarkFields<'a> {
+    pub paragraph: i64,
+    pub quote: &'a str,
+    pub note: Option<&'a str>,
+}
+
+const BOOKMARK_SELECT: &str = "SELECT b.id, b.type, b.novel_id, n.title, b.page, b.paragraph,
+        b.quote, b.note, b.created_at, b.updated_at
+     FROM bookmarks b LEFT JOIN novels n ON n.type = b.type AND n.id = b.novel_id";
+
+fn map_bookmark_row(row: &rusqlite::Row) -> rusqlite::Result<Bookmark> {
+    Ok(Bookmark {
+        id: row.get(0)?,
+        type_str: row.get(1)?,
+        novel_id: row.get(2)?,
+        title: row.get(3)?,
+        page: row.get(4)?,
+        paragraph: row.get(5)?,
+        quote: row.get(6)?,
+        note: row.get(7)?,
+        created_at: row.get(8)?,
+        updated_at: row.get(9)?,
+    })
+}
+
+pub struct BookmarksRepo<'c> {
+    conn: &'c Connection,
+}
+
+impl<'c> BookmarksRepo<'c> {
+    pub fn new(conn: &'c Connection) -> Self {
+        Self { conn }
+    }
+
+    /// Across the library, newest first, `limit` at a time starting below `before`.
+    pub fn list(
+        &self,
+        user_id: UserId,
+        before: Option<i64>,
+        limit: usize,
+    ) -> rusqlite::Result<Vec<Bookmark>> {
+        let mut stmt = self.conn.prepare(&format!(
+            "{} WHERE b.user_id = ?1 AND (?2 IS NULL OR b.id < ?2) ORDER BY b.id DESC LIMIT ?3",
+            BOOKMARK_SELECT
+        ))?;
+        let rows = stmt
+            .query_map(
+                rusqlite::params![user_id.0, before, limit as i64],
+                map_bookmark_row,
+            )?
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(rows)
+    }
+
+    /// One novel's bookmarks in reading order, optionally only one chapter's.
+    pub fn for_novel(
+        &self,
+        user_id: UserId,
+        type_str: &str,
+        novel_id: &str,
+        page: Option<i64>,
+    ) -> rusqlite::Result<Vec<Bookmark>> {
+        let mut stmt = self.conn.prepare(&format!(
+            "{} WHERE b.user_id = ?1 AND b.type = ?2 AND b.novel_id = ?3
+                AND (?4 IS NULL OR b.page = ?4)
+             ORDER BY b.page, b.paragraph, b.id",
+            BOOKMARK_SELECT
+        ))?;
+        let rows = stmt
+            .query_map(
+                rusqlite::params![user_id.0, type_str, novel_id, page],
+                map_bookmark_row,
+            )?
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(rows)
+    }
+
+    pub fn get(&self, user_id: UserId, bookmark_id: i64) -> rusqlite::Result<Option<Bookmark>> {
+        self.conn
+            .query_row(
+                &format!("{} WHERE b.user_id = ?1 AND b.id = ?2", BOOKMARK_SELECT),
+                rusqlite::params![user_id.0, bookmark_id],
+                map_bookmark_row,
+            )
+            .optional()
+    }
+
+    pub fn create(
+        &self,
+        user_id: UserId,
+        type_str: &str,
+        novel_id: &str,
+        page: i64,
+        fields: &BookmarkFields,
+    ) -> rusqlite::Result<Bookmark> {
+        self.conn.execute(
+            "INSERT INTO bookmarks (user_id, type, novel_id, page, paragraph, quote, note)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+            rusqlite::params![
+                user_id.0,
+                type_str,
+                novel_id,
+                page,
+                fields.paragraph,
+                fields.quote,
+                fields.note
+            ],
+        )?;
+        self.get(user_id, self.conn.last_insert_rowid())?
+            .ok_or(rusqlite::Error::QueryReturnedNoRows)
+    }
+
+    /// Returns false if the bookmark doesn't exist.
+    pub fn update(
+        &self,
+        user_id: UserId,
+        bookmark_id: i64,
+        fields: &BookmarkFields,
+    ) -> rusqlite::Result<bool> {
+        let changes = self.conn.execute(
+            "UPDATE bookmarks SET paragraph = ?3, quote = ?4, note = ?5,
+                updated_at = CURRENT_TIMESTAMP
+             WHERE user_id = ?1 AND id = ?2",
+            rusqlite::params![
+                user_id.0,
+                bookmark_id,
+                fields.paragraph,
+                fields.quote,
+                fields.note
+            ],
+        )?;
+        Ok(changes > 0)
+    }
+
+    /// The quote was found elsewhere in a revised chapter. Not an edit by the
+    /// user, so `updated_at` is left alone.
+    pub fn relocate(
+        &self,
+        user_id: UserId,
+        bookmark_id: i64,
+        paragraph: i64,
+    ) -> rusqlite::Result<()> {
+        self.conn.execute(
+            "UPDATE bookmarks SET paragraph = ?3 WHERE user_id = ?1 AND id = ?2",
+            rusqlite::params![user_id.0, bookmark_id, paragraph],
+        )?;
+        Ok(())
+    }
+
+    /// Returns false if the bookmark doesn't exist.
+    pub fn delete(&self, user_id: UserId, bookmark_id: i64) -> rusqlite::Result<bool> {
+        let changes = self.conn.execute(
+            "DELETE FROM bookmarks WHERE user_id = ?1 AND id = ?2",
+            rusqlite::params![user_id.0, bookmark_id],
+        )?;
+        Ok(changes > 0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fields(paragraph: i64, quote: &str) -> BookmarkFields<'_> {
+        BookmarkFields {
+            paragraph,
+            quote,
+            note: None,
+        }
+    }
+
+    #[test]
+    fn lists_by_novel_in_reading_order_and_library_newest_first() {
+        let conn = crate::db::open_memory();
+        let repo = BookmarksRepo::new(&conn);
+        let later = repo
+            .create(UserId(1), "narou", "n1", 5, &fields(2, "later"))
+            .unwrap();
+        let earlier = repo
+            .create(UserId(1), "narou", "n1", 3, &fields(9, "earlier"))
+            .unwrap();
+        let other = repo
+            .create(UserId(1), "kakuyomu", "k1", 1, &fields(0, "other"))
+            .unwrap();
+
+        let novel = repo.for_novel(UserId(1), "narou", "n1", None).unwrap();
+        assert_eq!(novel, [earlier.clone(), later.clone()]);
+        let chapter = repo.for_novel(UserId(1), "narou", "n1", Some(5)).unwrap();
+        assert_eq!(chapter.len(), 1);
+        assert_eq!(chapter[0].id, later.id);
+
+        let first = repo.list(UserId(1), None, 2).unwrap();
+        assert_eq!(first, [other, earlier]);
+        let rest = repo.list(UserId(1), Some(first[1].id), 2).unwrap();
+        assert_eq!(rest, [later]);
+    }
+
+    #[test]
+    fn bookmarks_belong_to_their_user() {
+        let conn = crate::db::open_memory();
+        let repo = BookmarksRepo::new(&conn);
+        let bookmark = repo
+            .create(UserId(1), "narou", "n1", 3, &fields(4, "quote"))
+            .unwrap();
+        let edited = BookmarkFields {
+            paragraph: 5,
+            quote: "quote",
+            note: Some("note"),
+        };
+        assert!(!repo.update(UserId(2), bookmark.id, &edited).unwrap());
+        assert!(!repo.delete(UserId(2), bookmark.id).unwrap());
+        assert!(repo.list(UserId(2), None, 10).unwrap().is_empty());
+
+        assert!(repo.update(UserId(1), bookmark.id, &edited).unwrap());
+        let stored = repo.get(UserId(1), bookmark.id).unwrap().unwrap();
+        assert_eq!(stored.paragraph, 5);
+        assert_eq!(stored.note.as_deref(), Some("note"));
+        assert!(repo.delete(UserId(1), bookmark.id).unwrap());
+        assert!(repo.get(UserId(1), bookmark.id).unwrap().is_none());
+    }
+}
@@ -0,0 +1,117 @@
+use crate::auth::UserId;
+use rusqlite::Connection;
+
+/// A favorite with unread chapters, and what is known about their lengths.
+#[derive(Debug, PartialEq)]
+pub struct Backlog {
+    pub type_str: String,
+    pub id: String,
+    pub title: String,
+    pub page: i64,
+    pub read: i64,
+    /// Unread chapters whose length has been recorded
+    pub known_chapters: i64,
+    /// Total characters of those chapters
+    pub known_chars: i64,
+    /// Average length of every recorded chapter of the novel
+    pub average_chars: Option<f64>,
+}
+
+pub struct ChapterLengthsRepo<'c> {
+    conn: &'c Connection,
+}
+
+impl<'c> ChapterLengthsRepo<'c> {
+    pub fn new(conn: &'c Connection) -> Self {
+        Self { conn }
+    }
+
+    /// Store a chapter's character count, replacing one recorded before a revision.
+    pub fn record(&self, type_str: &str, id: &str, page: i64, chars: i64) -> rusqlite::Result<()> {
+        self.conn.execute(
+            "INSERT INTO chapter_lengths (type, id, page, chars) VALUES (?1, ?2, ?3, ?4)
+             ON CONFLICT(type, id, page) DO UPDATE SET chars = excluded.chars",
+            rusqlite::params![type_str, id, page, chars],
+        )?;
+        Ok(())
+    }
+
+    /// Average length over all recorded chapters, for novels with none recorded.
+    pub fn average(&self) -> rusqlite::Result<Option<f64>> {
+        self.conn
+            .query_row("SELECT AVG(chars) FROM chapter_lengths", [], |row| {
+                row.get(0)
+            })
+    }
+
+    /// The user's favorites that have unread chapters, most unread first.
+    pub fn backlog(&self, user_id: UserId) -> rusqlite::Result<Vec<Backlog>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT f.type, f.id, n.title, n.page, f.read,
+                (SELECT COUNT(*) FROM chapter_lengths c
+                 WHERE c.type = f.type AND c.id = f.id AND c.page > f.read AND c.page <= n.page),
+                (SELECT COALESCE(SUM(c.chars), 0) FROM chapter_lengths c
+                 WHERE c.type = f.type AND c.id = f.id AND c.page > f.read AND c.page <= n.page),
+                (SELECT AVG(c.chars) FROM chapter_lengths c WHERE c.type = f.type AND c.id = f.id)
+             FROM favorites f
+             JOIN novels n ON n.type = f.type AND n.id = f.id
+             WHERE f.user_id = ?1 AND n.page > f.read
+             ORDER BY n.page - f.read DESC, f.type, f.id",
+        )?;
+        let rows = stmt
+            .query_map([user_id.0], |row| {
+                Ok(Backlog {
+                    type_str: row.get(0)?,
+                    id: row.get(1)?,
+                    title: row.get(2)?,
+                    page: row.get(3)?,
+                    read: row.get(4)?,
+                    known_chapters: row.get(5)?,
+                    known_chars: row.get(6)?,
+                    average_chars: row.get(7)?,
+                })
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(rows)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn backlog_sums_recorded_unread_chapters() {
+        let conn = crate::db::open_memory();
+        conn.execute_batch(
+            "INSERT INTO novels (type, id, title, page) VALUES ('narou', 'n1', 'One', 5);
+             INSERT INTO novels (type, id, title, page) VALUES ('narou', 'n2', 'Two', 2);
+             INSERT INTO favorites (user_id, type, id, read) VALUES (1, 'narou', 'n1', 2);
+             INSERT INTO favorites (user_id, type, id, read) VALUES (1, 'narou', 'n2', 2);",
+        )
+        .unwrap();
+        let repo = ChapterLengthsRepo::new(&conn);
+        repo.record("narou", "n1", 1, 1000).unwrap();
+        repo.record("narou", "n1", 3, 3000).unwrap();
+        repo.record("narou", "n1", 4, 5000).unwrap();
+        // A revised chapter replaces the old length
+        repo.record("narou", "n1", 4, 2000).unwrap();
+
+        let backlog = repo.backlog(UserId(1)).unwrap();
+        assert_eq!(
+            backlog,
+            vec![Backlog {
+                type_str: "narou".into(),
+                id: "n1".into(),
+                title: "One".into(),
+                page: 5,
+                read: 2,
+                known_chapters: 2,
+                known_chars: 5000,
+                average_chars: Some(2000.0),
+            }],
+            "caught-up favorites are left out"
+        );
+        assert_eq!(repo.average().unwrap(), Some(2000.0));
+    }
+}
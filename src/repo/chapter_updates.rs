@@ -0,0 +1,132 @@
+use crate::sync::NovelUpdate;
+use rusqlite::Connection;
+
+/// One novel's new chapters within a digest window.
+#[derive(Debug)]
+pub struct DigestEntry {
+    pub type_str: String,
+    pub id: String,
+    pub title: String,
+    /// Page count before the first update in the window
+    pub from_page: i64,
+    pub page: i64,
+    pub read: i64,
+}
+
+/// New chapters found by sync, kept for the email digest.
+pub struct ChapterUpdatesRepo<'c> {
+    conn: &'c Connection,
+}
+
+impl<'c> ChapterUpdatesRepo<'c> {
+    pub fn new(conn: &'c Connection) -> Self {
+        Self { conn }
+    }
+
+    pub fn record(&self, updates: &[NovelUpdate], detected_at: &str) -> rusqlite::Result<()> {
+        for u in updates {
+            self.conn.execute(
+                "INSERT INTO chapter_updates (type, id, old_page, new_page, detected_at)
+                 VALUES (?1, ?2, ?3, ?4, ?5)",
+                rusqlite::params![u.type_str, u.id, u.old_page, u.new_page, detected_at],
+            )?;
+        }
+        Ok(())
+    }
+
+    /// Drop updates detected before `cutoff`.
+    pub fn prune(&self, cutoff: &str) -> rusqlite::Result<()> {
+        self.conn.execute(
+            "DELETE FROM chapter_updates WHERE detected_at < ?1",
+            [cutoff],
+        )?;
+        Ok(())
+    }
+
+    /// Updates after `since` to the user's favorites, merged per novel, newest first.
+    pub fn digest_entries(&self, user_id: i64, since: &str) -> rusqlite::Result<Vec<DigestEntry>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT c.type, c.id, n.title, MIN(c.old_page), n.page, f.read
+             FROM chapter_updates c
+             JOIN favorites f ON f.type = c.type AND f.id = c.id AND f.user_id = ?1
+             JOIN novels n ON n.type = c.type AND n.id = c.id
+             WHERE c.detected_at > ?2
+             GROUP BY c.type, c.id
+             ORDER BY MAX(c.detected_at) DESC",
+        )?;
+        let rows = stmt.query_map(rusqlite::params![user_id, since], |row| {
+            Ok(DigestEntry {
+                type_str: row.get(0)?,
+                id: row.get(1)?,
+                title: row.get(2)?,
+                from_page: row.get(3)?,
+                page: row.get(4)?,
+                read: row.get(5)?,
+            })
+        })?;
+        rows.collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn seed(conn: &Connection) {
+        conn.execute_batch(
+            "INSERT INTO users (id, email) VALUES (2, 'alice@example.com');
+             INSERT INTO novels (type, id, title, page) VALUES
+                ('narou', 'n1', 'Novel One', 12), ('narou', 'n2', 'Other', 5);
+             INSERT INTO favorites (user_id, type, id, read) VALUES (2, 'narou', 'n1', 9);",
+        )
+        .unwrap();
+    }
+
+    fn update(id: &str, old_page: i64, new_page: i64) -> NovelUpdate {
+        NovelUpdate {
+            type_str: "narou".into(),
+            id: id.into(),
+            title: String::new(),
+            old_page,
+            new_page,
+        }
+    }
+
+    #[test]
+    fn digest_entries_merge_updates_within_window() {
+        let conn = crate::db::open_memory();
+        seed(&conn);
+        let repo = ChapterUpdatesRepo::new(&conn);
+        repo.record(&[update("n1", 7, 8)], "2026-03-10 00:00:00")
+            .unwrap();
+        repo.record(
+            &[update("n1", 8, 10), update("n2", 4, 5)],
+            "2026-03-13 12:00:00",
+        )
+        .unwrap();
+        repo.record(&[update("n1", 10, 12)], "2026-03-13 18:00:00")
+            .unwrap();
+
+        let entries = repo.digest_entries(2, "2026-03-13 00:00:00").unwrap();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].id, "n1");
+        assert_eq!((entries[0].from_page, entries[0].page), (8, 12));
+        assert_eq!(entries[0].read, 9);
+    }
+
+    #[test]
+    fn prune_drops_rows_before_cutoff() {
+        let conn = crate::db::open_memory();
+        seed(&conn);
+        let repo = ChapterUpdatesRepo::new(&conn);
+        repo.record(&[update("n1", 7, 8)], "2026-03-10 00:00:00")
+            .unwrap();
+        repo.record(&[update("n1", 8, 9)], "2026-04-20 00:00:00")
+            .unwrap();
+        repo.prune("2026-03-16 00:00:00").unwrap();
+        let count: i64 = conn
+            .query_row("SELECT COUNT(*) FROM chapter_updates", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(count, 1);
+    }
+}
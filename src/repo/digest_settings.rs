@@ -0,0 +1,147 @@
+use crate::auth::UserId;
+use rusqlite::{Connection, OptionalExtension};
+
+pub struct DigestSettings {
+    pub enabled: bool,
+    pub frequency: String,
+    pub hour: u32,
+    pub weekday: u32,
+    pub last_sent_at: Option<String>,
+}
+
+impl Default for DigestSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            frequency: "daily".into(),
+            hour: 0,
+            weekday: 0,
+            last_sent_at: None,
+        }
+    }
+}
+
+pub struct DigestSchedule {
+    pub user_id: i64,
+    pub email: String,
+    pub frequency: String,
+    pub hour: u32,
+    pub weekday: u32,
+    pub last_sent_at: Option<String>,
+}
+
+pub struct DigestSettingsRepo<'c> {
+    conn: &'c Connection,
+}
+
+impl<'c> DigestSettingsRepo<'c> {
+    pub fn new(conn: &'c Connection) -> Self {
+        Self { conn }
+    }
+
+    /// The user's settings, or the disabled defaults if they never saved any.
+    pub fn get(&self, user_id: UserId) -> rusqlite::Result<DigestSettings> {
+        let settings = self
+            .conn
+            .query_row(
+                "SELECT enabled, frequency, hour, weekday, last_sent_at FROM digest_settings WHERE user_id = ?1",
+                [user_id.0],
+                |row| {
+                    Ok(DigestSettings {
+                        enabled: row.get(0)?,
+                        frequency: row.get(1)?,
+                        hour: row.get(2)?,
+                        weekday: row.get(3)?,
+                        last_sent_at: row.get(4)?,
+                    })
+                },
+            )
+            .optional()?;
+        Ok(settings.unwrap_or_default())
+    }
+
+    pub fn save(&self, user_id: UserId, settings: &DigestSettings) -> rusqlite::Result<()> {
+        self.conn.execute(
+            "INSERT INTO digest_settings (user_id, enabled, frequency, hour, weekday, last_sent_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6)
+             ON CONFLICT(user_id) DO UPDATE SET
+                enabled = excluded.enabled, frequency = excluded.frequency, hour = excluded.hour,
+                weekday = excluded.weekday, last_sent_at = excluded.last_sent_at",
+            rusqlite::params![
+                user_id.0,
+                settings.enabled,
+                settings.frequency,
+                settings.hour,
+                settings.weekday,
+                settings.last_sent_at
+            ],
+        )?;
+        Ok(())
+    }
+
+    /// Every enabled digest with its owner's address.
+    pub fn enabled(&self) -> rusqlite::Result<Vec<DigestSchedule>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT d.user_id, u.email, d.frequency, d.hour, d.weekday, d.last_sent_at
+             FROM digest_settings d JOIN users u ON u.id = d.user_id
+             WHERE d.enabled = 1",
+        )?;
+        let rows = stmt.query_map([], |row| {
+            Ok(DigestSchedule {
+                user_id: row.get(0)?,
+                email: row.get(1)?,
+                frequency: row.get(2)?,
+                hour: row.get(3)?,
+                weekday: row.get(4)?,
+                last_sent_at: row.get(5)?,
+            })
+        })?;
+        rows.collect()
+    }
+
+    /// Switch the digest off, e.g. when the user's address can't be mailed.
+    pub fn disable(&self, user_id: UserId) -> rusqlite::Result<()> {
+        self.conn.execute(
+            "UPDATE digest_settings SET enabled = 0 WHERE user_id = ?1",
+            [user_id.0],
+        )?;
+        Ok(())
+    }
+
+    /// Start the user's next digest window at `sent_at`.
+    pub fn mark_sent(&self, user_id: UserId, sent_at: &str) -> rusqlite::Result<()> {
+        self.conn.execute(
+            "UPDATE digest_settings SET last_sent_at = ?1 WHERE user_id = ?2",
+            rusqlite::params![sent_at, user_id.0],
+        )?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn save_then_get_round_trips() {
+        let conn = crate::db::open_memory();
+        let repo = DigestSettingsRepo::new(&conn);
+        assert!(!repo.get(UserId(1)).unwrap().enabled);
+        let settings = DigestSettings {
+            enabled: true,
+            frequency: "weekly".into(),
+            hour: 22,
+            weekday: 5,
+            last_sent_at: Some("2026-03-13 22:00:00".into()),
+        };
+        repo.save(UserId(1), &settings).unwrap();
+        repo.mark_sent(UserId(1), "2026-03-20 22:00:00").unwrap();
+        let saved = repo.get(UserId(1)).unwrap();
+        assert!(saved.enabled);
+        assert_eq!(
+            (saved.frequency.as_str(), saved.hour, saved.weekday),
+            ("weekly", 22, 5)
+        );
+        assert_eq!(saved.last_sent_at.as_deref(), Some("2026-03-20 22:00:00"));
+    }
+}
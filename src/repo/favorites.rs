@@ -0,0 +1,1398 @@
+use crate::auth::UserId;
+use crate::sync::NovelUpdate;
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use chrono::Utc;
+use rusqlite::types::Value as SqlValue;
+use rusqlite::{Connection, OptionalExtension, ToSql};
+use serde_json::{json, Value};
+
+/// Consecutive "not found" results before a novel is marked as gone.
+/// A single miss is not trusted: the upstream APIs occasionally drop entries.
+const GONE_THRESHOLD: i64 = 3;
+
+/// Favorites joined with the shared novel metadata; column order matches `map_favorite_row`.
+const FAVORITE_SELECT: &str =
+    "SELECT f.type, f.id, n.title, n.novelupdated_at, n.page, f.read, n.gone_at, f.added_at,
+        f.read_paragraph, f.read_fraction, f.progress_at, f.sort_order,
+        (SELECT group_concat(t.name, char(31)) FROM favorite_tags ft JOIN tags t ON t.id = ft.tag_id
+         WHERE ft.user_id = f.user_id AND ft.type = f.type AND ft.id = f.id),
+        f.notes, f.rating
+     FROM favorites f JOIN novels n ON n.type = f.type AND n.id = f.id";
+
+/// A user's favorite with the shared novel metadata.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Favorite {
+    pub type_str: String,
+    pub id: String,
+    pub title: String,
+    pub novelupdated_at: Option<String>,
+    pub page: i64,
+    pub read: i64,
+    pub gone_at: Option<String>,
+    pub added_at: String,
+    /// Paragraph index within chapter `read`
+    pub read_paragraph: Option<i64>,
+    /// Scroll position within chapter `read`, 0.0 (top) to 1.0 (bottom)
+    pub read_fraction: Option<f64>,
+    /// When the device that last moved the progress recorded it
+    pub progress_at: Option<String>,
+    /// Position in the user's manual order; None sorts after ordered favorites
+    pub sort_order: Option<i64>,
+    /// Names of the user's tags on this favorite, sorted
+    pub tags: Vec<String>,
+    /// Private notes
+    pub notes: Option<String>,
+    /// 1 to 5
+    pub rating: Option<i64>,
+}
+
+impl Favorite {
+    /// API representation, as returned by `/api/favorites`.
+    pub fn to_json(&self) -> Value {
+        json!({
+            "type": self.type_str,
+            "id": self.id,
+            "title": self.title,
+            "novelupdated_at": self.novelupdated_at,
+            "page": self.page,
+            "read": self.read,
+            "status": if self.gone_at.is_some() { "gone" } else { "active" },
+            "gone_at": self.gone_at,
+            "added_at": self.added_at,
+            "position": self.position_json(),
+            "progress_at": self.progress_at,
+            "sort_order": self.sort_order,
+            "tags": self.tags,
+            "notes": self.notes,
+            "rating": self.rating,
+        })
+    }
+
+    /// Notes, rating and tags, as returned by `/api/favorites/{type}/{id}/notes`.
+    pub fn notes_json(&self) -> Value {
+        json!({
+            "notes": self.notes,
+            "rating": self.rating,
+            "tags": self.tags,
+        })
+    }
+
+    /// Just the reading progress, as returned by `/api/favorites/{type}/{id}/progress`.
+    pub fn progress_json(&self) -> Value {
+        json!({
+            "read": self.read,
+            "position": self.position_json(),
+            "progress_at": self.progress_at,
+        })
+    }
+
+    fn position_json(&self) -> Value {
+        position_json(self.read_paragraph, self.read_fraction)
+    }
+}
+
+/// The in-chapter position, or null when none was stored.
+pub(super) fn position_json(paragraph: Option<i64>, fraction: Option<f64>) -> Value {
+    if paragraph.is_none() && fraction.is_none() {
+        return Value::Null;
+    }
+    json!({
+        "paragraph": paragraph,
+        "fraction": fraction,
+    })
+}
+
+fn map_favorite_row(row: &rusqlite::Row) -> rusqlite::Result<Favorite> {
+    Ok(Favorite {
+        type_str: row.get(0)?,
+        id: row.get(1)?,
+        title: row.get(2)?,
+        novelupdated_at: row.get(3)?,
+        page: row.get(4)?,
+        read: row.get(5)?,
+        gone_at: row.get(6)?,
+        added_at: row.get(7)?,
+        read_paragraph: row.get(8)?,
+        read_fraction: row.get(9)?,
+        progress_at: row.get(10)?,
+        sort_order: row.get(11)?,
+        tags: {
+            let joined: Option<String> = row.get(12)?;
+            let mut tags: Vec<String> = joined
+                .map(|j| j.split('\u{1f}').map(str::to_string).collect())
+                .unwrap_or_default();
+            tags.sort();
+            tags
+        },
+        notes: row.get(13)?,
+        rating: row.get(14)?,
+    })
+}
+
+/// Metadata for a novel that may not be known yet.
+pub struct NewNovel<'a> {
+    pub type_str: &'a str,
+    pub id: &'a str,
+    pub title: &'a str,
+    /// 0 for a placeholder that sync fills in later
+    pub page: i64,
+    pub novelupdated_at: Option<&'a str>,
+}
+
+/// A reading position reported by one device.
+pub struct Progress {
+    /// Chapter number (0 = unread)
+    pub read: i64,
+    /// Paragraph index within chapter `read`
+    pub paragraph: Option<i64>,
+    /// Scroll position within chapter `read`, 0.0 to 1.0
+    pub fraction: Option<f64>,
+    /// When the device recorded it, as `PROGRESS_TIME_FORMAT` in UTC
+    pub at: String,
+}
+
+/// Fixed-width UTC timestamps, so progress times compare correctly as text.
+pub const PROGRESS_TIME_FORMAT: &str = "%Y-%m-%dT%H:%M:%S%.3fZ";
+
+/// What `upsert` changed.
+#[derive(Debug, PartialEq)]
+pub struct Upserted {
+    /// The novel was unknown and was inserted with the given metadata
+    pub novel_created: bool,
+    /// The user didn't have the favorite yet
+    pub favorite_added: bool,
+}
+
+/// Whether the novel is still published, as reported in `status`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FavoriteStatus {
+    Active,
+    Gone,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum FavoriteSort {
+    /// Most recently updated first, never-updated last
+    #[default]
+    Updated,
+    /// Most recently added first
+    Added,
+    /// By title
+    Title,
+    /// Most unread chapters first, then by update
+    Unread,
+    /// The user's manual order, then unordered favorites by update
+    Manual,
+}
+
+/// Sort keys as `(SQL expression, descending)`, most significant first. NULLs
+/// are ordered by an `IS NULL` key before the nullable one, so every key
+/// compares with `<`/`>` once the keys before it are equal.
+const UPDATED_KEYS: [(&str, bool); 2] = [
+    ("n.novelupdated_at IS NULL", false),
+    ("n.novelupdated_at", true),
+];
+/// Appended to every sort so the order, and therefore a cursor, is total.
+const TIEBREAK_KEYS: [(&str, bool); 2] = [("f.type", false), ("f.id", false)];
+
+impl FavoriteSort {
+    fn name(self) -> &'static str {
+        match self {
+            FavoriteSort::Updated => "updated",
+            FavoriteSort::Added => "added",
+            FavoriteSort::Title => "title",
+            FavoriteSort::Unread => "unread",
+            FavoriteSort::Manual => "manual",
+        }
+    }
+
+    fn keys(self) -> Vec<(&'static str, bool)> {
+        let mut keys = match self {
+            FavoriteSort::Updated => UPDATED_KEYS.to_vec(),
+            FavoriteSort::Added => vec![("f.added_at", true)],
+            FavoriteSort::Title => vec![("n.title", false)],
+            FavoriteSort::Unread => {
+                [&[("MAX(n.page - f.read, 0)", true)][..], &UPDATED_KEYS].concat()
+            }
+            FavoriteSort::Manual => [
+                &[("f.sort_order IS NULL", false), ("f.sort_order", false)][..],
+                &UPDATED_KEYS,
+            ]
+            .concat(),
+        };
+        keys.extend(TIEBREAK_KEYS);
+        keys
+    }
+
+    /// The favorite's values for `keys`, in the same order.
+    fn key_values(self, favorite: &Favorite) -> Vec<SqlValue> {
+        let updated = || {
+            vec![
+                SqlValue::Integer(favorite.novelupdated_at.is_none() as i64),
+                favorite
+                    .novelupdated_at
+                    .clone()
+                    .map_or(SqlValue::Null, SqlValue::Text),
+            ]
+        };
+        let mut values = match self {
+            FavoriteSort::Updated => updated(),
+            FavoriteSort::Added => vec![SqlValue::Text(favorite.added_at.clone())],
+            FavoriteSort::Title => vec![SqlValue::Text(favorite.title.clone())],
+            FavoriteSort::Unread => {
+                let mut values = vec![SqlValue::Integer((favorite.page - favorite.read).max(0))];
+                values.extend(updated());
+                values
+            }
+            FavoriteSort::Manual => {
+                let mut values = vec![
+                    SqlValue::Integer(favorite.sort_order.is_none() as i64),
+                    favorite
+                        .sort_order
+                        .map_or(SqlValue::Null, SqlValue::Integer),
+                ];
+                values.extend(updated());
+                values
+            }
+        };
+        values.push(SqlValue::Text(favorite.type_str.clone()));
+        values.push(SqlValue::Text(favorite.id.clone()));
+        values
+    }
+}
+
+/// Where the previous page of `list` ended: the last favorite's sort key
+/// values, so the next page starts right after it even if that favorite has
+/// since changed or been removed.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FavoritesCursor {
+    sort: FavoriteSort,
+    values: Vec<SqlValue>,
+}
+
+impl FavoritesCursor {
+    pub fn after(sort: FavoriteSort, favorite: &Favorite) -> Self {
+        Self {
+            sort,
+            values: sort.key_values(favorite),
+        }
+    }
+
+    /// Opaque form for clients: unpadded base64url of a JSON array.
+    pub fn encode(&self) -> String {
+        let mut items = vec![Value::from(self.sort.name())];
+        items.extend(self.values.iter().map(|value| match value {
+            SqlValue::Integer(i) => Value::from(*i),
+            SqlValue::Text(s) => Value::from(s.as_str()),
+            _ => Value::Null,
+        }));
+        URL_SAFE_NO_PAD.encode(Value::Array(items).to_string())
+    }
+
+    /// None if `cursor` isn't one `encode` produced for this sort.
+    pub fn decode(sort: FavoriteSort, cursor: &str) -> Option<Self> {
+        let bytes = URL_SAFE_NO_PAD.decode(cursor).ok()?;
+        let Value::Array(items) = serde_json::from_slice(&bytes).ok()? else {
+            return None;
+        };
+        let (name, values) = items.split_first()?;
+        if name.as_str() != Some(sort.name()) || values.len() != sort.keys().len() {
+            return None;
+        }
+        let values = values
+            .iter()
+            .map(|value| match value {
+                Value::Null => Some(SqlValue::Null),
+                Value::Number(n) => n.as_i64().map(SqlValue::Integer),
+                Value::String(s) => Some(SqlValue::Text(s.clone())),
+                _ => None,
+            })
+            .collect::<Option<Vec<_>>>()?;
+        Some(Self { sort, values })
+    }
+}
+
+/// Which favorites `list` returns and in what order.
+#[derive(Default)]
+pub struct FavoritesFilter<'a> {
+    /// Only favorites with this tag
+    pub tag: Option<&'a str>,
+    pub status: Option<FavoriteStatus>,
+    /// Words that must each appear in the title, notes or a tag name (case-insensitive)
+    pub query: Option<&'a str>,
+    /// Comma-separated site types
+    pub sites: Option<&'a str>,
+    /// Only favorites with (true) or without (false) unread chapters
+    pub unread: Option<bool>,
+    pub sort: FavoriteSort,
+    /// Only favorites after this position in `sort` order; ignored by `count`
+    pub after: Option<&'a FavoritesCursor>,
+    /// Ignored by `count`
+    pub limit: Option<usize>,
+}
+
+/// Unread window and scope for feed items.
+pub struct FeedItemsFilter<'a> {
+    pub min_unread: i64,
+    pub max_unread: i64,
+    /// Comma-separated site types
+    pub sites: Option<&'a str>,
+    /// Single novel as `(type, id)`
+    pub novel: Option<(&'a str, &'a str)>,
+    /// Only favorites with this tag
+    pub tag: Option<&'a str>,
+}
+
+/// `%word%` for LIKE, with the wildcards in `word` escaped by `\`.
+fn like_pattern(word: &str) -> String {
+    let escaped = word
+        .replace('\\', "\\\\")
+        .replace('%', "\\%")
+        .replace('_', "\\_");
+    format!("%{}%", escaped)
+}
+
+/// SQL condition for favorites `f` carrying the tag named by parameter `param`.
+fn has_tag(param: &str) -> String {
+    format!(
+        "EXISTS (SELECT 1 FROM favorite_tags ft JOIN tags t ON t.id = ft.tag_id
+                 WHERE ft.user_id = f.user_id AND ft.type = f.type AND ft.id = f.id
+                    AND t.name = {})",
+        param
+    )
+}
+
+/// Add a parameter and return its placeholder.
+fn bind(params: &mut Vec<Box<dyn ToSql>>, value: impl ToSql + 'static) -> String {
+    params.push(Box::new(value));
+    format!("?{}", params.len())
+}
+
+/// WHERE conditions for everything in the filter but the cursor, shared by `list` and `count`.
+fn filter_conditions(
+    user_id: UserId,
+    filter: &FavoritesFilter,
+    params: &mut Vec<Box<dyn ToSql>>,
+) -> Vec<String> {
+    let mut conditions = vec![format!("f.user_id = {}", bind(params, user_id.0))];
+    if let Some(tag) = filter.tag {
+        conditions.push(has_tag(&bind(params, tag.to_string())));
+    }
+    if let Some(status) = filter.status {
+        conditions.push(format!(
+            "(n.gone_at IS NOT NULL) = {}",
+            bind(params, status == FavoriteStatus::Gone)
+        ));
+    }
+    if let Some(sites) = filter.sites {
+        conditions.push(format!(
+            "(',' || {} || ',') LIKE '%,' || f.type || ',%'",
+            bind(params, sites.to_string())
+        ));
+    }
+    match filter.unread {
+        Some(true) => conditions.push("n.page > f.read".into()),
+        Some(false) => conditions.push("n.page <= f.read".into()),
+        None => {}
+    }
+    for word in filter.query.into_iter().flat_map(str::split_whitespace) {
+        let p = bind(params, like_pattern(word));
+        conditions.push(format!(
+            "(n.title LIKE {p} ESCAPE '\\' OR f.notes LIKE {p} ESCAPE '\\'
+                OR EXISTS (SELECT 1 FROM favorite_tags ft JOIN tags t ON t.id = ft.tag_id
+                    WHERE ft.user_id = f.user_id AND ft.type = f.type AND ft.id = f.id
+                        AND t.name LIKE {p} ESCAPE '\\'))"
+        ));
+    }
+    conditions
+}
+
+pub struct FavoritesRepo<'c> {
+    conn: &'c Connection,
+}
+
+impl<'c> FavoritesRepo<'c> {
+    pub fn new(conn: &'c Connection) -> Self {
+        Self { conn }
+    }
+
+    pub fn list(
+        &self,
+        user_id: UserId,
+        filter: &FavoritesFilter,
+    ) -> rusqlite::Result<Vec<Favorite>> {
+        let mut params = Vec::new();
+        let mut conditions = filter_conditions(user_id, filter, &mut params);
+        let keys = filter.sort.keys();
+        if let Some(cursor) = filter.after {
+            // Rows past the cursor: equal on the leading keys, then past it on the next one
+            let past: Vec<String> = (0..keys.len())
+                .map(|i| {
+                    let mut terms: Vec<String> = keys[..i]
+                        .iter()
+                        .zip(&cursor.values)
+                        .map(|((expr, _), value)| {
+                            format!("({}) IS {}", expr, bind(&mut params, value.clone()))
+                        })
+                        .collect();
+                    let (expr, descending) = keys[i];
+                    terms.push(format!(
+                        "({}) {} {}",
+                        expr,
+                        if descending { "<" } else { ">" },
+                        bind(&mut params, cursor.values[i].clone())
+                    ));
+                    format!("({})", terms.join(" AND "))
+                })
+                .collect();
+            conditions.push(format!("({})", past.join(" OR ")));
+        }
+        let order: Vec<String> = keys
+            .iter()
+            .map(|(expr, descending)| format!("{}{}", expr, if *descending { " DESC" } else { "" }))
+            .collect();
+        let limit = match filter.limit {
+            Some(limit) => format!(" LIMIT {}", bind(&mut params, limit as i64)),
+            None => String::new(),
+        };
+        let mut stmt = self.conn.prepare(&format!(
+            "{} WHERE {} ORDER BY {}{}",
+            FAVORITE_SELECT,
+            conditions.join(" AND "),
+            order.join(", "),
+            limit
+        ))?;
+        let rows = stmt
+            .query_map(rusqlite::params_from_iter(params.iter()), map_favorite_row)?
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(rows)
+    }
+
+    /// How many favorites match the filter, regardless of `after` and `limit`.
+    pub fn count(&self, user_id: UserId, filter: &FavoritesFilter) -> rusqlite::Result<i64> {
+        let mut params = Vec::new();
+        let conditions = filter_conditions(user_id, filter, &mut params);
+        self.conn.query_row(
+            &format!(
+                "SELECT COUNT(*) FROM favorites f JOIN novels n ON n.type = f.type AND n.id = f.id
+                 WHERE {}",
+                conditions.join(" AND ")
+            ),
+            rusqlite::params_from_iter(params.iter()),
+            |row| row.get(0),
+        )
+    }
+
+    /// Set the private notes and rating. Returns false if the user doesn't have the favorite.
+    pub fn set_notes(
+        &self,
+        user_id: UserId,
+        type_str: &str,
+        id: &str,
+        notes: Option<&str>,
+        rating: Option<i64>,
+    ) -> rusqlite::Result<bool> {
+        let changes = self.conn.execute(
+            "UPDATE favorites SET notes = ?1, rating = ?2 WHERE user_id = ?3 AND type = ?4 AND id = ?5",
+            rusqlite::params![notes, rating, user_id.0, type_str, id],
+        )?;
+        Ok(changes > 0)
+    }
+
+    /// Set whichever of notes and rating is given, keeping the other. Returns
+    /// false if nothing changed.
+    pub fn merge_notes(
+        &self,
+        user_id: UserId,
+        type_str: &str,
+        id: &str,
+        notes: Option<&str>,
+        rating: Option<i64>,
+    ) -> rusqlite::Result<bool> {
+        let changes = self.conn.execute(
+            "UPDATE favorites SET notes = COALESCE(?1, notes), rating = COALESCE(?2, rating)
+             WHERE user_id = ?3 AND type = ?4 AND id = ?5
+                AND (?1 IS NOT NULL AND notes IS NOT ?1 OR ?2 IS NOT NULL AND rating IS NOT ?2)",
+            rusqlite::params![notes, rating, user_id.0, type_str, id],
+        )?;
+        Ok(changes > 0)
+    }
+
+    /// Replace the user's manual order with `order`, first to last. Favorites
+    /// left out lose their position. Returns how many of `order` were found.
+    pub fn set_order(
+        &self,
+        user_id: UserId,
+        order: &[(String, String)],
+    ) -> rusqlite::Result<usize> {
+        self.conn.execute(
+            "UPDATE favorites SET sort_order = NULL WHERE user_id = ?1",
+            [user_id.0],
+        )?;
+        let mut stmt = self.conn.prepare(
+            "UPDATE favorites SET sort_order = ?1 WHERE user_id = ?2 AND type = ?3 AND id = ?4",
+        )?;
+        let mut found = 0;
+        for (position, (type_str, id)) in order.iter().enumerate() {
+            found += stmt.execute(rusqlite::params![position as i64, user_id.0, type_str, id])?;
+        }
+        Ok(found)
+    }
+
+    pub fn get(
+        &self,
+        user_id: UserId,
+        type_str: &str,
+        id: &str,
+    ) -> rusqlite::Result<Option<Favorite>> {
+        self.conn
+            .query_row(
+                &format!(
+                    "{} WHERE f.user_id = ?1 AND f.type = ?2 AND f.id = ?3",
+                    FAVORITE_SELECT
+                ),
+                rusqlite::params![user_id.0, type_str, id],
+                map_favorite_row,
+            )
+            .optional()
+    }
+
+    /// All of the user's favorites, oldest added first, for backups and OPML.
+    pub fn export_rows(&self, user_id: UserId) -> rusqlite::Result<Vec<Favorite>> {
+        let mut stmt = self.conn.prepare(&format!(
+            "{} WHERE f.user_id = ?1 ORDER BY f.added_at, f.type, f.id",
+            FAVORITE_SELECT
+        ))?;
+        let rows = stmt
+            .query_map([user_id.0], map_favorite_row)?
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(rows)
+    }
+
+    /// The chapter read so far, or None if the user doesn't have the favorite.
+    pub fn read_position(
+        &self,
+        user_id: UserId,
+        type_str: &str,
+        id: &str,
+    ) -> rusqlite::Result<Option<i64>> {
+        self.conn
+            .query_row(
+                "SELECT read FROM favorites WHERE user_id = ?1 AND type = ?2 AND id = ?3",
+                rusqlite::params![user_id.0, type_str, id],
+                |row| row.get(0),
+            )
+            .optional()
+    }
+
+    /// Add a favorite with a known read position, as restored from a backup.
+    /// `added_at` defaults to now. The novel must already exist.
+    pub fn insert_favorite(
+        &self,
+        user_id: UserId,
+        type_str: &str,
+        id: &str,
+        read: i64,
+        added_at: Option<&str>,
+    ) -> rusqlite::Result<()> {
+        self.conn.execute(
+            "INSERT INTO favorites (user_id, type, id, read, added_at)
+             VALUES (?1, ?2, ?3, ?4, COALESCE(?5, CURRENT_TIMESTAMP))",
+            rusqlite::params![user_id.0, type_str, id, read, added_at],
+        )?;
+        Ok(())
+    }
+
+    /// Set the read position, and `added_at` when given, regardless of the
+    /// current values. Returns whether anything changed.
+    pub fn overwrite_read(
+        &self,
+        user_id: UserId,
+        type_str: &str,
+        id: &str,
+        read: i64,
+        added_at: Option<&str>,
+    ) -> rusqlite::Result<bool> {
+        let changes = self.conn.execute(
+            "UPDATE favorites SET read = ?1, added_at = COALESCE(?2, added_at)
+             WHERE user_id = ?3 AND type = ?4 AND id = ?5
+                AND (read != ?1 OR ?2 IS NOT NULL AND ?2 != added_at)",
+            rusqlite::params![read, added_at, user_id.0, type_str, id],
+        )?;
+        Ok(changes > 0)
+    }
+
+    /// Move the read position forward to `read`, never back. Returns whether it moved.
+    pub fn raise_read(
+        &self,
+        user_id: UserId,
+        type_str: &str,
+        id: &str,
+        read: i64,
+    ) -> rusqlite::Result<bool> {
+        let changes = self.conn.execute(
+            "UPDATE favorites SET read = ?1
+             WHERE user_id = ?2 AND type = ?3 AND id = ?4 AND read < ?1",
+            rusqlite::params![read, user_id.0, type_str, id],
+        )?;
+        Ok(changes > 0)
+    }
+
+    /// Insert the novel unless it is already known. Novel metadata is shared across
+    /// users and kept fresh by sync, so an existing record is never overwritten.
+    pub fn insert_novel(&self, novel: &NewNovel) -> rusqlite::Result<bool> {
+        let changes = self.conn.execute(
+            "INSERT INTO novels (type, id, title, page, novelupdated_at) VALUES (?1, ?2, ?3, ?4, ?5)
+             ON CONFLICT(type, id) DO NOTHING",
+            rusqlite::params![
+                novel.type_str,
+                novel.id,
+                novel.title,
+                novel.page,
+                novel.novelupdated_at
+            ],
+        )?;
+        Ok(changes > 0)
+    }
+
+    /// Add the novel to the user's favorites, creating the novel if needed.
+    /// An existing favorite keeps its read position.
+    pub fn upsert(&self, user_id: UserId, novel: &NewNovel) -> rusqlite::Result<Upserted> {
+        let novel_created = self.insert_novel(novel)?;
+        let changes = self.conn.execute(
+            "INSERT INTO favorites (user_id, type, id) VALUES (?1, ?2, ?3)
+             ON CONFLICT(user_id, type, id) DO NOTHING",
+            rusqlite::params![user_id.0, novel.type_str, novel.id],
+        )?;
+        Ok(Upserted {
+            novel_created,
+            favorite_added: changes > 0,
+        })
+    }
+
+    /// Returns false if the user didn't have the favorite.
+    pub fn delete(&self, user_id: UserId, type_str: &str, id: &str) -> rusqlite::Result<bool> {
+        let changes = self.conn.execute(
+            "DELETE FROM favorites WHERE user_id = ?1 AND type = ?2 AND id = ?3",
+            rusqlite::params![user_id.0, type_str, id],
+        )?;
+        Ok(changes > 0)
+    }
+
+    /// Move the read position, unless another device already stored a later one
+    /// (last write wins by `progress.at`). Returns false if nothing was written,
+    /// because the update was stale or the user doesn't have the favorite.
+    pub fn set_progress(
+        &self,
+        user_id: UserId,
+        type_str: &str,
+        id: &str,
+        progress: &Progress,
+    ) -> rusqlite::Result<bool> {
+        let changes = self.conn.execute(
+            "UPDATE favorites SET read = ?1, read_paragraph = ?2, read_fraction = ?3, progress_at = ?4
+             WHERE user_id = ?5 AND type = ?6 AND id = ?7
+                AND (progress_at IS NULL OR progress_at <= ?4)",
+            rusqlite::params![
+                progress.read,
+                progress.paragraph,
+                progress.fraction,
+                progress.at,
+                user_id.0,
+                type_str,
+                id
+            ],
+        )?;
+        Ok(changes > 0)
+    }
+
+    /// Every novel of a site that is in at least one user's favorites.
+    pub fn novel_ids(&self, type_str: &str) -> rusqlite::Result<Vec<String>> {
+        let mut stmt = self
+            .conn
+            .prepare("SELECT DISTINCT id FROM favorites WHERE type = ?1")?;
+        let ids = stmt
+            .query_map([type_str], |row| row.get(0))?
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(ids)
+    }
+
+    /// The user's favorites with an unread count inside the filter's window,
+    /// most recently updated first.
+    pub fn feed_items(
+        &self,
+        user_id: UserId,
+        filter: &FeedItemsFilter,
+    ) -> rusqlite::Result<Vec<Favorite>> {
+        let mut stmt = self.conn.prepare(&format!(
+            "{} WHERE f.user_id = ?1 AND n.page - f.read >= ?2 AND n.page - f.read <= ?3
+                AND (?4 IS NULL OR (',' || ?4 || ',') LIKE '%,' || f.type || ',%')
+                AND (?5 IS NULL OR f.type = ?5 AND f.id = ?6)
+                AND (?7 IS NULL OR {})
+             ORDER BY n.novelupdated_at DESC NULLS LAST",
+            FAVORITE_SELECT,
+            has_tag("?7")
+        ))?;
+        let rows = stmt
+            .query_map(
+                rusqlite::params![
+                    user_id.0,
+                    filter.min_unread,
+                    filter.max_unread,
+                    filter.sites,
+                    filter.novel.map(|(t, _)| t),
+                    filter.novel.map(|(_, id)| id),
+                    filter.tag
+                ],
+                map_favorite_row,
+            )?
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(rows)
+    }
+
+    /// Write one fetched datum to `novels` and clear its "not found" streak.
+    /// Only moves `novelupdated_at` to `now` when the page count increased.
+    /// Returns the number of changed rows and, if the page count grew, the update.
+    pub fn apply_datum(
+        &self,
+        type_str: &str,
+        datum: &Value,
+        now: &str,
+    ) -> rusqlite::Result<(usize, Option<NovelUpdate>)> {
+        let id = datum["id"].as_str().unwrap_or_default();
+        let title = datum["title"].as_str();
+        let new_page = datum["pages"].as_array().map(|a| a.len() as i64);
+
+        self.clear_missing(type_str, id)?;
+        if title.is_none() && new_page.is_none() {
+            return Ok((0, None));
+        }
+
+        let previous: Option<(String, i64)> = self
+            .conn
+            .query_row(
+                "SELECT title, page FROM novels WHERE type = ?1 AND id = ?2",
+                rusqlite::params![type_str, id],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .optional()?;
+        let changed = self.conn.execute(
+            "UPDATE novels SET
+                title = COALESCE(?1, title),
+                page = COALESCE(?2, page),
+                novelupdated_at = CASE WHEN ?2 > page THEN ?3 ELSE novelupdated_at END
+             WHERE type = ?4 AND id = ?5
+                AND (?2 IS NOT NULL AND ?2 != page OR ?1 IS NOT NULL AND ?1 != title)",
+            rusqlite::params![title, new_page, now, type_str, id],
+        )?;
+
+        let update = match (previous, new_page) {
+            (Some((old_title, old_page)), Some(new_page)) if new_page > old_page => {
+                Some(NovelUpdate {
+                    type_str: type_str.to_string(),
+                    id: id.to_string(),
+                    title: title.map(str::to_string).unwrap_or(old_title),
+                    old_page,
+                    new_page,
+                })
+            }
+            _ => None,
+        };
+        Ok((changed, update))
+    }
+
+    /// Record one more consecutive "not found" result for a novel.
+    /// Once the streak reaches `GONE_THRESHOLD`, `gone_at` is set to today's date (kept
+    /// from the first time it crossed, so later misses don't move it).
+    pub fn mark_missing(&self, type_str: &str, id: &str) -> rusqlite::Result<usize> {
+        let today = Utc::now().format("%Y-%m-%d").to_string();
+        self.conn.execute(
+            "UPDATE novels SET
+                missing_count = missing_count + 1,
+                gone_at = CASE WHEN missing_count + 1 >= ?1 THEN COALESCE(gone_at, ?2) ELSE gone_at END
+             WHERE type = ?3 AND id = ?4",
+            rusqlite::params![GONE_THRESHOLD, today, type_str, id],
+        )
+    }
+
+    /// Reset the "not found" streak after the novel was seen upstream again.
+    pub fn clear_missing(&self, type_str: &str, id: &str) -> rusqlite::Result<usize> {
+        self.conn.execute(
+            "UPDATE novels SET missing_count = 0, gone_at = NULL
+             WHERE type = ?1 AND id = ?2 AND (missing_count > 0 OR gone_at IS NOT NULL)",
+            rusqlite::params![type_str, id],
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn insert_favorite(conn: &Connection, user_id: i64, id: &str) {
+        conn.execute(
+            "INSERT OR IGNORE INTO users (id, email) VALUES (?1, ?2)",
+            rusqlite::params![user_id, format!("u{}@example.com", user_id)],
+        )
+        .unwrap();
+        conn.execute(
+            "INSERT OR IGNORE INTO novels (type, id, title, page) VALUES ('narou', ?1, 'Novel', 10)",
+            [id],
+        )
+        .unwrap();
+        conn.execute(
+            "INSERT INTO favorites (user_id, type, id) VALUES (?1, 'narou', ?2)",
+            rusqlite::params![user_id, id],
+        )
+        .unwrap();
+    }
+
+    fn missing_state(conn: &Connection, id: &str) -> (i64, Option<String>) {
+        conn.query_row(
+            "SELECT missing_count, gone_at FROM novels WHERE type = 'narou' AND id = ?1",
+            [id],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )
+        .unwrap()
+    }
+
+    fn progress(read: i64, at: &str) -> Progress {
+        Progress {
+            read,
+            paragraph: None,
+            fraction: None,
+            at: at.into(),
+        }
+    }
+
+    fn novel<'a>(id: &'a str, title: &'a str, page: i64) -> NewNovel<'a> {
+        NewNovel {
+            type_str: "narou",
+            id,
+            title,
+            page,
+            novelupdated_at: None,
+        }
+    }
+
+    #[test]
+    fn upsert_keeps_existing_metadata_and_progress() {
+        let conn = crate::db::open_memory();
+        insert_favorite(&conn, 1, "n1");
+        let repo = FavoritesRepo::new(&conn);
+        repo.set_progress(
+            UserId(1),
+            "narou",
+            "n1",
+            &progress(4, "2026-03-14T00:00:00.000Z"),
+        )
+        .unwrap();
+
+        let result = repo
+            .upsert(UserId(1), &novel("n1", "Client title", 99))
+            .unwrap();
+        assert_eq!(
+            result,
+            Upserted {
+                novel_created: false,
+                favorite_added: false
+            }
+        );
+        let favorite = repo.get(UserId(1), "narou", "n1").unwrap().unwrap();
+        assert_eq!(
+            (favorite.title.as_str(), favorite.page, favorite.read),
+            ("Novel", 10, 4)
+        );
+
+        let result = repo.upsert(UserId(1), &novel("n2", "Fresh", 3)).unwrap();
+        assert!(result.novel_created && result.favorite_added);
+        assert_eq!(
+            repo.list(UserId(1), &FavoritesFilter::default())
+                .unwrap()
+                .len(),
+            2
+        );
+    }
+
+    #[test]
+    fn list_orders_by_update_time_with_unknown_last() {
+        let conn = crate::db::open_memory();
+        for id in ["n1", "n2", "n3"] {
+            insert_favorite(&conn, 1, id);
+        }
+        conn.execute_batch(
+            "UPDATE novels SET novelupdated_at = '2026-01-01 00:00:00' WHERE id = 'n1';
+             UPDATE novels SET novelupdated_at = '2026-02-01 00:00:00' WHERE id = 'n3';",
+        )
+        .unwrap();
+        let ids: Vec<String> = FavoritesRepo::new(&conn)
+            .list(UserId(1), &FavoritesFilter::default())
+            .unwrap()
+            .into_iter()
+            .map(|f| f.id)
+            .collect();
+        assert_eq!(ids, ["n3", "n1", "n2"]);
+    }
+
+    #[test]
+    fn list_filters_by_tag_and_status_in_manual_order() {
+        let conn = crate::db::open_memory();
+        for id in ["n1", "n2", "n3"] {
+            insert_favorite(&conn, 1, id);
+        }
+        conn.execute_batch(
+            "UPDATE novels SET gone_at = '2026-03-01' WHERE id = 'n3';
+             INSERT INTO tags (id, user_id, name) VALUES (1, 1, 'reading');
+             INSERT INTO favorite_tags (user_id, type, id, tag_id) VALUES (1, 'narou', 'n1', 1);
+             INSERT INTO favorite_tags (user_id, type, id, tag_id) VALUES (1, 'narou', 'n3', 1);",
+        )
+        .unwrap();
+        let repo = FavoritesRepo::new(&conn);
+        let ids = |filter: FavoritesFilter| -> Vec<String> {
+            repo.list(UserId(1), &filter)
+                .unwrap()
+                .into_iter()
+                .map(|f| f.id)
+                .collect()
+        };
+        assert_eq!(
+            ids(FavoritesFilter {
+                tag: Some("reading"),
+                status: Some(FavoriteStatus::Active),
+                ..Default::default()
+            }),
+            ["n1"]
+        );
+        assert_eq!(
+            ids(FavoritesFilter {
+                status: Some(FavoriteStatus::Gone),
+                ..Default::default()
+            }),
+            ["n3"]
+        );
+
+        repo.set_notes(UserId(1), "narou", "n2", Some("100% worth it"), Some(5))
+            .unwrap();
+        let search = |q| FavoritesFilter {
+            query: Some(q),
+            ..Default::default()
+        };
+        assert_eq!(ids(search("100%")), ["n2"]);
+        assert!(ids(search("0_%")).is_empty(), "wildcards match literally");
+        assert_eq!(
+            ids(search("READ")),
+            ["n1", "n3"],
+            "tag names, case-insensitive"
+        );
+        assert_eq!(ids(search("Novel worth")), ["n2"], "every word must match");
+
+        let order = [
+            ("narou".to_string(), "n3".to_string()),
+            ("narou".to_string(), "n2".to_string()),
+        ];
+        assert_eq!(repo.set_order(UserId(1), &order).unwrap(), 2);
+        let manual = FavoritesFilter {
+            sort: FavoriteSort::Manual,
+            ..Default::default()
+        };
+        assert_eq!(ids(manual), ["n3", "n2", "n1"]);
+
+        let favorite = repo.get(UserId(1), "narou", "n3").unwrap().unwrap();
+        assert_eq!(
+            (favorite.sort_order, favorite.tags),
+            (Some(0), vec!["reading".to_string()])
+        );
+        // Removing the favorite drops its tag assignments
+        repo.delete(UserId(1), "narou", "n1").unwrap();
+        let left: i64 = conn
+            .query_row("SELECT COUNT(*) FROM favorite_tags", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(left, 1);
+    }
+
+    #[test]
+    fn cursor_pages_cover_every_sort_without_gaps() {
+        let conn = crate::db::open_memory();
+        for id in ["n1", "n2", "n3", "n4", "n5"] {
+            insert_favorite(&conn, 1, id);
+        }
+        conn.execute_batch(
+            "UPDATE novels SET novelupdated_at = '2026-01-01 00:00:00' WHERE id IN ('n1', 'n4');
+             UPDATE novels SET novelupdated_at = '2026-02-01 00:00:00', title = 'Another' WHERE id = 'n3';
+             UPDATE favorites SET read = 10 WHERE id = 'n2';
+             UPDATE favorites SET read = 4, sort_order = 0 WHERE id = 'n5';
+             UPDATE favorites SET added_at = '2026-01-01 00:00:00';
+             UPDATE favorites SET added_at = '2026-03-01 00:00:00' WHERE id = 'n4';",
+        )
+        .unwrap();
+        let repo = FavoritesRepo::new(&conn);
+        for sort in [
+            FavoriteSort::Updated,
+            FavoriteSort::Added,
+            FavoriteSort::Title,
+            FavoriteSort::Unread,
+            FavoriteSort::Manual,
+        ] {
+            let all = FavoritesFilter {
+                sort,
+                ..Default::default()
+            };
+            let expected: Vec<String> = repo
+                .list(UserId(1), &all)
+                .unwrap()
+                .into_iter()
+                .map(|f| f.id)
+                .collect();
+            let mut paged = Vec::new();
+            let mut cursor: Option<FavoritesCursor> = None;
+            loop {
+                let filter = FavoritesFilter {
+                    sort,
+                    after: cursor.as_ref(),
+                    limit: Some(2),
+                    ..Default::default()
+                };
+                let page = repo.list(UserId(1), &filter).unwrap();
+                let Some(last) = page.last() else { break };
+                // Round-trip through the client-facing form
+                let encoded = FavoritesCursor::after(sort, last).encode();
+                cursor = Some(FavoritesCursor::decode(sort, &encoded).unwrap());
+                paged.extend(page.into_iter().map(|f| f.id));
+            }
+            assert_eq!(paged, expected, "{:?}", sort);
+        }
+        let ids = |sort| -> Vec<String> {
+            let filter = FavoritesFilter {
+                sort,
+                ..Default::default()
+            };
+            repo.list(UserId(1), &filter)
+                .unwrap()
+                .into_iter()
+                .map(|f| f.id)
+                .collect()
+        };
+        assert_eq!(ids(FavoriteSort::Added)[0], "n4");
+        assert_eq!(ids(FavoriteSort::Title)[0], "n3");
+        assert_eq!(ids(FavoriteSort::Unread), ["n3", "n1", "n4", "n5", "n2"]);
+        assert_eq!(ids(FavoriteSort::Manual)[0], "n5");
+
+        let updated = FavoritesCursor::after(
+            FavoriteSort::Updated,
+            &repo.list(UserId(1), &FavoritesFilter::default()).unwrap()[0],
+        )
+        .encode();
+        assert!(FavoritesCursor::decode(FavoriteSort::Title, &updated).is_none());
+        assert!(FavoritesCursor::decode(FavoriteSort::Updated, "not a cursor").is_none());
+    }
+
+    #[test]
+    fn list_and_count_filter_by_site_and_unread() {
+        let conn = crate::db::open_memory();
+        for id in ["n1", "n2", "n3"] {
+            insert_favorite(&conn, 1, id);
+        }
+        conn.execute_batch(
+            "INSERT INTO novels (type, id, title, page) VALUES ('kakuyomu', 'k1', 'K', 5);
+             INSERT INTO favorites (user_id, type, id, read) VALUES (1, 'kakuyomu', 'k1', 5);
+             UPDATE favorites SET read = 10 WHERE id = 'n2';",
+        )
+        .unwrap();
+        let repo = FavoritesRepo::new(&conn);
+        let caught_up = FavoritesFilter {
+            unread: Some(false),
+            ..Default::default()
+        };
+        assert_eq!(repo.count(UserId(1), &caught_up).unwrap(), 2);
+        let narou_unread = FavoritesFilter {
+            sites: Some("narou,nocturne"),
+            unread: Some(true),
+            limit: Some(1),
+            ..Default::default()
+        };
+        assert_eq!(repo.list(UserId(1), &narou_unread).unwrap().len(), 1);
+        assert_eq!(repo.count(UserId(1), &narou_unread).unwrap(), 2);
+        let kakuyomu = FavoritesFilter {
+            sites: Some("kakuyomu"),
+            ..Default::default()
+        };
+        assert_eq!(repo.list(UserId(1), &kakuyomu).unwrap()[0].id, "k1");
+    }
+
+    #[test]
+    fn delete_and_set_progress_only_touch_the_users_favorite() {
+        let conn = crate::db::open_memory();
+        insert_favorite(&conn, 1, "n1");
+        insert_favorite(&conn, 2, "n1");
+        let repo = FavoritesRepo::new(&conn);
+        let at = "2026-03-14T00:00:00.000Z";
+        assert!(!repo
+            .set_progress(UserId(1), "narou", "n2", &progress(1, at))
+            .unwrap());
+        assert!(repo
+            .set_progress(UserId(2), "narou", "n1", &progress(7, at))
+            .unwrap());
+        assert_eq!(repo.get(UserId(1), "narou", "n1").unwrap().unwrap().read, 0);
+
+        assert!(repo.delete(UserId(1), "narou", "n1").unwrap());
+        assert!(!repo.delete(UserId(1), "narou", "n1").unwrap());
+        assert!(repo.get(UserId(2), "narou", "n1").unwrap().is_some());
+        assert_eq!(repo.novel_ids("narou").unwrap(), ["n1"]);
+    }
+
+    #[test]
+    fn set_progress_keeps_the_latest_write() {
+        let conn = crate::db::open_memory();
+        insert_favorite(&conn, 1, "n1");
+        let repo = FavoritesRepo::new(&conn);
+        let phone = Progress {
+            read: 12,
+            paragraph: Some(40),
+            fraction: Some(0.5),
+            at: "2026-03-14T09:00:00.000Z".into(),
+        };
+        assert!(repo.set_progress(UserId(1), "narou", "n1", &phone).unwrap());
+
+        // A laptop that synced late reports an older position: ignored
+        let stale = progress(10, "2026-03-14T08:59:59.999Z");
+        assert!(!repo.set_progress(UserId(1), "narou", "n1", &stale).unwrap());
+        let favorite = repo.get(UserId(1), "narou", "n1").unwrap().unwrap();
+        assert_eq!(
+            favorite.progress_json(),
+            json!({
+                "read": 12,
+                "position": {"paragraph": 40, "fraction": 0.5},
+                "progress_at": "2026-03-14T09:00:00.000Z",
+            })
+        );
+
+        // A newer write wins even when it moves backwards, and clears the position
+        let reread = progress(3, "2026-03-14T10:00:00.000Z");
+        assert!(repo
+            .set_progress(UserId(1), "narou", "n1", &reread)
+            .unwrap());
+        let favorite = repo.get(UserId(1), "narou", "n1").unwrap().unwrap();
+        assert_eq!(favorite.read, 3);
+        assert_eq!(favorite.to_json()["position"], Value::Null);
+    }
+
+    #[test]
+    fn to_json_reports_gone_status() {
+        let conn = crate::db::open_memory();
+        insert_favorite(&conn, 1, "n1");
+        conn.execute(
+            "UPDATE novels SET gone_at = '2026-03-01' WHERE id = 'n1'",
+            [],
+        )
+        .unwrap();
+        let favorite = FavoritesRepo::new(&conn)
+            .get(UserId(1), "narou", "n1")
+            .unwrap()
+            .unwrap()
+            .to_json();
+        assert_eq!(favorite["status"], "gone");
+        assert_eq!(favorite["gone_at"], "2026-03-01");
+        assert_eq!(favorite["type"], "narou");
+    }
+
+    #[test]
+    fn feed_items_filter_by_unread_site_and_novel() {
+        let conn = crate::db::open_memory();
+        conn.execute_batch(
+            "INSERT INTO novels (type, id, title, page) VALUES
+                ('narou', 'n1', 'One', 10), ('narou', 'n2', 'Two', 10), ('kakuyomu', 'k1', 'Three', 10);
+             INSERT INTO favorites (user_id, type, id, read) VALUES
+                (1, 'narou', 'n1', 9), (1, 'narou', 'n2', 2), (1, 'kakuyomu', 'k1', 5);",
+        )
+        .unwrap();
+        let repo = FavoritesRepo::new(&conn);
+        let ids = |filter: FeedItemsFilter| -> Vec<String> {
+            repo.feed_items(UserId(1), &filter)
+                .unwrap()
+                .into_iter()
+                .map(|f| f.id)
+                .collect()
+        };
+        let window = |min_unread, max_unread| FeedItemsFilter {
+            min_unread,
+            max_unread,
+            sites: None,
+            novel: None,
+            tag: None,
+        };
+        assert_eq!(ids(window(1, 5)).len(), 2);
+        assert_eq!(ids(window(6, 10)), ["n2"]);
+        assert_eq!(
+            ids(FeedItemsFilter {
+                sites: Some("kakuyomu"),
+                ..window(0, 10)
+            }),
+            ["k1"]
+        );
+        assert_eq!(
+            ids(FeedItemsFilter {
+                novel: Some(("narou", "n1")),
+                ..window(0, 10)
+            }),
+            ["n1"]
+        );
+    }
+
+    #[test]
+    fn apply_datum_reports_new_pages_once() {
+        let conn = crate::db::open_memory();
+        insert_favorite(&conn, 1, "n1");
+        let repo = FavoritesRepo::new(&conn);
+        let datum =
+            json!({"id": "n1", "title": "Renamed", "pages": [1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11]});
+
+        let (changed, update) = repo
+            .apply_datum("narou", &datum, "2026-03-14 00:00:00")
+            .unwrap();
+        assert_eq!(changed, 1);
+        let update = update.unwrap();
+        assert_eq!(
+            (update.old_page, update.new_page, update.title.as_str()),
+            (10, 11, "Renamed")
+        );
+
+        let (changed, update) = repo
+            .apply_datum("narou", &datum, "2026-03-15 00:00:00")
+            .unwrap();
+        assert_eq!((changed, update.is_none()), (0, true));
+        let favorite = repo.get(UserId(1), "narou", "n1").unwrap().unwrap();
+        assert_eq!(
+            favorite.novelupdated_at.as_deref(),
+            Some("2026-03-14 00:00:00")
+        );
+    }
+
+    #[test]
+    fn mark_missing_sets_gone_at_after_threshold() {
+        let conn = crate::db::open_memory();
+        insert_favorite(&conn, 1, "n1");
+        let repo = FavoritesRepo::new(&conn);
+
+        for _ in 0..GONE_THRESHOLD - 1 {
+            repo.mark_missing("narou", "n1").unwrap();
+        }
+        let (count, gone_at) = missing_state(&conn, "n1");
+        assert_eq!(count, GONE_THRESHOLD - 1);
+        assert!(gone_at.is_none(), "must not be gone before the threshold");
+
+        repo.mark_missing("narou", "n1").unwrap();
+        let (_, gone_at) = missing_state(&conn, "n1");
+        assert_eq!(gone_at, Some(Utc::now().format("%Y-%m-%d").to_string()));
+    }
+
+    #[test]
+    fn mark_missing_keeps_first_gone_date() {
+        let conn = crate::db::open_memory();
+        insert_favorite(&conn, 1, "n1");
+        conn.execute(
+            "UPDATE novels SET missing_count = 5, gone_at = '2026-01-01' WHERE id = 'n1'",
+            [],
+        )
+        .unwrap();
+
+        FavoritesRepo::new(&conn)
+            .mark_missing("narou", "n1")
+            .unwrap();
+        let (count, gone_at) = missing_state(&conn, "n1");
+        assert_eq!(count, 6);
+        assert_eq!(gone_at.as_deref(), Some("2026-01-01"));
+    }
+
+    #[test]
+    fn mark_missing_writes_once_for_all_users() {
+        let conn = crate::db::open_memory();
+        insert_favorite(&conn, 1, "n1");
+        insert_favorite(&conn, 2, "n1");
+        assert_eq!(
+            FavoritesRepo::new(&conn)
+                .mark_missing("narou", "n1")
+                .unwrap(),
+            1
+        );
+    }
+
+    #[test]
+    fn clear_missing_resets_streak() {
+        let conn = crate::db::open_memory();
+        insert_favorite(&conn, 1, "n1");
+        let repo = FavoritesRepo::new(&conn);
+        for _ in 0..GONE_THRESHOLD {
+            repo.mark_missing("narou", "n1").unwrap();
+        }
+
+        assert_eq!(repo.clear_missing("narou", "n1").unwrap(), 1);
+        assert_eq!(missing_state(&conn, "n1"), (0, None));
+        // Nothing to reset the second time
+        assert_eq!(repo.clear_missing("narou", "n1").unwrap(), 0);
+    }
+
+    #[test]
+    fn restore_helpers_move_read_positions() {
+        let conn = crate::db::open_memory();
+        insert_favorite(&conn, 1, "n1");
+        conn.execute(
+            "INSERT INTO novels (type, id, title, page) VALUES ('narou', 'n2', 'Two', 10)",
+            [],
+        )
+        .unwrap();
+        let repo = FavoritesRepo::new(&conn);
+        assert_eq!(repo.read_position(UserId(1), "narou", "n2").unwrap(), None);
+        repo.insert_favorite(UserId(1), "narou", "n2", 4, Some("2020-01-01 00:00:00"))
+            .unwrap();
+        assert_eq!(
+            repo.read_position(UserId(1), "narou", "n2").unwrap(),
+            Some(4)
+        );
+
+        assert!(!repo.raise_read(UserId(1), "narou", "n2", 3).unwrap());
+        assert!(repo.raise_read(UserId(1), "narou", "n2", 6).unwrap());
+        assert!(!repo
+            .overwrite_read(UserId(1), "narou", "n2", 6, None)
+            .unwrap());
+        assert!(repo
+            .overwrite_read(UserId(1), "narou", "n2", 2, None)
+            .unwrap());
+        assert_eq!(
+            repo.read_position(UserId(1), "narou", "n2").unwrap(),
+            Some(2)
+        );
+
+        // Oldest added first
+        let ids: Vec<String> = repo
+            .export_rows(UserId(1))
+            .unwrap()
+            .into_iter()
+            .map(|f| f.id)
+            .collect();
+        assert_eq!(ids, ["n2", "n1"]);
+    }
+
+    #[test]
+    fn merge_notes_keeps_fields_left_empty() {
+        let conn = crate::db::open_memory();
+        insert_favorite(&conn, 1, "n1");
+        let repo = FavoritesRepo::new(&conn);
+        repo.set_notes(UserId(1), "narou", "n1", Some("good"), Some(3))
+            .unwrap();
+        assert!(!repo
+            .merge_notes(UserId(1), "narou", "n1", None, None)
+            .unwrap());
+        assert!(!repo
+            .merge_notes(UserId(1), "narou", "n1", Some("good"), None)
+            .unwrap());
+        assert!(repo
+            .merge_notes(UserId(1), "narou", "n1", None, Some(5))
+            .unwrap());
+        let favorite = repo.get(UserId(1), "narou", "n1").unwrap().unwrap();
+        assert_eq!(
+            (favorite.notes.as_deref(), favorite.rating),
+            (Some("good"), Some(5))
+        );
+    }
+}
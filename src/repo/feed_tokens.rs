@@ -0,0 +1,79 @@
+use crate::auth::{hash_token, UserId};
+use rusqlite::{Connection, OptionalExtension};
+
+/// One token per user, letting feed readers fetch that user's feeds without a login.
+pub struct FeedTokensRepo<'c> {
+    conn: &'c Connection,
+}
+
+impl<'c> FeedTokensRepo<'c> {
+    pub fn new(conn: &'c Connection) -> Self {
+        Self { conn }
+    }
+
+    /// Owner of a feed token, if it is valid.
+    pub fn user(&self, token: &str) -> rusqlite::Result<Option<UserId>> {
+        self.conn
+            .query_row(
+                "SELECT user_id FROM feed_tokens WHERE token_hash = ?1",
+                [hash_token(token)],
+                |row| row.get(0).map(UserId),
+            )
+            .optional()
+    }
+
+    /// When the user's current token was issued, if they have one.
+    pub fn created_at(&self, user_id: UserId) -> rusqlite::Result<Option<String>> {
+        self.conn
+            .query_row(
+                "SELECT created_at FROM feed_tokens WHERE user_id = ?1",
+                [user_id.0],
+                |row| row.get(0),
+            )
+            .optional()
+    }
+
+    /// Store `token` as the user's only token, replacing any previous one.
+    /// Returns the issue time.
+    pub fn replace(&self, user_id: UserId, token: &str) -> rusqlite::Result<String> {
+        self.conn.execute(
+            "INSERT INTO feed_tokens (user_id, token_hash) VALUES (?1, ?2)
+             ON CONFLICT(user_id) DO UPDATE SET
+                token_hash = excluded.token_hash, created_at = CURRENT_TIMESTAMP",
+            rusqlite::params![user_id.0, hash_token(token)],
+        )?;
+        self.conn.query_row(
+            "SELECT created_at FROM feed_tokens WHERE user_id = ?1",
+            [user_id.0],
+            |row| row.get(0),
+        )
+    }
+
+    /// Returns false if the user had no token.
+    pub fn delete(&self, user_id: UserId) -> rusqlite::Result<bool> {
+        let changes = self
+            .conn
+            .execute("DELETE FROM feed_tokens WHERE user_id = ?1", [user_id.0])?;
+        Ok(changes > 0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn replacing_a_token_invalidates_the_old_one() {
+        let conn = crate::db::open_memory();
+        let tokens = FeedTokensRepo::new(&conn);
+        assert_eq!(tokens.created_at(UserId(1)).unwrap(), None);
+        tokens.replace(UserId(1), "first").unwrap();
+        assert_eq!(tokens.user("first").unwrap(), Some(UserId(1)));
+        tokens.replace(UserId(1), "second").unwrap();
+        assert_eq!(tokens.user("first").unwrap(), None);
+        assert_eq!(tokens.user("second").unwrap(), Some(UserId(1)));
+        assert!(tokens.delete(UserId(1)).unwrap());
+        assert!(!tokens.delete(UserId(1)).unwrap());
+        assert_eq!(tokens.user("second").unwrap(), None);
+    }
+}
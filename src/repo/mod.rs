@@ -0,0 +1,33 @@
+//! SQL for each table, grouped by what it stores. Handlers and background tasks
+//! call these inside `Db::read`/`Db::write` closures instead of writing queries inline.
+
+mod bookmarks;
+mod chapter_lengths;
+mod chapter_updates;
+mod digest_settings;
+mod favorites;
+mod feed_tokens;
+mod push_subscriptions;
+mod reading_events;
+mod recent_reads;
+mod settings;
+mod tags;
+mod users;
+mod webhooks;
+
+pub use bookmarks::{BookmarkFields, BookmarksRepo};
+pub use chapter_lengths::{Backlog, ChapterLengthsRepo};
+pub use chapter_updates::{ChapterUpdatesRepo, DigestEntry};
+pub use digest_settings::{DigestSettings, DigestSettingsRepo};
+pub use favorites::{
+    Favorite, FavoriteSort, FavoriteStatus, FavoritesCursor, FavoritesFilter, FavoritesRepo,
+    FeedItemsFilter, NewNovel, Progress, PROGRESS_TIME_FORMAT,
+};
+pub use feed_tokens::FeedTokensRepo;
+pub use push_subscriptions::PushSubscriptionsRepo;
+pub use reading_events::{DayReads, HistoryFilter, NovelReads, ReadingEventsRepo, SessionTotals};
+pub use recent_reads::RecentReadsRepo;
+pub use settings::SettingsRepo;
+pub use tags::TagsRepo;
+pub use users::UsersRepo;
+pub use webhooks::WebhooksRepo;
@@ -0,0 +1,137 @@
+use crate::auth::UserId;
+use crate::push::Subscription;
+use crate::sync::NovelUpdate;
+use rusqlite::Connection;
+
+/// A browser to notify about an update, with its owner's read position.
+pub struct PushTarget {
+    pub subscription: Subscription,
+    pub read: i64,
+}
+
+pub struct PushSubscriptionsRepo<'c> {
+    conn: &'c Connection,
+}
+
+impl<'c> PushSubscriptionsRepo<'c> {
+    pub fn new(conn: &'c Connection) -> Self {
+        Self { conn }
+    }
+
+    /// Register a browser. Re-registering an endpoint updates its keys. Returns
+    /// false, changing nothing, if the endpoint belongs to another user.
+    pub fn upsert(
+        &self,
+        user_id: UserId,
+        endpoint: &str,
+        p256dh: &str,
+        auth: &str,
+    ) -> rusqlite::Result<bool> {
+        let changes = self.conn.execute(
+            "INSERT INTO push_subscriptions (user_id, endpoint, p256dh, auth) VALUES (?1, ?2, ?3, ?4)
+             ON CONFLICT(endpoint) DO UPDATE SET p256dh = excluded.p256dh, auth = excluded.auth
+             WHERE push_subscriptions.user_id = excluded.user_id",
+            rusqlite::params![user_id.0, endpoint, p256dh, auth],
+        )?;
+        Ok(changes > 0)
+    }
+
+    /// Returns false if the user has no subscription with this endpoint.
+    pub fn delete(&self, user_id: UserId, endpoint: &str) -> rusqlite::Result<bool> {
+        let changes = self.conn.execute(
+            "DELETE FROM push_subscriptions WHERE endpoint = ?1 AND user_id = ?2",
+            rusqlite::params![endpoint, user_id.0],
+        )?;
+        Ok(changes > 0)
+    }
+
+    /// Subscriptions of every user who has the updated novel in favorites.
+    pub fn targets(&self, update: &NovelUpdate) -> rusqlite::Result<Vec<PushTarget>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT s.endpoint, s.p256dh, s.auth, f.read FROM push_subscriptions s
+             JOIN favorites f ON f.user_id = s.user_id
+             WHERE f.type = ?1 AND f.id = ?2",
+        )?;
+        let rows = stmt
+            .query_map(rusqlite::params![update.type_str, update.id], |row| {
+                Ok(PushTarget {
+                    subscription: Subscription {
+                        endpoint: row.get(0)?,
+                        p256dh: row.get(1)?,
+                        auth: row.get(2)?,
+                    },
+                    read: row.get(3)?,
+                })
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(rows)
+    }
+
+    /// Drop a subscription the push service reported as gone, whoever owns it.
+    pub fn delete_expired(&self, endpoint: &str) -> rusqlite::Result<()> {
+        self.conn.execute(
+            "DELETE FROM push_subscriptions WHERE endpoint = ?1",
+            [endpoint],
+        )?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn resubscribing_updates_keys_but_never_the_owner() {
+        let conn = crate::db::open_memory();
+        conn.execute(
+            "INSERT INTO users (id, email) VALUES (2, 'alice@example.com')",
+            [],
+        )
+        .unwrap();
+        let subs = PushSubscriptionsRepo::new(&conn);
+        assert!(subs
+            .upsert(UserId(1), "https://push/1", "k1", "a1")
+            .unwrap());
+        assert!(subs
+            .upsert(UserId(1), "https://push/1", "k2", "a2")
+            .unwrap());
+        assert!(!subs
+            .upsert(UserId(2), "https://push/1", "k3", "a3")
+            .unwrap());
+        assert!(!subs.delete(UserId(2), "https://push/1").unwrap());
+        let (user_id, key): (i64, String) = conn
+            .query_row(
+                "SELECT user_id, p256dh FROM push_subscriptions WHERE endpoint = 'https://push/1'",
+                [],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .unwrap();
+        assert_eq!((user_id, key.as_str()), (1, "k2"));
+    }
+
+    #[test]
+    fn targets_only_match_users_with_the_favorite() {
+        let conn = crate::db::open_memory();
+        conn.execute_batch(
+            "INSERT INTO users (id, email) VALUES (2, 'alice@example.com');
+             INSERT INTO novels (type, id, title, page) VALUES ('narou', 'n1', 'Novel', 10);
+             INSERT INTO favorites (user_id, type, id, read) VALUES (2, 'narou', 'n1', 7);
+             INSERT INTO push_subscriptions (user_id, endpoint, p256dh, auth) VALUES
+                (1, 'https://push/guest', 'k', 'a'),
+                (2, 'https://push/alice', 'k', 'a');",
+        )
+        .unwrap();
+        let update = NovelUpdate {
+            type_str: "narou".into(),
+            id: "n1".into(),
+            title: "Novel".into(),
+            old_page: 10,
+            new_page: 12,
+        };
+        let targets = PushSubscriptionsRepo::new(&conn).targets(&update).unwrap();
+        assert_eq!(targets.len(), 1);
+        assert_eq!(targets[0].subscription.endpoint, "https://push/alice");
+        assert_eq!(targets[0].read, 7);
+    }
+}
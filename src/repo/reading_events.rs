@@ -0,0 +1,482 @@
+use crate::auth::UserId;
+use rusqlite::Connection;
+use serde_json::{json, Value};
+
+/// Repeats of the same chapter closer together than this are one reading.
+const DEDUPE_MINUTES: i64 = 60;
+
+/// One progress update, as kept in the reading history.
+#[derive(Debug, PartialEq)]
+pub struct ReadingEvent {
+    pub id: i64,
+    pub type_str: String,
+    pub novel_id: String,
+    /// Title from the shared novel metadata; None if the novel was never fetched
+    pub title: Option<String>,
+    pub page: i64,
+    pub device: Option<String>,
+    pub read_at: String,
+    /// Whether the novel is currently one of the user's favorites
+    pub favorite: bool,
+}
+
+impl ReadingEvent {
+    pub fn to_json(&self) -> Value {
+        json!({
+            "id": self.id,
+            "type": self.type_str,
+            "novel_id": self.novel_id,
+            "title": self.title,
+            "page": self.page,
+            "device": self.device,
+            "read_at": self.read_at,
+            "favorite": self.favorite,
+        })
+    }
+}
+
+/// Chapters read on one UTC day. Characters count only chapters whose text
+/// was fetched.
+#[derive(Debug, PartialEq)]
+pub struct DayReads {
+    /// `YYYY-MM-DD`
+    pub date: String,
+    pub chapters: i64,
+    pub characters: i64,
+}
+
+/// Everything the user read of one novel.
+#[derive(Debug, PartialEq)]
+pub struct NovelReads {
+    pub type_str: String,
+    pub novel_id: String,
+    pub title: Option<String>,
+    pub chapters: i64,
+    pub characters: i64,
+    pub last_read_at: String,
+}
+
+/// Reading sessions over the whole history. A session's last chapter has no
+/// end time, so only the chapters before it are timed, and only in sessions
+/// where all of those have a known length.
+#[derive(Debug, Default, PartialEq)]
+pub struct SessionTotals {
+    pub count: i64,
+    pub chapters: i64,
+    pub timed_chars: i64,
+    pub timed_secs: i64,
+}
+
+/// Which events `list` returns, newest first.
+pub struct HistoryFilter<'a> {
+    /// Only events for this `(type, id)`
+    pub novel: Option<(&'a str, &'a str)>,
+    /// Only events older than this event id (the previous page's last id)
+    pub before: Option<i64>,
+    pub limit: usize,
+}
+
+pub struct ReadingEventsRepo<'c> {
+    conn: &'c Connection,
+}
+
+impl<'c> ReadingEventsRepo<'c> {
+    pub fn new(conn: &'c Connection) -> Self {
+        Self { conn }
+    }
+
+    /// Log that the user reached `page`. Scrolling within a chapter sends many
+    /// progress updates, so nothing is written while the novel's latest event
+    /// has the same page and device and is less than `DEDUPE_MINUTES` older.
+    /// Returns whether an event was added.
+    pub fn record(
+        &self,
+        user_id: UserId,
+        type_str: &str,
+        novel_id: &str,
+        page: i64,
+        device: Option<&str>,
+        read_at: &str,
+    ) -> rusqlite::Result<bool> {
+        let changes = self.conn.execute(
+            "INSERT INTO reading_events (user_id, type, novel_id, page, device, read_at)
+             SELECT ?1, ?2, ?3, ?4, ?5, ?6
+             WHERE NOT EXISTS (
+                SELECT 1 FROM (
+                    SELECT page, device, read_at FROM reading_events
+                    WHERE user_id = ?1 AND type = ?2 AND novel_id = ?3
+                    ORDER BY id DESC LIMIT 1
+                ) WHERE page = ?4 AND device IS ?5
+                    AND (julianday(?6) - julianday(read_at)) * 1440 < ?7
+             )",
+            rusqlite::params![
+                user_id.0,
+                type_str,
+                novel_id,
+                page,
+                device,
+                read_at,
+                DEDUPE_MINUTES
+            ],
+        )?;
+        Ok(changes > 0)
+    }
+
+    pub fn list(
+        &self,
+        user_id: UserId,
+        filter: &HistoryFilter,
+    ) -> rusqlite::Result<Vec<ReadingEvent>> {
+        let (type_str, novel_id) = filter.novel.unzip();
+        let mut stmt = self.conn.prepare(
+            "SELECT e.id, e.type, e.novel_id, n.title, e.page, e.device, e.read_at,
+                EXISTS (SELECT 1 FROM favorites f
+                        WHERE f.user_id = e.user_id AND f.type = e.type AND f.id = e.novel_id)
+             FROM reading_events e
+             LEFT JOIN novels n ON n.type = e.type AND n.id = e.novel_id
+             WHERE e.user_id = ?1
+                AND (?2 IS NULL OR (e.type = ?2 AND e.novel_id = ?3))
+                AND (?4 IS NULL OR e.id < ?4)
+             ORDER BY e.id DESC
+             LIMIT ?5",
+        )?;
+        let rows = stmt
+            .query_map(
+                rusqlite::params![
+                    user_id.0,
+                    type_str,
+                    novel_id,
+                    filter.before,
+                    filter.limit as i64
+                ],
+                |row| {
+                    Ok(ReadingEvent {
+                        id: row.get(0)?,
+                        type_str: row.get(1)?,
+                        novel_id: row.get(2)?,
+                        title: row.get(3)?,
+                        page: row.get(4)?,
+                        device: row.get(5)?,
+                        read_at: row.get(6)?,
+                        favorite: row.get(7)?,
+                    })
+                },
+            )?
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(rows)
+    }
+
+    /// Reads per day, oldest first.
+    pub fn days(&self, user_id: UserId) -> rusqlite::Result<Vec<DayReads>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT date(e.read_at), COUNT(*), COALESCE(SUM(c.chars), 0)
+             FROM reading_events e
+             LEFT JOIN chapter_lengths c
+                ON c.type = e.type AND c.id = e.novel_id AND c.page = e.page
+             WHERE e.user_id = ?1
+             GROUP BY 1
+             ORDER BY 1",
+        )?;
+        let rows = stmt
+            .query_map([user_id.0], |row| {
+                Ok(DayReads {
+                    date: row.get(0)?,
+                    chapters: row.get(1)?,
+                    characters: row.get(2)?,
+                })
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(rows)
+    }
+
+    /// Reads per novel, most chapters first, then most recently read.
+    pub fn novels(&self, user_id: UserId) -> rusqlite::Result<Vec<NovelReads>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT e.type, e.novel_id, n.title, COUNT(*), COALESCE(SUM(c.chars), 0),
+                MAX(e.read_at)
+             FROM reading_events e
+             LEFT JOIN novels n ON n.type = e.type AND n.id = e.novel_id
+             LEFT JOIN chapter_lengths c
+                ON c.type = e.type AND c.id = e.novel_id AND c.page = e.page
+             WHERE e.user_id = ?1
+             GROUP BY e.type, e.novel_id
+             ORDER BY COUNT(*) DESC, MAX(e.read_at) DESC",
+        )?;
+        let rows = stmt
+            .query_map([user_id.0], |row| {
+                Ok(NovelReads {
+                    type_str: row.get(0)?,
+                    novel_id: row.get(1)?,
+                    title: row.get(2)?,
+                    chapters: row.get(3)?,
+                    characters: row.get(4)?,
+                    last_read_at: row.get(5)?,
+                })
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(rows)
+    }
+
+    /// Split the history into sessions wherever reads are more than `gap_secs`
+    /// apart, and total them.
+    pub fn sessions(&self, user_id: UserId, gap_secs: i64) -> rusqlite::Result<SessionTotals> {
+        self.conn.query_row(
+            "WITH ordered AS (
+                SELECT e.read_at, c.chars,
+                    ROW_NUMBER() OVER w AS n,
+                    CAST(ROUND((julianday(e.read_at) - julianday(LAG(e.read_at) OVER w))
+                        * 86400000) AS INTEGER) / 1000 AS gap
+                FROM reading_events e
+                LEFT JOIN chapter_lengths c
+                    ON c.type = e.type AND c.id = e.novel_id AND c.page = e.page
+                WHERE e.user_id = ?1
+                WINDOW w AS (ORDER BY e.read_at, e.id)
+             ), numbered AS (
+                SELECT read_at, chars, n,
+                    SUM(gap IS NULL OR gap > ?2) OVER (ORDER BY n) AS session
+                FROM ordered
+             ), marked AS (
+                SELECT read_at, chars, session,
+                    n = MAX(n) OVER (PARTITION BY session) AS is_last
+                FROM numbered
+             ), sessions AS (
+                SELECT COUNT(*) AS chapters,
+                    CAST(ROUND((julianday(MAX(read_at)) - julianday(MIN(read_at)))
+                        * 86400000) AS INTEGER) / 1000 AS secs,
+                    SUM(chars) FILTER (WHERE NOT is_last) AS timed_chars,
+                    COUNT(*) FILTER (WHERE NOT is_last AND chars IS NULL) AS unknown
+                FROM marked
+                GROUP BY session
+             )
+             SELECT COUNT(*), COALESCE(SUM(chapters), 0),
+                COALESCE(SUM(timed_chars) FILTER (WHERE chapters > 1 AND unknown = 0), 0),
+                COALESCE(SUM(secs) FILTER (WHERE chapters > 1 AND unknown = 0), 0)
+             FROM sessions",
+            rusqlite::params![user_id.0, gap_secs],
+            |row| {
+                Ok(SessionTotals {
+                    count: row.get(0)?,
+                    chapters: row.get(1)?,
+                    timed_chars: row.get(2)?,
+                    timed_secs: row.get(3)?,
+                })
+            },
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn all(repo: &ReadingEventsRepo) -> Vec<(String, i64)> {
+        let filter = HistoryFilter {
+            novel: None,
+            before: None,
+            limit: 100,
+        };
+        repo.list(UserId(1), &filter)
+            .unwrap()
+            .into_iter()
+            .map(|e| (e.novel_id, e.page))
+            .collect()
+    }
+
+    #[test]
+    fn record_skips_repeats_of_the_same_chapter() {
+        let conn = crate::db::open_memory();
+        let repo = ReadingEventsRepo::new(&conn);
+        let at = "2026-03-14T09:00:00.000Z";
+        assert!(repo
+            .record(UserId(1), "narou", "n1", 3, Some("phone"), at)
+            .unwrap());
+        assert!(!repo
+            .record(UserId(1), "narou", "n1", 3, Some("phone"), at)
+            .unwrap());
+        // Same chapter picked up on another device is worth a line
+        assert!(repo.record(UserId(1), "narou", "n1", 3, None, at).unwrap());
+        assert!(!repo.record(UserId(1), "narou", "n1", 3, None, at).unwrap());
+        assert!(repo.record(UserId(1), "narou", "n1", 4, None, at).unwrap());
+        // Going back to a chapter read earlier is logged again
+        assert!(repo.record(UserId(1), "narou", "n1", 3, None, at).unwrap());
+
+        assert_eq!(
+            all(&repo),
+            vec![
+                ("n1".to_string(), 3),
+                ("n1".to_string(), 4),
+                ("n1".to_string(), 3),
+                ("n1".to_string(), 3),
+            ]
+        );
+    }
+
+    #[test]
+    fn record_logs_rereading_the_same_chapter_later() {
+        let conn = crate::db::open_memory();
+        let repo = ReadingEventsRepo::new(&conn);
+        let record = |at| repo.record(UserId(1), "narou", "n1", 3, None, at).unwrap();
+        assert!(record("2026-03-14T09:00:00.000Z"));
+        assert!(!record("2026-03-14T09:59:00.000Z"));
+        assert!(record("2026-03-17T21:00:00.000Z"));
+        assert_eq!(all(&repo).len(), 2);
+    }
+
+    #[test]
+    fn list_pages_backwards_and_filters_by_novel() {
+        let conn = crate::db::open_memory();
+        conn.execute(
+            "INSERT INTO users (id, email) VALUES (2, 'alice@example.com')",
+            [],
+        )
+        .unwrap();
+        conn.execute(
+            "INSERT INTO novels (type, id, title, page) VALUES ('narou', 'n2', 'Two', 10)",
+            [],
+        )
+        .unwrap();
+        conn.execute(
+            "INSERT INTO favorites (user_id, type, id) VALUES (1, 'narou', 'n2')",
+            [],
+        )
+        .unwrap();
+        let repo = ReadingEventsRepo::new(&conn);
+        let at = "2026-03-14T09:00:00.000Z";
+        for (id, page) in [("n1", 1), ("n2", 1), ("n1", 2), ("n2", 2)] {
+            repo.record(UserId(1), "narou", id, page, None, at).unwrap();
+        }
+        repo.record(UserId(2), "narou", "n1", 9, None, at).unwrap();
+
+        let first = repo
+            .list(
+                UserId(1),
+                &HistoryFilter {
+                    novel: None,
+                    before: None,
+                    limit: 3,
+                },
+            )
+            .unwrap();
+        assert_eq!(first.len(), 3);
+        assert_eq!(first[0].title.as_deref(), Some("Two"));
+        assert!(first[0].favorite);
+        assert!(!first[1].favorite, "non-favorites stay in the history");
+
+        let rest = repo
+            .list(
+                UserId(1),
+                &HistoryFilter {
+                    novel: None,
+                    before: Some(first[2].id),
+                    limit: 3,
+                },
+            )
+            .unwrap();
+        assert_eq!(rest.len(), 1);
+        assert_eq!((rest[0].novel_id.as_str(), rest[0].page), ("n1", 1));
+
+        let n1 = repo
+            .list(
+                UserId(1),
+                &HistoryFilter {
+                    novel: Some(("narou", "n1")),
+                    before: None,
+                    limit: 10,
+                },
+            )
+            .unwrap();
+        assert_eq!(
+            n1.iter().map(|e| e.page).collect::<Vec<_>>(),
+            vec![2, 1],
+            "other users' events are not included"
+        );
+    }
+
+    #[test]
+    fn days_and_novels_tally_reads_with_known_lengths() {
+        let conn = crate::db::open_memory();
+        conn.execute(
+            "INSERT INTO novels (type, id, title, page) VALUES ('narou', 'n1', 'One', 10)",
+            [],
+        )
+        .unwrap();
+        let lengths = crate::repo::ChapterLengthsRepo::new(&conn);
+        lengths.record("narou", "n1", 1, 1000).unwrap();
+        lengths.record("narou", "n1", 2, 2000).unwrap();
+        let repo = ReadingEventsRepo::new(&conn);
+        for (id, page, at) in [
+            ("n1", 1, "2026-03-13T09:00:00.000Z"),
+            ("n1", 2, "2026-03-13T23:59:00.000Z"),
+            ("n2", 1, "2026-03-14T09:00:00.000Z"),
+        ] {
+            repo.record(UserId(1), "narou", id, page, None, at).unwrap();
+        }
+
+        assert_eq!(
+            repo.days(UserId(1)).unwrap(),
+            vec![
+                DayReads {
+                    date: "2026-03-13".into(),
+                    chapters: 2,
+                    characters: 3000,
+                },
+                DayReads {
+                    date: "2026-03-14".into(),
+                    chapters: 1,
+                    characters: 0,
+                },
+            ]
+        );
+        let novels = repo.novels(UserId(1)).unwrap();
+        assert_eq!(
+            novels
+                .iter()
+                .map(|n| (
+                    n.novel_id.as_str(),
+                    n.title.as_deref(),
+                    n.chapters,
+                    n.characters
+                ))
+                .collect::<Vec<_>>(),
+            vec![("n1", Some("One"), 2, 3000), ("n2", None, 1, 0)]
+        );
+        assert_eq!(novels[0].last_read_at, "2026-03-13T23:59:00.000Z");
+        assert!(repo.days(UserId(2)).unwrap().is_empty());
+    }
+
+    #[test]
+    fn sessions_time_all_but_the_last_chapter() {
+        let conn = crate::db::open_memory();
+        let lengths = crate::repo::ChapterLengthsRepo::new(&conn);
+        for page in 1..=3 {
+            lengths.record("narou", "n1", page, 6000).unwrap();
+        }
+        let repo = ReadingEventsRepo::new(&conn);
+        assert_eq!(
+            repo.sessions(UserId(1), 1800).unwrap(),
+            SessionTotals::default()
+        );
+        for (page, at) in [
+            (1, "2026-03-14T09:00:00.000Z"),
+            (2, "2026-03-14T09:10:00.000Z"),
+            (3, "2026-03-14T09:20:00.000Z"),
+            // Next evening: a separate session
+            (4, "2026-03-14T21:00:00.000Z"),
+            // Chapter 5's length is unknown, so this session isn't timed
+            (5, "2026-03-15T21:00:00.000Z"),
+            (6, "2026-03-15T21:20:00.000Z"),
+        ] {
+            repo.record(UserId(1), "narou", "n1", page, None, at)
+                .unwrap();
+        }
+        assert_eq!(
+            repo.sessions(UserId(1), 1800).unwrap(),
+            SessionTotals {
+                count: 3,
+                chapters: 6,
+                timed_chars: 12000,
+                timed_secs: 1200,
+            }
+        );
+    }
+}
@@ -0,0 +1,288 @@
+use super::favorites::position_json;
+use super::{FavoritesRepo, Progress};
+use crate::auth::UserId;
+use rusqlite::{Connection, OptionalExtension};
+use serde_json::{json, Value};
+
+/// Novels kept per user; the least recently read fall off.
+const MAX_RECENT_READS: i64 = 100;
+
+/// Shared metadata wins over what the reader reported, which may be stale.
+const RECENT_SELECT: &str =
+    "SELECT r.type, r.id, COALESCE(n.title, r.title), COALESCE(n.page, r.page), r.read,
+        r.read_paragraph, r.read_fraction, r.progress_at
+     FROM recent_reads r LEFT JOIN novels n ON n.type = r.type AND n.id = r.id";
+
+/// Reading progress in a novel that isn't a favorite.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RecentRead {
+    pub type_str: String,
+    pub id: String,
+    /// None if neither the reader nor a sync has supplied it
+    pub title: Option<String>,
+    pub page: Option<i64>,
+    pub read: i64,
+    pub read_paragraph: Option<i64>,
+    pub read_fraction: Option<f64>,
+    pub progress_at: String,
+}
+
+impl RecentRead {
+    /// API representation, as returned by `/api/recent`.
+    pub fn to_json(&self) -> Value {
+        json!({
+            "type": self.type_str,
+            "id": self.id,
+            "title": self.title,
+            "page": self.page,
+            "read": self.read,
+            "position": position_json(self.read_paragraph, self.read_fraction),
+            "progress_at": self.progress_at,
+        })
+    }
+
+    /// Same shape as `Favorite::progress_json`.
+    pub fn progress_json(&self) -> Value {
+        json!({
+            "read": self.read,
+            "position": position_json(self.read_paragraph, self.read_fraction),
+            "progress_at": self.progress_at,
+        })
+    }
+}
+
+fn map_recent_row(row: &rusqlite::Row) -> rusqlite::Result<RecentRead> {
+    Ok(RecentRead {
+        type_str: row.get(0)?,
+        id: row.get(1)?,
+        title: row.get(2)?,
+        page: row.get(3)?,
+        read: row.get(4)?,
+        read_paragraph: row.get(5)?,
+        read_fraction: row.get(6)?,
+        progress_at: row.get(7)?,
+    })
+}
+
+pub struct RecentReadsRepo<'c> {
+    conn: &'c Connection,
+}
+
+impl<'c> RecentReadsRepo<'c> {
+    pub fn new(conn: &'c Connection) -> Self {
+        Self { conn }
+    }
+
+    /// Most recently read first.
+    pub fn list(&self, user_id: UserId) -> rusqlite::Result<Vec<RecentRead>> {
+        let mut stmt = self.conn.prepare(&format!(
+            "{} WHERE r.user_id = ?1 ORDER BY r.progress_at DESC, r.type, r.id",
+            RECENT_SELECT
+        ))?;
+        let rows = stmt
+            .query_map([user_id.0], map_recent_row)?
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(rows)
+    }
+
+    pub fn get(
+        &self,
+        user_id: UserId,
+        type_str: &str,
+        id: &str,
+    ) -> rusqlite::Result<Option<RecentRead>> {
+        self.conn
+            .query_row(
+                &format!(
+                    "{} WHERE r.user_id = ?1 AND r.type = ?2 AND r.id = ?3",
+                    RECENT_SELECT
+                ),
+                rusqlite::params![user_id.0, type_str, id],
+                map_recent_row,
+            )
+            .optional()
+    }
+
+    /// Store the position with the same last-write-wins rule as favorites.
+    /// `title` and `page` (chapter count) are kept for promoting the novel to a
+    /// favorite later; omitting them keeps earlier values. Returns false if the
+    /// update was stale.
+    pub fn set_progress(
+        &self,
+        user_id: UserId,
+        type_str: &str,
+        id: &str,
+        title: Option<&str>,
+        page: Option<i64>,
+        progress: &Progress,
+    ) -> rusqlite::Result<bool> {
+        let changes = self.conn.execute(
+            "INSERT INTO recent_reads
+                (user_id, type, id, title, page, read, read_paragraph, read_fraction, progress_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)
+             ON CONFLICT(user_id, type, id) DO UPDATE SET
+                title = COALESCE(excluded.title, recent_reads.title),
+                page = COALESCE(excluded.page, recent_reads.page),
+                read = excluded.read,
+                read_paragraph = excluded.read_paragraph,
+                read_fraction = excluded.read_fraction,
+                progress_at = excluded.progress_at
+             WHERE recent_reads.progress_at <= excluded.progress_at",
+            rusqlite::params![
+                user_id.0,
+                type_str,
+                id,
+                title,
+                page,
+                progress.read,
+                progress.paragraph,
+                progress.fraction,
+                progress.at
+            ],
+        )?;
+        self.conn.execute(
+            "DELETE FROM recent_reads WHERE user_id = ?1 AND rowid NOT IN (
+                SELECT rowid FROM recent_reads WHERE user_id = ?1
+                ORDER BY progress_at DESC LIMIT ?2
+             )",
+            rusqlite::params![user_id.0, MAX_RECENT_READS],
+        )?;
+        Ok(changes > 0)
+    }
+
+    /// Returns false if the novel wasn't in the list.
+    pub fn delete(&self, user_id: UserId, type_str: &str, id: &str) -> rusqlite::Result<bool> {
+        let changes = self.conn.execute(
+            "DELETE FROM recent_reads WHERE user_id = ?1 AND type = ?2 AND id = ?3",
+            rusqlite::params![user_id.0, type_str, id],
+        )?;
+        Ok(changes > 0)
+    }
+
+    /// The novel has just become a favorite: move its position over, unless
+    /// the favorite already has a later one, and drop it from the list.
+    pub fn carry_over(&self, user_id: UserId, type_str: &str, id: &str) -> rusqlite::Result<()> {
+        let Some(recent) = self.get(user_id, type_str, id)? else {
+            return Ok(());
+        };
+        FavoritesRepo::new(self.conn).set_progress(
+            user_id,
+            type_str,
+            id,
+            &Progress {
+                read: recent.read,
+                paragraph: recent.read_paragraph,
+                fraction: recent.read_fraction,
+                at: recent.progress_at,
+            },
+        )?;
+        self.delete(user_id, type_str, id)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::repo::NewNovel;
+
+    fn progress(read: i64, at: &str) -> Progress {
+        Progress {
+            read,
+            paragraph: Some(7),
+            fraction: None,
+            at: at.into(),
+        }
+    }
+
+    #[test]
+    fn set_progress_keeps_the_latest_write_and_reported_metadata() {
+        let conn = crate::db::open_memory();
+        let repo = RecentReadsRepo::new(&conn);
+        let at = "2026-03-14T09:00:00.000Z";
+        assert!(repo
+            .set_progress(
+                UserId(1),
+                "narou",
+                "n1",
+                Some("One"),
+                Some(20),
+                &progress(3, at)
+            )
+            .unwrap());
+        let stale = progress(1, "2026-03-14T08:00:00.000Z");
+        assert!(!repo
+            .set_progress(UserId(1), "narou", "n1", None, None, &stale)
+            .unwrap());
+        let later = progress(4, "2026-03-14T10:00:00.000Z");
+        assert!(repo
+            .set_progress(UserId(1), "narou", "n1", None, None, &later)
+            .unwrap());
+
+        let recent = repo.get(UserId(1), "narou", "n1").unwrap().unwrap();
+        assert_eq!(recent.read, 4);
+        assert_eq!(recent.title.as_deref(), Some("One"));
+        assert_eq!(recent.page, Some(20));
+        assert!(repo.list(UserId(2)).unwrap().is_empty());
+    }
+
+    #[test]
+    fn list_is_capped_to_the_most_recent() {
+        let conn = crate::db::open_memory();
+        let repo = RecentReadsRepo::new(&conn);
+        for i in 0..MAX_RECENT_READS + 5 {
+            let at = format!("2026-03-14T09:{:02}:{:02}.000Z", i / 60, i % 60);
+            repo.set_progress(
+                UserId(1),
+                "narou",
+                &format!("n{}", i),
+                None,
+                None,
+                &progress(1, &at),
+            )
+            .unwrap();
+        }
+        let list = repo.list(UserId(1)).unwrap();
+        assert_eq!(list.len() as i64, MAX_RECENT_READS);
+        assert_eq!(list[0].id, format!("n{}", MAX_RECENT_READS + 4));
+        assert!(repo.get(UserId(1), "narou", "n4").unwrap().is_none());
+    }
+
+    #[test]
+    fn carry_over_moves_the_position_to_the_new_favorite() {
+        let conn = crate::db::open_memory();
+        let repo = RecentReadsRepo::new(&conn);
+        repo.set_progress(
+            UserId(1),
+            "narou",
+            "n1",
+            Some("One"),
+            Some(20),
+            &progress(5, "2026-03-14T09:00:00.000Z"),
+        )
+        .unwrap();
+        let favorites = FavoritesRepo::new(&conn);
+        favorites
+            .upsert(
+                UserId(1),
+                &NewNovel {
+                    type_str: "narou",
+                    id: "n1",
+                    title: "One",
+                    page: 20,
+                    novelupdated_at: None,
+                },
+            )
+            .unwrap();
+        repo.carry_over(UserId(1), "narou", "n1").unwrap();
+
+        let favorite = favorites.get(UserId(1), "narou", "n1").unwrap().unwrap();
+        assert_eq!(favorite.read, 5);
+        assert_eq!(favorite.read_paragraph, Some(7));
+        assert_eq!(
+            favorite.progress_at.as_deref(),
+            Some("2026-03-14T09:00:00.000Z")
+        );
+        assert!(repo.list(UserId(1)).unwrap().is_empty());
+    }
+}
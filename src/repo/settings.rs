@@ -0,0 +1,37 @@
+use rusqlite::Connection;
+
+/// Server-wide key/value settings.
+pub struct SettingsRepo<'c> {
+    conn: &'c Connection,
+}
+
+impl<'c> SettingsRepo<'c> {
+    pub fn new(conn: &'c Connection) -> Self {
+        Self { conn }
+    }
+
+    /// The stored value of `key`, storing `value` first if there is none.
+    pub fn get_or_insert(&self, key: &str, value: &str) -> rusqlite::Result<String> {
+        self.conn.execute(
+            "INSERT OR IGNORE INTO settings (key, value) VALUES (?1, ?2)",
+            [key, value],
+        )?;
+        self.conn
+            .query_row("SELECT value FROM settings WHERE key = ?1", [key], |row| {
+                row.get(0)
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn first_value_wins() {
+        let conn = crate::db::open_memory();
+        let settings = SettingsRepo::new(&conn);
+        assert_eq!(settings.get_or_insert("k", "a").unwrap(), "a");
+        assert_eq!(settings.get_or_insert("k", "b").unwrap(), "a");
+    }
+}
@@ -0,0 +1,185 @@
+use crate::auth::UserId;
+use rusqlite::{Connection, OptionalExtension};
+use serde_json::{json, Value};
+
+/// A user's tag with the number of favorites carrying it.
+#[derive(Debug, PartialEq)]
+pub struct Tag {
+    pub id: i64,
+    pub name: String,
+    pub count: i64,
+}
+
+impl Tag {
+    pub fn to_json(&self) -> Value {
+        json!({ "id": self.id, "name": self.name, "count": self.count })
+    }
+}
+
+const TAG_SELECT: &str = "SELECT t.id, t.name,
+        (SELECT COUNT(*) FROM favorite_tags ft WHERE ft.tag_id = t.id)
+     FROM tags t";
+
+fn map_tag_row(row: &rusqlite::Row) -> rusqlite::Result<Tag> {
+    Ok(Tag {
+        id: row.get(0)?,
+        name: row.get(1)?,
+        count: row.get(2)?,
+    })
+}
+
+pub struct TagsRepo<'c> {
+    conn: &'c Connection,
+}
+
+impl<'c> TagsRepo<'c> {
+    pub fn new(conn: &'c Connection) -> Self {
+        Self { conn }
+    }
+
+    /// The user's tags by name.
+    pub fn list(&self, user_id: UserId) -> rusqlite::Result<Vec<Tag>> {
+        let mut stmt = self.conn.prepare(&format!(
+            "{} WHERE t.user_id = ?1 ORDER BY t.name",
+            TAG_SELECT
+        ))?;
+        let rows = stmt
+            .query_map([user_id.0], map_tag_row)?
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(rows)
+    }
+
+    fn get(&self, user_id: UserId, tag_id: i64) -> rusqlite::Result<Option<Tag>> {
+        self.conn
+            .query_row(
+                &format!("{} WHERE t.user_id = ?1 AND t.id = ?2", TAG_SELECT),
+                rusqlite::params![user_id.0, tag_id],
+                map_tag_row,
+            )
+            .optional()
+    }
+
+    /// Returns None if the user already has a tag with this name.
+    pub fn create(&self, user_id: UserId, name: &str) -> rusqlite::Result<Option<Tag>> {
+        let changes = self.conn.execute(
+            "INSERT INTO tags (user_id, name) VALUES (?1, ?2) ON CONFLICT(user_id, name) DO NOTHING",
+            rusqlite::params![user_id.0, name],
+        )?;
+        if changes == 0 {
+            return Ok(None);
+        }
+        self.get(user_id, self.conn.last_insert_rowid())
+    }
+
+    /// Returns None if the tag doesn't exist, or Some(false) if the new name is taken.
+    pub fn rename(
+        &self,
+        user_id: UserId,
+        tag_id: i64,
+        name: &str,
+    ) -> rusqlite::Result<Option<bool>> {
+        let Some(tag) = self.get(user_id, tag_id)? else {
+            return Ok(None);
+        };
+        if tag.name == name {
+            return Ok(Some(true));
+        }
+        let taken: bool = self.conn.query_row(
+            "SELECT EXISTS (SELECT 1 FROM tags WHERE user_id = ?1 AND name = ?2)",
+            rusqlite::params![user_id.0, name],
+            |row| row.get(0),
+        )?;
+        if taken {
+            return Ok(Some(false));
+        }
+        self.conn.execute(
+            "UPDATE tags SET name = ?1 WHERE user_id = ?2 AND id = ?3",
+            rusqlite::params![name, user_id.0, tag_id],
+        )?;
+        Ok(Some(true))
+    }
+
+    /// Delete the tag and untag every favorite. Returns false if it didn't exist.
+    pub fn delete(&self, user_id: UserId, tag_id: i64) -> rusqlite::Result<bool> {
+        let changes = self.conn.execute(
+            "DELETE FROM tags WHERE user_id = ?1 AND id = ?2",
+            rusqlite::params![user_id.0, tag_id],
+        )?;
+        Ok(changes > 0)
+    }
+
+    /// Replace the favorite's tags with `names`, creating tags the user doesn't
+    /// have yet. The caller checks that the favorite exists.
+    pub fn set_for_favorite(
+        &self,
+        user_id: UserId,
+        type_str: &str,
+        id: &str,
+        names: &[String],
+    ) -> rusqlite::Result<()> {
+        self.conn.execute(
+            "DELETE FROM favorite_tags WHERE user_id = ?1 AND type = ?2 AND id = ?3",
+            rusqlite::params![user_id.0, type_str, id],
+        )?;
+        for name in names {
+            self.conn.execute(
+                "INSERT INTO tags (user_id, name) VALUES (?1, ?2) ON CONFLICT(user_id, name) DO NOTHING",
+                rusqlite::params![user_id.0, name],
+            )?;
+            self.conn.execute(
+                "INSERT OR IGNORE INTO favorite_tags (user_id, type, id, tag_id)
+                 SELECT ?1, ?2, ?3, id FROM tags WHERE user_id = ?1 AND name = ?4",
+                rusqlite::params![user_id.0, type_str, id, name],
+            )?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tags_are_per_user_and_counted() {
+        let conn = crate::db::open_memory();
+        conn.execute_batch(
+            "INSERT INTO users (id, email) VALUES (2, 'alice@example.com');
+             INSERT INTO novels (type, id, title, page) VALUES ('narou', 'n1', 'One', 10);
+             INSERT INTO favorites (user_id, type, id) VALUES (1, 'narou', 'n1');",
+        )
+        .unwrap();
+        let tags = TagsRepo::new(&conn);
+        let on_hold = tags.create(UserId(1), "on hold").unwrap().unwrap();
+        assert!(tags.create(UserId(1), "on hold").unwrap().is_none());
+        assert!(tags.create(UserId(2), "on hold").unwrap().is_some());
+
+        let names = vec!["reading".to_string(), "on hold".to_string()];
+        tags.set_for_favorite(UserId(1), "narou", "n1", &names)
+            .unwrap();
+        let listed = tags.list(UserId(1)).unwrap();
+        assert_eq!(
+            listed
+                .iter()
+                .map(|t| (t.name.as_str(), t.count))
+                .collect::<Vec<_>>(),
+            [("on hold", 1), ("reading", 1)]
+        );
+
+        assert_eq!(
+            tags.rename(UserId(1), on_hold.id, "reading").unwrap(),
+            Some(false)
+        );
+        assert_eq!(
+            tags.rename(UserId(1), on_hold.id, "paused").unwrap(),
+            Some(true)
+        );
+        assert_eq!(tags.rename(UserId(2), on_hold.id, "x").unwrap(), None);
+
+        assert!(!tags.delete(UserId(2), on_hold.id).unwrap());
+        assert!(tags.delete(UserId(1), on_hold.id).unwrap());
+        tags.set_for_favorite(UserId(1), "narou", "n1", &[])
+            .unwrap();
+        assert_eq!(tags.list(UserId(1)).unwrap()[0].count, 0);
+    }
+}
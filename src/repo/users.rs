@@ -0,0 +1,59 @@
+use crate::auth::UserId;
+use rusqlite::{Connection, OptionalExtension};
+
+pub struct UsersRepo<'c> {
+    conn: &'c Connection,
+}
+
+impl<'c> UsersRepo<'c> {
+    pub fn new(conn: &'c Connection) -> Self {
+        Self { conn }
+    }
+
+    pub fn find_by_email(&self, email: &str) -> rusqlite::Result<Option<UserId>> {
+        self.conn
+            .query_row("SELECT id FROM users WHERE email = ?1", [email], |row| {
+                row.get(0).map(UserId)
+            })
+            .optional()
+    }
+
+    /// Id of the user with `email`, creating the user on first sight.
+    pub fn get_or_create(&self, email: &str) -> rusqlite::Result<UserId> {
+        self.conn
+            .execute("INSERT OR IGNORE INTO users (email) VALUES (?1)", [email])?;
+        self.conn
+            .query_row("SELECT id FROM users WHERE email = ?1", [email], |row| {
+                row.get(0).map(UserId)
+            })
+    }
+
+    pub fn email(&self, user_id: UserId) -> rusqlite::Result<String> {
+        self.conn.query_row(
+            "SELECT email FROM users WHERE id = ?1",
+            [user_id.0],
+            |row| row.get(0),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn get_or_create_is_stable() {
+        let conn = crate::db::open_memory();
+        let users = UsersRepo::new(&conn);
+        assert_eq!(users.find_by_email("alice@example.com").unwrap(), None);
+        let alice = users.get_or_create("alice@example.com").unwrap();
+        assert_ne!(alice, UserId(1));
+        assert_eq!(users.get_or_create("alice@example.com").unwrap(), alice);
+        assert_eq!(
+            users.find_by_email("alice@example.com").unwrap(),
+            Some(alice)
+        );
+        assert_eq!(users.email(alice).unwrap(), "alice@example.com");
+        assert_eq!(users.email(UserId(1)).unwrap(), "guest");
+    }
+}
@@ -0,0 +1,246 @@
+use crate::auth::UserId;
+use crate::sync::NovelUpdate;
+use crate::webhook::DeliveryOutcome;
+use rusqlite::Connection;
+use serde_json::{json, Value};
+
+/// Delivery log rows kept per webhook
+const DELIVERY_LOG_LIMIT: i64 = 100;
+
+/// A webhook to notify about an update, with its owner's read position.
+pub struct WebhookTarget {
+    pub webhook_id: i64,
+    pub url: String,
+    pub secret: String,
+    pub read: i64,
+}
+
+pub struct WebhooksRepo<'c> {
+    conn: &'c Connection,
+}
+
+fn map_webhook_row(row: &rusqlite::Row) -> rusqlite::Result<Value> {
+    let events: String = row.get(2)?;
+    Ok(json!({
+        "id": row.get::<_, i64>(0)?,
+        "url": row.get::<_, String>(1)?,
+        "events": events.split(',').collect::<Vec<_>>(),
+        "created_at": row.get::<_, String>(3)?,
+    }))
+}
+
+impl<'c> WebhooksRepo<'c> {
+    pub fn new(conn: &'c Connection) -> Self {
+        Self { conn }
+    }
+
+    /// The user's webhooks, oldest first, without their secrets.
+    pub fn list(&self, user_id: UserId) -> rusqlite::Result<Vec<Value>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT id, url, events, created_at FROM webhooks WHERE user_id = ?1 ORDER BY id",
+        )?;
+        let rows = stmt
+            .query_map([user_id.0], map_webhook_row)?
+            .collect::<Result<Vec<Value>, _>>()?;
+        Ok(rows)
+    }
+
+    pub fn create(
+        &self,
+        user_id: UserId,
+        url: &str,
+        secret: &str,
+        events: &[String],
+    ) -> rusqlite::Result<Value> {
+        self.conn.execute(
+            "INSERT INTO webhooks (user_id, url, secret, events) VALUES (?1, ?2, ?3, ?4)",
+            rusqlite::params![user_id.0, url, secret, events.join(",")],
+        )?;
+        self.conn.query_row(
+            "SELECT id, url, events, created_at FROM webhooks WHERE id = ?1",
+            [self.conn.last_insert_rowid()],
+            map_webhook_row,
+        )
+    }
+
+    /// Returns false if the webhook doesn't exist or belongs to someone else.
+    pub fn delete(&self, user_id: UserId, id: i64) -> rusqlite::Result<bool> {
+        let changes = self.conn.execute(
+            "DELETE FROM webhooks WHERE id = ?1 AND user_id = ?2",
+            rusqlite::params![id, user_id.0],
+        )?;
+        Ok(changes > 0)
+    }
+
+    pub fn is_owned_by(&self, user_id: UserId, id: i64) -> rusqlite::Result<bool> {
+        self.conn.query_row(
+            "SELECT COUNT(*) > 0 FROM webhooks WHERE id = ?1 AND user_id = ?2",
+            rusqlite::params![id, user_id.0],
+            |row| row.get(0),
+        )
+    }
+
+    /// Webhooks subscribed to `event` whose owner has the updated novel in favorites.
+    pub fn targets(
+        &self,
+        update: &NovelUpdate,
+        event: &str,
+    ) -> rusqlite::Result<Vec<WebhookTarget>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT w.id, w.url, w.secret, f.read FROM webhooks w
+             JOIN favorites f ON f.user_id = w.user_id
+             WHERE f.type = ?1 AND f.id = ?2 AND (',' || w.events || ',') LIKE '%,' || ?3 || ',%'",
+        )?;
+        let rows = stmt
+            .query_map(
+                rusqlite::params![update.type_str, update.id, event],
+                |row| {
+                    Ok(WebhookTarget {
+                        webhook_id: row.get(0)?,
+                        url: row.get(1)?,
+                        secret: row.get(2)?,
+                        read: row.get(3)?,
+                    })
+                },
+            )?
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(rows)
+    }
+
+    /// Record a delivery, keeping only the latest `DELIVERY_LOG_LIMIT` per webhook.
+    pub fn log_delivery(
+        &self,
+        webhook_id: i64,
+        event: &str,
+        payload: &str,
+        outcome: &DeliveryOutcome,
+    ) -> rusqlite::Result<()> {
+        self.conn.execute(
+            "INSERT INTO webhook_deliveries (webhook_id, event, payload, status, attempts, success, error)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+            rusqlite::params![
+                webhook_id,
+                event,
+                payload,
+                outcome.status,
+                outcome.attempts,
+                outcome.success(),
+                outcome.error
+            ],
+        )?;
+        self.conn.execute(
+            "DELETE FROM webhook_deliveries WHERE webhook_id = ?1 AND id NOT IN (
+                SELECT id FROM webhook_deliveries WHERE webhook_id = ?1 ORDER BY id DESC LIMIT ?2
+             )",
+            rusqlite::params![webhook_id, DELIVERY_LOG_LIMIT],
+        )?;
+        Ok(())
+    }
+
+    /// Latest deliveries of a webhook, newest first.
+    pub fn deliveries(&self, id: i64, limit: i64) -> rusqlite::Result<Vec<Value>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT id, event, payload, status, attempts, success, error, created_at
+             FROM webhook_deliveries WHERE webhook_id = ?1 ORDER BY id DESC LIMIT ?2",
+        )?;
+        let rows = stmt
+            .query_map(rusqlite::params![id, limit], |row| {
+                Ok(json!({
+                    "id": row.get::<_, i64>(0)?,
+                    "event": row.get::<_, String>(1)?,
+                    "payload": row.get::<_, String>(2)?,
+                    "status": row.get::<_, Option<i64>>(3)?,
+                    "attempts": row.get::<_, i64>(4)?,
+                    "success": row.get::<_, bool>(5)?,
+                    "error": row.get::<_, Option<String>>(6)?,
+                    "created_at": row.get::<_, String>(7)?,
+                }))
+            })?
+            .collect::<Result<Vec<Value>, _>>()?;
+        Ok(rows)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn webhooks_are_scoped_to_their_owner() {
+        let conn = crate::db::open_memory();
+        conn.execute(
+            "INSERT INTO users (id, email) VALUES (2, 'alice@example.com')",
+            [],
+        )
+        .unwrap();
+        let webhooks = WebhooksRepo::new(&conn);
+        let events = vec!["new_chapters".to_string()];
+        let created = webhooks
+            .create(UserId(1), "http://a", "secret", &events)
+            .unwrap();
+        let id = created["id"].as_i64().unwrap();
+        assert_eq!(created["events"], json!(["new_chapters"]));
+        assert!(created.get("secret").is_none());
+
+        assert_eq!(webhooks.list(UserId(1)).unwrap().len(), 1);
+        assert!(webhooks.list(UserId(2)).unwrap().is_empty());
+        assert!(!webhooks.is_owned_by(UserId(2), id).unwrap());
+        assert!(!webhooks.delete(UserId(2), id).unwrap());
+        assert!(webhooks.delete(UserId(1), id).unwrap());
+        assert!(webhooks.list(UserId(1)).unwrap().is_empty());
+    }
+
+    #[test]
+    fn targets_only_match_subscribers_with_the_novel() {
+        let conn = crate::db::open_memory();
+        conn.execute_batch(
+            "INSERT INTO users (id, email) VALUES (2, 'alice@example.com');
+             INSERT INTO novels (type, id, title, page) VALUES ('narou', 'n1', 'Novel', 10), ('narou', 'n2', 'Other', 5);
+             INSERT INTO favorites (user_id, type, id, read) VALUES (1, 'narou', 'n1', 4), (2, 'narou', 'n2', 0);
+             INSERT INTO webhooks (id, user_id, url, secret, events) VALUES
+                (1, 1, 'http://a', 's1', 'new_chapters'),
+                (2, 2, 'http://b', 's2', 'new_chapters'),
+                (3, 1, 'http://c', 's3', 'other_event');",
+        )
+        .unwrap();
+        let update = NovelUpdate {
+            type_str: "narou".into(),
+            id: "n1".into(),
+            title: "Novel".into(),
+            old_page: 10,
+            new_page: 12,
+        };
+
+        let targets = WebhooksRepo::new(&conn)
+            .targets(&update, "new_chapters")
+            .unwrap();
+        assert_eq!(targets.len(), 1);
+        assert_eq!(targets[0].webhook_id, 1);
+        assert_eq!(targets[0].read, 4);
+    }
+
+    #[test]
+    fn log_delivery_keeps_latest_rows() {
+        let conn = crate::db::open_memory();
+        conn.execute(
+            "INSERT INTO webhooks (id, user_id, url, secret, events) VALUES (1, 1, 'http://a', 's', 'new_chapters')",
+            [],
+        )
+        .unwrap();
+        let outcome = DeliveryOutcome {
+            status: Some(200),
+            attempts: 1,
+            error: None,
+        };
+        let webhooks = WebhooksRepo::new(&conn);
+        for _ in 0..DELIVERY_LOG_LIMIT + 5 {
+            webhooks
+                .log_delivery(1, "new_chapters", "{}", &outcome)
+                .unwrap();
+        }
+        assert_eq!(
+            webhooks.deliveries(1, 1000).unwrap().len() as i64,
+            DELIVERY_LOG_LIMIT
+        );
+    }
+}
@@ -1,13 +1,21 @@
 use crate::auth::UserId;
+use crate::error::AppError;
+use crate::repo::{FeedTokensRepo, UsersRepo};
 use crate::state::AppState;
 use axum::extract::State;
+use axum::http::HeaderMap;
 use axum::Extension;
 use axum::routing::get;
 use axum::{Json, Router};
 use serde_json::{json, Value};
 
 pub fn routes() -> Router<AppState> {
-    Router::new().route("/api/auth/me", get(get_me))
+    Router::new().route("/api/auth/me", get(get_me)).route(
+        "/api/auth/feed-token",
+        get(get_feed_token)
+            .post(post_feed_token)
+            .delete(delete_feed_token),
+    )
 }
 
 #[utoipa::path(
@@ -25,12 +33,100 @@ async fn get_me(
     State(state): State<AppState>,
     Extension(user_id): Extension<UserId>,
 ) -> Json<Value> {
-    let email = {
-        let db = state.db.lock().unwrap();
-        db.query_row("SELECT email FROM users WHERE id = ?1", [user_id.0], |row| {
-            row.get::<_, String>(0)
-        })
-        .unwrap_or_else(|_| "guest".to_string())
-    };
+    let email = state
+        .db
+        .read(move |conn| UsersRepo::new(conn).email(user_id))
+        .await
+        .unwrap_or_else(|_| "guest".to_string());
     Json(json!({ "email": email }))
 }
+
+#[utoipa::path(
+    get,
+    path = "/api/auth/feed-token",
+    tag = "認証",
+    summary = "フィードトークン状態取得",
+    description = "フィード用トークンが発行済みかどうかと発行日時を返す。トークン自体はハッシュのみ保存しているため返せない。紛失した場合は再発行する。",
+    responses(
+        (status = 200, description = "トークン状態", body = crate::openapi::FeedTokenStatus,
+            example = json!({"active": true, "created_at": "2026-03-14 00:00:00"})),
+        (status = 500, description = "DBエラー", body = crate::openapi::ErrorResponse),
+    ),
+)]
+async fn get_feed_token(
+    State(state): State<AppState>,
+    Extension(user_id): Extension<UserId>,
+) -> Result<Json<Value>, AppError> {
+    let created_at = state
+        .db
+        .read(move |conn| FeedTokensRepo::new(conn).created_at(user_id))
+        .await?;
+    Ok(Json(json!({
+        "active": created_at.is_some(),
+        "created_at": created_at,
+    })))
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/auth/feed-token",
+    tag = "認証",
+    summary = "フィードトークン発行",
+    description = "フィード用トークンを発行する。既存のトークンは無効になる（ローテーション）。\n\nフィードリーダーは `X-Forwarded-Email` ヘッダーを送れないため、`/api/rss`・`/api/rss.atom`・`/api/feed.json` に `?token=<トークン>` を付けるとこのユーザーのフィードを取得できる。トークンはフィードの読み取りにのみ有効。トークンはこのレスポンスでのみ返される。",
+    responses(
+        (status = 200, description = "発行されたトークンとフィードURL", body = crate::openapi::FeedToken,
+            example = json!({"token": "9f86d081884c7d65...", "created_at": "2026-03-14 00:00:00", "feeds": {"rss": "https://example.com/api/rss?token=9f86d081884c7d65...", "atom": "https://example.com/api/rss.atom?token=9f86d081884c7d65...", "json": "https://example.com/api/feed.json?token=9f86d081884c7d65..."}})),
+        (status = 500, description = "DBエラー", body = crate::openapi::ErrorResponse),
+    ),
+)]
+async fn post_feed_token(
+    State(state): State<AppState>,
+    Extension(user_id): Extension<UserId>,
+    headers: HeaderMap,
+) -> Result<Json<Value>, AppError> {
+    let token = crate::auth::random_token();
+    let created_at = {
+        let token = token.clone();
+        state
+            .db
+            .write(move |conn| FeedTokensRepo::new(conn).replace(user_id, &token))
+            .await?
+    };
+    let base = super::rss::resolve_base_url(&headers, &state.config);
+    Ok(Json(json!({
+        "token": token,
+        "created_at": created_at,
+        "feeds": {
+            "rss": format!("{}/api/rss?token={}", base, token),
+            "atom": format!("{}/api/rss.atom?token={}", base, token),
+            "json": format!("{}/api/feed.json?token={}", base, token),
+        },
+    })))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/api/auth/feed-token",
+    tag = "認証",
+    summary = "フィードトークン失効",
+    description = "フィード用トークンを失効させる。以後そのトークン付きのフィードURLは401を返す。",
+    responses(
+        (status = 200, description = "失効成功", body = crate::openapi::OkResponse,
+            example = json!({"ok": true})),
+        (status = 404, description = "トークンが発行されていない", body = crate::openapi::ErrorResponse),
+        (status = 500, description = "DBエラー", body = crate::openapi::ErrorResponse),
+    ),
+)]
+async fn delete_feed_token(
+    State(state): State<AppState>,
+    Extension(user_id): Extension<UserId>,
+) -> Result<Json<Value>, AppError> {
+    let deleted = state
+        .db
+        .write(move |conn| FeedTokensRepo::new(conn).delete(user_id))
+        .await?;
+    if !deleted {
+        return Err(AppError::NotFound("Not found".into()));
+    }
+    Ok(Json(json!({ "ok": true })))
+}
@@ -0,0 +1,656 @@
+use crate::auth::UserId;
+use crate::error::AppError;
+use crate::modules::ModuleType;
+use crate::repo::{FavoritesRepo, NewNovel, TagsRepo};
+use crate::state::AppState;
+use axum::extract::{Query, State};
+use axum::http::header;
+use axum::response::IntoResponse;
+use axum::routing::{get, post};
+use axum::{Extension, Json, Router};
+use chrono::{NaiveDateTime, Utc};
+use rusqlite::Connection;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use utoipa::IntoParams;
+
+/// Current backup format. Imports accept this version and older ones.
+/// 2 added notes, rating and tags.
+const FORMAT_VERSION: u32 = 2;
+/// Records accepted per import
+const MAX_IMPORT_RECORDS: usize = 5000;
+const TIMESTAMP_FORMAT: &str = "%Y-%m-%d %H:%M:%S";
+
+pub fn routes() -> Router<AppState> {
+    Router::new()
+        .route("/api/favorites/export", get(get_export))
+        .route("/api/favorites/import", post(post_import))
+}
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+struct ExportQuery {
+    /// `json`（デフォルト）または `csv`
+    format: Option<String>,
+}
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+struct ImportQuery {
+    /// `json`（デフォルト）または `csv`
+    format: Option<String>,
+    /// 登録済みのお気に入りの扱い: `skip`（デフォルト）/ `overwrite` / `keep-max-read`
+    strategy: Option<String>,
+    /// `true` で変更を保存せず結果だけ返す
+    dry_run: Option<bool>,
+}
+
+#[derive(Debug, PartialEq)]
+enum Format {
+    Json,
+    Csv,
+}
+
+fn parse_format(format: Option<&str>) -> Result<Format, AppError> {
+    match format {
+        None | Some("json") => Ok(Format::Json),
+        Some("csv") => Ok(Format::Csv),
+        Some(other) => Err(AppError::BadRequest(format!(
+            "format must be json or csv, got {}",
+            other
+        ))),
+    }
+}
+
+/// How an imported record is applied to a favorite that already exists
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Strategy {
+    /// Leave the existing favorite untouched
+    Skip,
+    /// Replace read position and added date with the backup's
+    Overwrite,
+    /// Keep whichever read position is further along
+    KeepMaxRead,
+}
+
+impl Strategy {
+    fn parse(s: Option<&str>) -> Result<Self, AppError> {
+        match s {
+            None | Some("skip") => Ok(Self::Skip),
+            Some("overwrite") => Ok(Self::Overwrite),
+            Some("keep-max-read") => Ok(Self::KeepMaxRead),
+            Some(other) => Err(AppError::BadRequest(format!(
+                "strategy must be skip, overwrite or keep-max-read, got {}",
+                other
+            ))),
+        }
+    }
+}
+
+/// One favorite in a backup. Also the CSV row layout.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct Record {
+    #[serde(rename = "type")]
+    type_str: String,
+    id: String,
+    title: Option<String>,
+    page: Option<i64>,
+    novelupdated_at: Option<String>,
+    read: Option<i64>,
+    added_at: Option<String>,
+    #[serde(default)]
+    notes: Option<String>,
+    #[serde(default)]
+    rating: Option<i64>,
+    /// Comma-separated tag names
+    #[serde(default)]
+    tags: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct BackupFile {
+    version: u32,
+    favorites: Vec<Record>,
+}
+
+#[derive(Debug, Default, PartialEq)]
+struct ImportSummary {
+    added: usize,
+    updated: usize,
+    skipped: usize,
+    errors: Vec<Value>,
+    /// Novels created by this import, to be filled in by an initial fetch
+    fetch: Vec<(ModuleType, String)>,
+}
+
+fn load_records(conn: &Connection, user_id: UserId) -> rusqlite::Result<Vec<Record>> {
+    let favorites = FavoritesRepo::new(conn).export_rows(user_id)?;
+    Ok(favorites
+        .into_iter()
+        .map(|f| Record {
+            type_str: f.type_str,
+            id: f.id,
+            title: Some(f.title),
+            page: Some(f.page),
+            novelupdated_at: f.novelupdated_at,
+            read: Some(f.read),
+            added_at: Some(f.added_at),
+            notes: f.notes,
+            rating: f.rating,
+            tags: (!f.tags.is_empty()).then(|| f.tags.join(",")),
+        })
+        .collect())
+}
+
+fn to_json(records: &[Record]) -> Value {
+    json!({
+        "version": FORMAT_VERSION,
+        "exported_at": Utc::now().format(TIMESTAMP_FORMAT).to_string(),
+        "favorites": records,
+    })
+}
+
+fn to_csv(records: &[Record]) -> Result<String, AppError> {
+    let mut writer = csv::Writer::from_writer(Vec::new());
+    for record in records {
+        writer
+            .serialize(record)
+            .map_err(|e| AppError::Internal(e.to_string()))?;
+    }
+    let bytes = writer
+        .into_inner()
+        .map_err(|e| AppError::Internal(e.to_string()))?;
+    String::from_utf8(bytes).map_err(|e| AppError::Internal(e.to_string()))
+}
+
+fn parse_json(body: &str) -> Result<Vec<Record>, AppError> {
+    let file: BackupFile = serde_json::from_str(body)
+        .map_err(|e| AppError::BadRequest(format!("invalid backup: {}", e)))?;
+    if file.version == 0 || file.version > FORMAT_VERSION {
+        return Err(AppError::BadRequest(format!(
+            "unsupported backup version {} (supported: 1-{})",
+            file.version, FORMAT_VERSION
+        )));
+    }
+    Ok(file.favorites)
+}
+
+fn parse_csv(body: &str) -> Result<Vec<Record>, AppError> {
+    csv::Reader::from_reader(body.as_bytes())
+        .deserialize()
+        .enumerate()
+        .map(|(i, row)| {
+            row.map_err(|e| AppError::BadRequest(format!("invalid CSV row {}: {}", i + 1, e)))
+        })
+        .collect()
+}
+
+/// Tag names in a record's `tags` column.
+fn record_tags(record: &Record) -> Result<Vec<String>, AppError> {
+    let mut names = record
+        .tags
+        .iter()
+        .flat_map(|tags| tags.split(','))
+        .filter(|name| !name.trim().is_empty())
+        .map(super::tags::tag_name)
+        .collect::<Result<Vec<_>, _>>()?;
+    names.sort();
+    names.dedup();
+    Ok(names)
+}
+
+/// Check a record before touching the database. Returns the normalized site type.
+fn validate(record: &Record) -> Result<ModuleType, String> {
+    let module = ModuleType::resolve(&record.type_str)
+        .map_err(|_| format!("unknown type: {}", record.type_str))?;
+    if record.id.trim().is_empty() {
+        return Err("id is empty".into());
+    }
+    if record.read.is_some_and(|r| r < 0) || record.page.is_some_and(|p| p < 0) {
+        return Err("read and page must not be negative".into());
+    }
+    for ts in [&record.added_at, &record.novelupdated_at]
+        .into_iter()
+        .flatten()
+    {
+        NaiveDateTime::parse_from_str(ts, TIMESTAMP_FORMAT)
+            .map_err(|_| format!("invalid timestamp: {}", ts))?;
+    }
+    if record.rating.is_some_and(|r| !(1..=5).contains(&r)) {
+        return Err("rating must be between 1 and 5".into());
+    }
+    if record
+        .notes
+        .as_ref()
+        .is_some_and(|n| n.chars().count() > super::favorites::MAX_NOTES_LEN)
+    {
+        return Err("notes are too long".into());
+    }
+    if let Err(AppError::BadRequest(e)) = record_tags(record) {
+        return Err(e);
+    }
+    Ok(module)
+}
+
+/// Apply a record's notes, rating and tags to the favorite; fields the record
+/// leaves empty are kept. Returns whether anything changed.
+fn apply_annotations(
+    tx: &Connection,
+    user_id: UserId,
+    type_str: &str,
+    record: &Record,
+) -> rusqlite::Result<bool> {
+    let favorites = FavoritesRepo::new(tx);
+    let mut changed = favorites.merge_notes(
+        user_id,
+        type_str,
+        &record.id,
+        record.notes.as_deref(),
+        record.rating,
+    )?;
+    if record.tags.is_some() {
+        let tags = record_tags(record).unwrap_or_default();
+        let current = favorites
+            .get(user_id, type_str, &record.id)?
+            .map(|f| f.tags)
+            .unwrap_or_default();
+        if tags != current {
+            TagsRepo::new(tx).set_for_favorite(user_id, type_str, &record.id, &tags)?;
+            changed = true;
+        }
+    }
+    Ok(changed)
+}
+
+/// Apply records in one transaction; `dry_run` rolls it back so the summary shows
+/// what would change. Shared novel metadata is only inserted, never overwritten.
+fn apply_records(
+    conn: &mut Connection,
+    user_id: UserId,
+    records: &[Record],
+    strategy: Strategy,
+    dry_run: bool,
+) -> rusqlite::Result<ImportSummary> {
+    let mut summary = ImportSummary::default();
+    let tx = conn.transaction()?;
+    for (index, record) in records.iter().enumerate() {
+        let module = match validate(record) {
+            Ok(module) => module,
+            Err(error) => {
+                summary.errors.push(json!({
+                    "index": index,
+                    "type": record.type_str,
+                    "id": record.id,
+                    "error": error,
+                }));
+                continue;
+            }
+        };
+        let type_str = module.as_str();
+        let read = record.read.unwrap_or(0);
+
+        let created = FavoritesRepo::new(&tx).insert_novel(&NewNovel {
+            type_str,
+            id: &record.id,
+            title: record.title.as_deref().unwrap_or(&record.id),
+            page: record.page.unwrap_or(0),
+            novelupdated_at: record.novelupdated_at.as_deref(),
+        })?;
+        if created {
+            summary.fetch.push((module, record.id.clone()));
+        }
+
+        let favorites = FavoritesRepo::new(&tx);
+        let existing = favorites.read_position(user_id, type_str, &record.id)?;
+        let added_at = record.added_at.as_deref();
+        let changed = match (existing, strategy) {
+            (None, _) => {
+                favorites.insert_favorite(user_id, type_str, &record.id, read, added_at)?;
+                apply_annotations(&tx, user_id, type_str, record)?;
+                summary.added += 1;
+                continue;
+            }
+            (Some(_), Strategy::Skip) => false,
+            (Some(_), Strategy::Overwrite) => {
+                let moved =
+                    favorites.overwrite_read(user_id, type_str, &record.id, read, added_at)?;
+                let annotated = apply_annotations(&tx, user_id, type_str, record)?;
+                moved || annotated
+            }
+            (Some(_), Strategy::KeepMaxRead) => {
+                favorites.raise_read(user_id, type_str, &record.id, read)?
+            }
+        };
+        if changed {
+            summary.updated += 1;
+        } else {
+            summary.skipped += 1;
+        }
+    }
+    if dry_run {
+        summary.fetch.clear();
+        tx.rollback()?;
+    } else {
+        tx.commit()?;
+    }
+    Ok(summary)
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/favorites/export",
+    tag = "お気に入り",
+    summary = "お気に入りバックアップ",
+    description = "お気に入り・既読位置・登録日時・メモ・評価・タグをバックアップ用に出力する。\n\n## JSON（デフォルト）\n`{\"version\": 2, \"exported_at\": ..., \"favorites\": [...]}`。`version` は形式のバージョンで、インポートは同じか古いバージョンを受け付ける。\n\n## CSV\nヘッダー行 `type,id,title,page,novelupdated_at,read,added_at,notes,rating,tags` に続けて1行1作品。`tags` はタグ名のカンマ区切り。\n\n日時は `YYYY-MM-DD HH:MM:SS`（UTC）。",
+    params(ExportQuery),
+    responses(
+        (status = 200, description = "バックアップ", body = crate::openapi::BackupFile,
+            example = json!({"version": 2, "exported_at": "2026-03-14 09:00:00", "favorites": [{"type": "narou", "id": "n1234ab", "title": "小説タイトル", "page": 150, "novelupdated_at": "2026-03-13 22:00:00", "read": 42, "added_at": "2025-11-02 12:34:56", "notes": "3章から面白くなる", "rating": 4, "tags": "異世界,読書中"}]})),
+        (status = 400, description = "formatが不正", body = crate::openapi::ErrorResponse,
+            example = json!({"error": "format must be json or csv, got xml"})),
+        (status = 500, description = "DBエラー", body = crate::openapi::ErrorResponse),
+    ),
+)]
+async fn get_export(
+    State(state): State<AppState>,
+    Extension(user_id): Extension<UserId>,
+    Query(query): Query<ExportQuery>,
+) -> Result<impl IntoResponse, AppError> {
+    let format = parse_format(query.format.as_deref())?;
+    let records = state.db.read(move |db| load_records(db, user_id)).await?;
+    let (content_type, filename, body) = match format {
+        Format::Json => (
+            "application/json",
+            "favorites.json",
+            to_json(&records).to_string(),
+        ),
+        Format::Csv => (
+            "text/csv; charset=utf-8",
+            "favorites.csv",
+            to_csv(&records)?,
+        ),
+    };
+    Ok((
+        [
+            (header::CONTENT_TYPE, content_type.to_string()),
+            (
+                header::CONTENT_DISPOSITION,
+                format!("attachment; filename=\"{}\"", filename),
+            ),
+        ],
+        body,
+    ))
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/favorites/import",
+    tag = "お気に入り",
+    summary = "お気に入りリストア",
+    description = "`GET /api/favorites/export` の出力からお気に入りを復元する。リクエストボディにファイルの内容をそのまま送り、`format` で形式を指定する。\n\n## 登録済みの作品（strategy）\n- `skip`（デフォルト）: 変更しない\n- `overwrite`: 既読位置と登録日時をバックアップの値にする。メモ・評価・タグもバックアップに値があれば上書きする\n- `keep-max-read`: 既読位置が進んでいる方を残す\n\n未登録の作品はバックアップの既読位置・登録日時・メモ・評価・タグで追加する。バージョン1のバックアップ（メモ・評価・タグなし）も取り込める。小説のメタデータ（タイトル・話数・更新日時）は全ユーザー共有のため、既にある小説では上書きしない。新しい小説はバックグラウンドでメタデータを取得する。\n\n不正な行は `errors` に返し、残りは取り込む。`dry_run=true` では保存せずに結果だけ返す。",
+    params(ImportQuery),
+    request_body(content = String, description = "バックアップ（JSONまたはCSV）"),
+    responses(
+        (status = 200, description = "取り込み結果", body = crate::openapi::ImportSummary,
+            example = json!({"dry_run": false, "strategy": "keep-max-read", "added": 3, "updated": 1, "skipped": 5, "errors": [{"index": 7, "type": "pixiv", "id": "123", "error": "unknown type: pixiv"}]})),
+        (status = 400, description = "パラメータまたはファイルが不正", body = crate::openapi::ErrorResponse,
+            example = json!({"error": "unsupported backup version 3 (supported: 1-2)"})),
+        (status = 500, description = "DBエラー", body = crate::openapi::ErrorResponse),
+    ),
+)]
+async fn post_import(
+    State(state): State<AppState>,
+    Extension(user_id): Extension<UserId>,
+    Query(query): Query<ImportQuery>,
+    body: String,
+) -> Result<Json<Value>, AppError> {
+    let format = parse_format(query.format.as_deref())?;
+    let strategy = Strategy::parse(query.strategy.as_deref())?;
+    let dry_run = query.dry_run.unwrap_or(false);
+    let records = match format {
+        Format::Json => parse_json(&body)?,
+        Format::Csv => parse_csv(&body)?,
+    };
+    if records.len() > MAX_IMPORT_RECORDS {
+        return Err(AppError::BadRequest(format!(
+            "too many records (max {})",
+            MAX_IMPORT_RECORDS
+        )));
+    }
+
+    let summary = state
+        .db
+        .write(move |db| apply_records(db, user_id, &records, strategy, dry_run))
+        .await?;
+    for module in [
+        ModuleType::Narou,
+        ModuleType::Nocturne,
+        ModuleType::Kakuyomu,
+    ] {
+        let ids = summary
+            .fetch
+            .iter()
+            .filter(|(m, _)| *m == module)
+            .map(|(_, id)| id.clone())
+            .collect();
+        crate::sync::spawn_initial_fetch(state.clone(), module, ids);
+    }
+
+    Ok(Json(json!({
+        "dry_run": dry_run,
+        "strategy": query.strategy.as_deref().unwrap_or("skip"),
+        "added": summary.added,
+        "updated": summary.updated,
+        "skipped": summary.skipped,
+        "errors": summary.errors,
+    })))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rusqlite::OptionalExtension;
+
+    fn seeded() -> Connection {
+        let conn = crate::db::open_memory();
+        conn.execute_batch(
+            "INSERT INTO novels (type, id, title, page, novelupdated_at) VALUES
+                ('narou', 'n1', 'One', 50, '2026-03-01 00:00:00'),
+                ('kakuyomu', '100', 'Two', 20, NULL);
+             INSERT INTO favorites (user_id, type, id, read, added_at) VALUES
+                (1, 'narou', 'n1', 30, '2025-01-01 00:00:00'),
+                (1, 'kakuyomu', '100', 5, '2025-02-01 00:00:00');",
+        )
+        .unwrap();
+        conn
+    }
+
+    fn record(type_str: &str, id: &str, read: i64) -> Record {
+        Record {
+            type_str: type_str.into(),
+            id: id.into(),
+            title: Some(format!("Title {}", id)),
+            page: Some(60),
+            novelupdated_at: None,
+            read: Some(read),
+            added_at: Some("2024-06-01 12:00:00".into()),
+            notes: None,
+            rating: None,
+            tags: None,
+        }
+    }
+
+    fn read_of(conn: &Connection, user: i64, id: &str) -> Option<(i64, String)> {
+        conn.query_row(
+            "SELECT read, added_at FROM favorites WHERE user_id = ?1 AND id = ?2",
+            rusqlite::params![user, id],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )
+        .optional()
+        .unwrap()
+    }
+
+    #[test]
+    fn json_and_csv_round_trip() {
+        let conn = seeded();
+        let records = load_records(&conn, UserId(1)).unwrap();
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[0].read, Some(30));
+        assert_eq!(records[1].novelupdated_at, None);
+
+        let json = to_json(&records).to_string();
+        assert_eq!(parse_json(&json).unwrap(), records);
+
+        let csv = to_csv(&records).unwrap();
+        assert!(
+            csv.starts_with("type,id,title,page,novelupdated_at,read,added_at,notes,rating,tags\n")
+        );
+        assert_eq!(parse_csv(&csv).unwrap(), records);
+    }
+
+    #[test]
+    fn notes_rating_and_tags_round_trip() {
+        let mut conn = seeded();
+        conn.execute_batch(
+            "UPDATE favorites SET notes = 'Gets good, \"really\"', rating = 4 WHERE id = 'n1';
+             INSERT INTO users (id, email) VALUES (2, 'b@example.com');",
+        )
+        .unwrap();
+        let names = vec!["reading".to_string(), "isekai".to_string()];
+        TagsRepo::new(&conn)
+            .set_for_favorite(UserId(1), "narou", "n1", &names)
+            .unwrap();
+        let records = load_records(&conn, UserId(1)).unwrap();
+        assert_eq!(records[0].tags.as_deref(), Some("isekai,reading"));
+        assert_eq!(parse_csv(&to_csv(&records).unwrap()).unwrap(), records);
+
+        apply_records(&mut conn, UserId(2), &records, Strategy::Skip, false).unwrap();
+        let restored = FavoritesRepo::new(&conn)
+            .get(UserId(2), "narou", "n1")
+            .unwrap()
+            .unwrap();
+        assert_eq!(restored.notes.as_deref(), Some("Gets good, \"really\""));
+        assert_eq!(restored.rating, Some(4));
+        assert_eq!(restored.tags, ["isekai", "reading"]);
+
+        // Version 1 CSV files have no annotation columns
+        let v1 = "type,id,title,page,novelupdated_at,read,added_at\nnarou,n1,One,50,,3,\n";
+        let parsed = parse_csv(v1).unwrap();
+        assert_eq!(
+            (parsed[0].notes.as_ref(), parsed[0].tags.as_ref()),
+            (None, None)
+        );
+
+        let mut bad = record("narou", "n1", 1);
+        bad.rating = Some(6);
+        assert!(validate(&bad).is_err());
+    }
+
+    #[test]
+    fn parse_json_checks_version() {
+        assert!(parse_json(r#"{"version": 1, "favorites": []}"#).is_ok());
+        assert!(parse_json(r#"{"version": 2, "favorites": []}"#).is_ok());
+        assert!(parse_json(r#"{"version": 99, "favorites": []}"#).is_err());
+        assert!(parse_json(r#"{"favorites": []}"#).is_err());
+    }
+
+    #[test]
+    fn restore_into_another_user() {
+        let mut conn = seeded();
+        let records = load_records(&conn, UserId(1)).unwrap();
+        conn.execute(
+            "INSERT INTO users (id, email) VALUES (2, 'b@example.com')",
+            [],
+        )
+        .unwrap();
+        let summary = apply_records(&mut conn, UserId(2), &records, Strategy::Skip, false).unwrap();
+        assert_eq!((summary.added, summary.updated, summary.skipped), (2, 0, 0));
+        assert!(summary.fetch.is_empty());
+        assert_eq!(
+            read_of(&conn, 2, "n1"),
+            Some((30, "2025-01-01 00:00:00".into()))
+        );
+    }
+
+    #[test]
+    fn strategies_for_existing_favorites() {
+        let records = [record("narou", "n1", 40), record("kakuyomu", "100", 2)];
+
+        let mut conn = seeded();
+        let summary = apply_records(&mut conn, UserId(1), &records, Strategy::Skip, false).unwrap();
+        assert_eq!((summary.added, summary.updated, summary.skipped), (0, 0, 2));
+        assert_eq!(read_of(&conn, 1, "n1").unwrap().0, 30);
+
+        let mut conn = seeded();
+        let summary =
+            apply_records(&mut conn, UserId(1), &records, Strategy::Overwrite, false).unwrap();
+        assert_eq!((summary.updated, summary.skipped), (2, 0));
+        assert_eq!(
+            read_of(&conn, 1, "100"),
+            Some((2, "2024-06-01 12:00:00".into()))
+        );
+
+        let mut conn = seeded();
+        let summary =
+            apply_records(&mut conn, UserId(1), &records, Strategy::KeepMaxRead, false).unwrap();
+        assert_eq!((summary.updated, summary.skipped), (1, 1));
+        assert_eq!(read_of(&conn, 1, "n1").unwrap().0, 40);
+        assert_eq!(read_of(&conn, 1, "100").unwrap().0, 5);
+    }
+
+    #[test]
+    fn new_novels_are_created_without_touching_shared_metadata() {
+        let mut conn = seeded();
+        let mut known = record("narou", "n1", 1);
+        known.title = Some("Renamed".into());
+        let records = [known, record("nocturne", "n9", 3)];
+        let summary = apply_records(&mut conn, UserId(1), &records, Strategy::Skip, false).unwrap();
+        assert_eq!(summary.added, 1);
+        assert_eq!(summary.fetch, [(ModuleType::Nocturne, "n9".to_string())]);
+        let title: String = conn
+            .query_row("SELECT title FROM novels WHERE id = 'n1'", [], |row| {
+                row.get(0)
+            })
+            .unwrap();
+        assert_eq!(title, "One");
+    }
+
+    #[test]
+    fn invalid_records_are_reported_and_skipped() {
+        let mut conn = seeded();
+        let mut bad_time = record("narou", "n2", 1);
+        bad_time.added_at = Some("yesterday".into());
+        let records = [
+            record("pixiv", "1", 0),
+            record("narou", "", 0),
+            record("narou", "n3", -1),
+            bad_time,
+            record("narou", "n4", 1),
+        ];
+        let summary = apply_records(&mut conn, UserId(1), &records, Strategy::Skip, false).unwrap();
+        assert_eq!(summary.added, 1);
+        let indexes: Vec<i64> = summary
+            .errors
+            .iter()
+            .map(|e| e["index"].as_i64().unwrap())
+            .collect();
+        assert_eq!(indexes, [0, 1, 2, 3]);
+        assert_eq!(summary.errors[0]["error"], "unknown type: pixiv");
+    }
+
+    #[test]
+    fn dry_run_reports_without_saving() {
+        let mut conn = seeded();
+        let records = [record("narou", "n1", 45), record("narou", "n5", 1)];
+        let summary =
+            apply_records(&mut conn, UserId(1), &records, Strategy::KeepMaxRead, true).unwrap();
+        assert_eq!((summary.added, summary.updated), (1, 1));
+        assert!(summary.fetch.is_empty());
+        assert_eq!(read_of(&conn, 1, "n1").unwrap().0, 30);
+        assert_eq!(read_of(&conn, 1, "n5"), None);
+        let novels: i64 = conn
+            .query_row("SELECT COUNT(*) FROM novels", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(novels, 2);
+    }
+}
@@ -0,0 +1,413 @@
+use super::favorites::MAX_NOTES_LEN;
+use crate::auth::UserId;
+use crate::error::AppError;
+use crate::modules::ModuleType;
+use crate::repo::{BookmarkFields, BookmarksRepo};
+use crate::sanitize;
+use crate::state::AppState;
+use axum::extract::{Path, Query, State};
+use axum::routing::{delete, get, patch, post};
+use axum::{Extension, Json, Router};
+use serde::Deserialize;
+use serde_json::{json, Value};
+use utoipa::IntoParams;
+
+pub fn routes() -> Router<AppState> {
+    Router::new()
+        .route("/api/bookmarks", get(get_bookmarks))
+        .route("/api/bookmarks/{id}", patch(patch_bookmark))
+        .route("/api/bookmarks/{id}", delete(delete_bookmark))
+        .route("/api/novel/{type}/{id}/bookmarks", get(get_novel_bookmarks))
+        .route("/api/novel/{type}/{id}/bookmarks", post(post_bookmark))
+        .route(
+            "/api/novel/{type}/{id}/pages/{num}/bookmarks",
+            get(get_page_bookmarks),
+        )
+}
+
+const MAX_QUOTE_LEN: usize = 500;
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+struct BookmarksQuery {
+    /// このしおりIDより古いものを取得する（前のページの `next_before`）
+    before: Option<i64>,
+    /// 取得件数（デフォルト50、最大200）
+    limit: Option<usize>,
+}
+
+#[derive(Deserialize)]
+struct NewBookmarkBody {
+    page: Option<i64>,
+    paragraph: Option<i64>,
+    quote: Option<String>,
+    note: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct BookmarkBody {
+    paragraph: Option<i64>,
+    quote: Option<String>,
+    note: Option<String>,
+}
+
+fn check_paragraph(paragraph: i64) -> Result<i64, AppError> {
+    if paragraph < 0 {
+        return Err(AppError::BadRequest(
+            "paragraph must not be negative".into(),
+        ));
+    }
+    Ok(paragraph)
+}
+
+fn check_quote(quote: String) -> Result<String, AppError> {
+    if quote.trim().is_empty() || quote.chars().count() > MAX_QUOTE_LEN {
+        return Err(AppError::BadRequest(format!(
+            "quote must be 1 to {} characters",
+            MAX_QUOTE_LEN
+        )));
+    }
+    Ok(quote)
+}
+
+/// A blank note is no note.
+fn check_note(note: String) -> Result<Option<String>, AppError> {
+    if note.chars().count() > MAX_NOTES_LEN {
+        return Err(AppError::BadRequest(format!(
+            "note must be at most {} characters",
+            MAX_NOTES_LEN
+        )));
+    }
+    Ok(Some(note).filter(|n| !n.trim().is_empty()))
+}
+
+/// Whitespace differs between a selection in the browser and the page's text,
+/// and revisions often only reflow lines, so it is ignored when matching.
+fn normalize(text: &str) -> String {
+    text.chars().filter(|c| !c.is_whitespace()).collect()
+}
+
+/// Where `quote` is in the chapter now: the paragraph containing it closest
+/// to where it was, preferring the earlier one on a tie. A quote spanning
+/// paragraphs is matched by its first line. None if the text is gone.
+fn locate(paragraphs: &[String], paragraph: i64, quote: &str) -> Option<i64> {
+    let needle = quote.lines().map(normalize).find(|line| !line.is_empty())?;
+    paragraphs
+        .iter()
+        .enumerate()
+        .filter(|(_, text)| normalize(text).contains(&needle))
+        .map(|(i, _)| i as i64)
+        .min_by_key(|&i| ((i - paragraph).abs(), i))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/bookmarks",
+    tag = "しおり",
+    summary = "しおり一覧（全作品）",
+    description = "全作品のしおりを新しい順に取得する。\n\n## ページング\nレスポンスの `next_before` を次のリクエストの `before` に指定すると続きを取得できる。`next_before` が null なら最後のページ。",
+    params(BookmarksQuery),
+    responses(
+        (status = 200, description = "しおり", body = crate::openapi::BookmarksResponse,
+            example = json!({"bookmarks": [{"id": 8, "type": "narou", "novel_id": "n1234ab", "title": "小説タイトル", "page": 3, "paragraph": 41, "quote": "名前：アリシア　職業：魔導士", "note": "キャラ設定", "created_at": "2026-03-14 09:00:00", "updated_at": "2026-03-14 09:00:00"}], "next_before": null})),
+        (status = 500, description = "DBエラー", body = crate::openapi::ErrorResponse),
+    ),
+)]
+async fn get_bookmarks(
+    State(state): State<AppState>,
+    Extension(user_id): Extension<UserId>,
+    Query(query): Query<BookmarksQuery>,
+) -> Result<Json<Value>, AppError> {
+    let limit = super::page_limit(query.limit);
+    let before = query.before;
+
+    let mut bookmarks = state
+        .db
+        .read(move |conn| BookmarksRepo::new(conn).list(user_id, before, limit + 1))
+        .await?;
+    let next_before = if bookmarks.len() > limit {
+        bookmarks.truncate(limit);
+        bookmarks.last().map(|b| b.id)
+    } else {
+        None
+    };
+    Ok(Json(json!({
+        "bookmarks": bookmarks.iter().map(|b| b.to_json()).collect::<Vec<_>>(),
+        "next_before": next_before,
+    })))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/novel/{type}/{id}/bookmarks",
+    tag = "しおり",
+    summary = "しおり一覧（作品別）",
+    description = "1作品のしおりをページ・段落順に取得する。お気に入りに登録していない小説も含む。",
+    params(
+        ("type" = String, Path, description = "対象サイト", example = "narou"),
+        ("id" = String, Path, description = "小説ID", example = "n1234ab"),
+    ),
+    responses(
+        (status = 200, description = "しおり", body = Vec<crate::openapi::Bookmark>),
+        (status = 400, description = "無効なサイト種別", body = crate::openapi::ErrorResponse),
+        (status = 500, description = "DBエラー", body = crate::openapi::ErrorResponse),
+    ),
+)]
+async fn get_novel_bookmarks(
+    State(state): State<AppState>,
+    Extension(user_id): Extension<UserId>,
+    Path((type_str, id)): Path<(String, String)>,
+) -> Result<Json<Value>, AppError> {
+    let type_str = ModuleType::resolve(&type_str)?.as_str().to_string();
+    let bookmarks = state
+        .db
+        .read(move |conn| BookmarksRepo::new(conn).for_novel(user_id, &type_str, &id, None))
+        .await?;
+    Ok(Json(bookmarks.iter().map(|b| b.to_json()).collect()))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/novel/{type}/{id}/pages/{num}/bookmarks",
+    tag = "しおり",
+    summary = "しおり一覧（ページ別・位置補正）",
+    description = "1ページ分のしおりを、現在の本文に合わせた段落位置で取得する。本文が改稿されて段落がずれた場合は、引用文を含む段落（元の位置に最も近いもの）に移動して保存する。引用文が見つからない場合は `found` が false になり、段落位置は元のまま。\n\n段落番号は本文HTMLの `<p>` 要素の0始まりの番号。引用文の空白・改行は無視して照合する。",
+    params(
+        ("type" = String, Path, description = "対象サイト", example = "narou"),
+        ("id" = String, Path, description = "小説ID", example = "n1234ab"),
+        ("num" = i64, Path, description = "ページ番号（1始まり）", example = 3),
+    ),
+    responses(
+        (status = 200, description = "しおり", body = Vec<crate::openapi::LocatedBookmark>,
+            example = json!([{"id": 8, "type": "narou", "novel_id": "n1234ab", "title": "小説タイトル", "page": 3, "paragraph": 43, "quote": "名前：アリシア　職業：魔導士", "note": "キャラ設定", "created_at": "2026-03-14 09:00:00", "updated_at": "2026-03-14 09:00:00", "found": true}])),
+        (status = 400, description = "無効なサイト種別・ページ番号", body = crate::openapi::ErrorResponse),
+        (status = 500, description = "DBエラー", body = crate::openapi::ErrorResponse),
+        (status = 502, description = "外部サイトからの取得に失敗", body = crate::openapi::ErrorResponse),
+    ),
+)]
+async fn get_page_bookmarks(
+    State(state): State<AppState>,
+    Extension(user_id): Extension<UserId>,
+    Path((type_str, id, num)): Path<(String, String, String)>,
+) -> Result<Json<Value>, AppError> {
+    let module = ModuleType::resolve(&type_str)?;
+    let page: i64 = num
+        .parse()
+        .map_err(|_| AppError::BadRequest("page must be a number".into()))?;
+    let type_str = module.as_str().to_string();
+    let bookmarks = {
+        let (type_str, id) = (type_str.clone(), id.clone());
+        state
+            .db
+            .read(move |conn| {
+                BookmarksRepo::new(conn).for_novel(user_id, &type_str, &id, Some(page))
+            })
+            .await?
+    };
+    if bookmarks.is_empty() {
+        return Ok(Json(json!([])));
+    }
+
+    let html = super::pages::page_html(&state, &module, &id, &num).await?;
+    let paragraphs = sanitize::paragraphs(&html);
+    let mut located = Vec::with_capacity(bookmarks.len());
+    let mut moved = Vec::new();
+    for mut bookmark in bookmarks {
+        let found = match locate(&paragraphs, bookmark.paragraph, &bookmark.quote) {
+            Some(paragraph) => {
+                if paragraph != bookmark.paragraph {
+                    bookmark.paragraph = paragraph;
+                    moved.push((bookmark.id, paragraph));
+                }
+                true
+            }
+            None => false,
+        };
+        let mut json = bookmark.to_json();
+        json["found"] = json!(found);
+        located.push(json);
+    }
+    located.sort_by_key(|b| (b["paragraph"].as_i64(), b["id"].as_i64()));
+
+    if !moved.is_empty() {
+        state
+            .db
+            .write(move |conn| {
+                let tx = conn.transaction()?;
+                let repo = BookmarksRepo::new(&tx);
+                for (bookmark_id, paragraph) in moved {
+                    repo.relocate(user_id, bookmark_id, paragraph)?;
+                }
+                tx.commit()
+            })
+            .await?;
+    }
+    Ok(Json(Value::Array(located)))
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/novel/{type}/{id}/bookmarks",
+    tag = "しおり",
+    summary = "しおり追加",
+    description = "本文中の一節にしおりを付ける。引用文は本文が改稿されたときに位置を探し直すために使う。お気に入りに登録していない小説にも付けられる。",
+    params(
+        ("type" = String, Path, description = "対象サイト", example = "narou"),
+        ("id" = String, Path, description = "小説ID", example = "n1234ab"),
+    ),
+    request_body(content = crate::openapi::BookmarkRequest, description = "しおりの位置と内容",
+        example = json!({"page": 3, "paragraph": 41, "quote": "名前：アリシア　職業：魔導士", "note": "キャラ設定"})),
+    responses(
+        (status = 200, description = "追加されたしおり", body = crate::openapi::Bookmark),
+        (status = 400, description = "パラメータ不正", body = crate::openapi::ErrorResponse,
+            example = json!({"error": "quote must be 1 to 500 characters"})),
+        (status = 500, description = "DBエラー", body = crate::openapi::ErrorResponse),
+    ),
+)]
+async fn post_bookmark(
+    State(state): State<AppState>,
+    Extension(user_id): Extension<UserId>,
+    Path((type_str, id)): Path<(String, String)>,
+    Json(body): Json<NewBookmarkBody>,
+) -> Result<Json<Value>, AppError> {
+    let type_str = ModuleType::resolve(&type_str)?.as_str().to_string();
+    let page = body
+        .page
+        .filter(|&p| p >= 1)
+        .ok_or_else(|| AppError::BadRequest("page is required".into()))?;
+    let paragraph = check_paragraph(
+        body.paragraph
+            .ok_or_else(|| AppError::BadRequest("paragraph is required".into()))?,
+    )?;
+    let quote = check_quote(body.quote.unwrap_or_default())?;
+    let note = match body.note {
+        Some(note) => check_note(note)?,
+        None => None,
+    };
+    let bookmark = state
+        .db
+        .write(move |conn| {
+            let fields = BookmarkFields {
+                paragraph,
+                quote: &quote,
+                note: note.as_deref(),
+            };
+            BookmarksRepo::new(conn).create(user_id, &type_str, &id, page, &fields)
+        })
+        .await?;
+    Ok(Json(bookmark.to_json()))
+}
+
+#[utoipa::path(
+    patch,
+    path = "/api/bookmarks/{id}",
+    tag = "しおり",
+    summary = "しおり更新",
+    description = "しおりの段落位置・引用文・メモを変更する。省略した項目はそのまま。メモに空文字を指定すると削除する。",
+    params(
+        ("id" = i64, Path, description = "しおりID", example = 8),
+    ),
+    request_body(content = crate::openapi::BookmarkUpdateRequest, description = "変更する項目",
+        example = json!({"note": "主人公の師匠"})),
+    responses(
+        (status = 200, description = "更新後のしおり", body = crate::openapi::Bookmark),
+        (status = 400, description = "パラメータ不正", body = crate::openapi::ErrorResponse),
+        (status = 404, description = "しおりが存在しない", body = crate::openapi::ErrorResponse),
+        (status = 500, description = "DBエラー", body = crate::openapi::ErrorResponse),
+    ),
+)]
+async fn patch_bookmark(
+    State(state): State<AppState>,
+    Extension(user_id): Extension<UserId>,
+    Path(bookmark_id): Path<i64>,
+    Json(body): Json<BookmarkBody>,
+) -> Result<Json<Value>, AppError> {
+    let paragraph = body.paragraph.map(check_paragraph).transpose()?;
+    let quote = body.quote.map(check_quote).transpose()?;
+    let note = body.note.map(check_note).transpose()?;
+    let bookmark = state
+        .db
+        .write(move |conn| {
+            let tx = conn.transaction()?;
+            let repo = BookmarksRepo::new(&tx);
+            let Some(current) = repo.get(user_id, bookmark_id)? else {
+                return Ok(None);
+            };
+            let fields = BookmarkFields {
+                paragraph: paragraph.unwrap_or(current.paragraph),
+                quote: quote.as_deref().unwrap_or(&current.quote),
+                note: match &note {
+                    Some(note) => note.as_deref(),
+                    None => current.note.as_deref(),
+                },
+            };
+            repo.update(user_id, bookmark_id, &fields)?;
+            let bookmark = repo.get(user_id, bookmark_id)?;
+            tx.commit()?;
+            Ok::<_, rusqlite::Error>(bookmark)
+        })
+        .await?
+        .ok_or_else(|| AppError::NotFound("Not found".into()))?;
+    Ok(Json(bookmark.to_json()))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/api/bookmarks/{id}",
+    tag = "しおり",
+    summary = "しおり削除",
+    params(
+        ("id" = i64, Path, description = "しおりID", example = 8),
+    ),
+    responses(
+        (status = 200, description = "削除成功", body = crate::openapi::OkResponse,
+            example = json!({"ok": true})),
+        (status = 404, description = "しおりが存在しない", body = crate::openapi::ErrorResponse),
+        (status = 500, description = "DBエラー", body = crate::openapi::ErrorResponse),
+    ),
+)]
+async fn delete_bookmark(
+    State(state): State<AppState>,
+    Extension(user_id): Extension<UserId>,
+    Path(bookmark_id): Path<i64>,
+) -> Result<Json<Value>, AppError> {
+    let deleted = state
+        .db
+        .write(move |conn| BookmarksRepo::new(conn).delete(user_id, bookmark_id))
+        .await?;
+    if !deleted {
+        return Err(AppError::NotFound("Not found".into()));
+    }
+    Ok(Json(json!({ "ok": true })))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn chapter(texts: &[&str]) -> Vec<String> {
+        texts.iter().map(|t| t.to_string()).collect()
+    }
+
+    #[test]
+    fn locate_follows_the_quote_to_the_nearest_paragraph() {
+        let paragraphs = chapter(&["前書き", "名前：アリシア", "", "本文", "名前：アリシア"]);
+        assert_eq!(locate(&paragraphs, 1, "名前：アリシア"), Some(1));
+        // Two lines were inserted before it
+        assert_eq!(locate(&paragraphs, 3, "名前：アリシア"), Some(4));
+        // Equally close: the earlier one
+        assert_eq!(locate(&paragraphs, 2, "アリシア"), Some(1));
+        assert_eq!(locate(&paragraphs, 1, "ボブ"), None);
+    }
+
+    #[test]
+    fn locate_ignores_whitespace_and_uses_the_first_line() {
+        let paragraphs = chapter(&["a", "名前：アリシア　職業：魔導士", "次の行"]);
+        assert_eq!(
+            locate(&paragraphs, 0, "名前：アリシア 職業：魔導士"),
+            Some(1)
+        );
+        assert_eq!(locate(&paragraphs, 0, "\n職業：魔導士\n次の行"), Some(1));
+        assert_eq!(locate(&paragraphs, 0, " \n "), None);
+    }
+}
@@ -0,0 +1,195 @@
+use crate::auth::UserId;
+use crate::digest;
+use crate::error::AppError;
+use crate::repo::{DigestSettings, DigestSettingsRepo, UsersRepo};
+use crate::state::AppState;
+use axum::extract::State;
+use axum::routing::get;
+use axum::{Extension, Json, Router};
+use chrono::{DateTime, Utc};
+use lettre::message::Mailbox;
+use serde::Deserialize;
+use serde_json::{json, Value};
+
+pub fn routes() -> Router<AppState> {
+    Router::new().route("/api/digest", get(get_digest).put(put_digest))
+}
+
+#[derive(Deserialize)]
+struct DigestBody {
+    enabled: Option<bool>,
+    frequency: Option<String>,
+    hour: Option<u32>,
+    weekday: Option<u32>,
+}
+
+fn settings_json(settings: &DigestSettings, email: &str, available: bool) -> Value {
+    json!({
+        "enabled": settings.enabled,
+        "frequency": settings.frequency,
+        "hour": settings.hour,
+        "weekday": settings.weekday,
+        "last_sent_at": settings.last_sent_at,
+        "email": email,
+        "available": available,
+    })
+}
+
+/// Restarts the window whenever the digest is switched on, so enabling (or
+/// re-enabling after a pause) doesn't immediately mail old updates.
+fn window_start(current: &DigestSettings, enabled: bool, now: DateTime<Utc>) -> Option<String> {
+    if enabled && !current.enabled {
+        Some(now.format("%Y-%m-%d %H:%M:%S").to_string())
+    } else {
+        current.last_sent_at.clone()
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/digest",
+    tag = "メールダイジェスト",
+    summary = "ダイジェスト設定取得",
+    description = "ログインユーザーのメールダイジェスト設定を取得する。未設定の場合は無効状態のデフォルト値を返す。`available` はサーバーにSMTPが設定されているか。",
+    responses(
+        (status = 200, description = "ダイジェスト設定", body = crate::openapi::DigestSettings,
+            example = json!({"enabled": true, "frequency": "daily", "hour": 22, "weekday": 0, "last_sent_at": "2026-03-13 22:00:00", "email": "alice@example.com", "available": true})),
+        (status = 500, description = "DBエラー", body = crate::openapi::ErrorResponse),
+    ),
+)]
+async fn get_digest(
+    State(state): State<AppState>,
+    Extension(user_id): Extension<UserId>,
+) -> Result<Json<Value>, AppError> {
+    let (settings, email) = state
+        .db
+        .read(move |conn| {
+            Ok::<_, rusqlite::Error>((
+                DigestSettingsRepo::new(conn).get(user_id)?,
+                UsersRepo::new(conn).email(user_id)?,
+            ))
+        })
+        .await?;
+    Ok(Json(settings_json(
+        &settings,
+        &email,
+        state.config.smtp.is_some(),
+    )))
+}
+
+#[utoipa::path(
+    put,
+    path = "/api/digest",
+    tag = "メールダイジェスト",
+    summary = "ダイジェスト設定更新",
+    description = "メールダイジェストの購読設定を更新する。省略した項目は現在の値を維持する。\n\n同期で検出したお気に入りの新着話を、指定した時刻（UTC）にまとめてメール送信する。`weekly` の場合は `weekday`（0=月曜〜6=日曜）の同時刻に送信する。新着がない回は送信しない。\n\n送信先はログインユーザーのメールアドレス。メールアドレスとして解釈できないユーザー（ゲストなど）やSMTP未設定のサーバーでは有効にできない。送信時にアドレスが使えないと判明した場合はダイジェストを無効にする。",
+    request_body(content = crate::openapi::DigestRequest, description = "ダイジェスト設定",
+        example = json!({"enabled": true, "frequency": "weekly", "hour": 0, "weekday": 5})),
+    responses(
+        (status = 200, description = "更新後の設定", body = crate::openapi::DigestSettings,
+            example = json!({"enabled": true, "frequency": "weekly", "hour": 0, "weekday": 5, "last_sent_at": "2026-03-14 09:12:00", "email": "alice@example.com", "available": true})),
+        (status = 400, description = "設定値が不正、または有効化できない", body = crate::openapi::ErrorResponse,
+            example = json!({"error": "hour must be between 0 and 23"})),
+        (status = 500, description = "DBエラー", body = crate::openapi::ErrorResponse),
+    ),
+)]
+async fn put_digest(
+    State(state): State<AppState>,
+    Extension(user_id): Extension<UserId>,
+    Json(body): Json<DigestBody>,
+) -> Result<Json<Value>, AppError> {
+    if let Some(f) = &body.frequency {
+        if !digest::FREQUENCIES.contains(&f.as_str()) {
+            return Err(AppError::BadRequest(format!(
+                "frequency must be one of: {}",
+                digest::FREQUENCIES.join(", ")
+            )));
+        }
+    }
+    if body.hour.is_some_and(|h| h > 23) {
+        return Err(AppError::BadRequest("hour must be between 0 and 23".into()));
+    }
+    if body.weekday.is_some_and(|w| w > 6) {
+        return Err(AppError::BadRequest(
+            "weekday must be between 0 (Monday) and 6 (Sunday)".into(),
+        ));
+    }
+
+    let available = state.config.smtp.is_some();
+    let (settings, email) = state
+        .db
+        .write(move |conn| {
+            let repo = DigestSettingsRepo::new(conn);
+            let email = UsersRepo::new(conn).email(user_id)?;
+            let current = repo.get(user_id)?;
+            let enabled = body.enabled.unwrap_or(current.enabled);
+            if enabled && !current.enabled {
+                if !available {
+                    return Err(AppError::BadRequest(
+                        "email delivery is not configured on this server".into(),
+                    ));
+                }
+                if email.parse::<Mailbox>().is_err() {
+                    return Err(AppError::BadRequest(
+                        "a signed-in user with an email address is required".into(),
+                    ));
+                }
+            }
+            let last_sent_at = window_start(&current, enabled, Utc::now());
+            repo.save(
+                user_id,
+                &DigestSettings {
+                    enabled,
+                    frequency: body.frequency.unwrap_or(current.frequency),
+                    hour: body.hour.unwrap_or(current.hour),
+                    weekday: body.weekday.unwrap_or(current.weekday),
+                    last_sent_at,
+                },
+            )?;
+            Ok((repo.get(user_id)?, email))
+        })
+        .await?;
+    Ok(Json(settings_json(&settings, &email, available)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(s: &str) -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339(s).unwrap().with_timezone(&Utc)
+    }
+
+    #[test]
+    fn window_restarts_when_digest_is_re_enabled() {
+        let mut settings = DigestSettings::default();
+        settings.last_sent_at = window_start(&settings, true, at("2026-03-01T22:00:00Z"));
+        settings.enabled = true;
+        assert_eq!(
+            settings.last_sent_at.as_deref(),
+            Some("2026-03-01 22:00:00")
+        );
+
+        // Disabling keeps the old timestamp
+        settings.last_sent_at = window_start(&settings, false, at("2026-03-02T08:00:00Z"));
+        settings.enabled = false;
+        assert_eq!(
+            settings.last_sent_at.as_deref(),
+            Some("2026-03-01 22:00:00")
+        );
+
+        // Re-enabling days later starts a fresh window instead of mailing the gap
+        settings.last_sent_at = window_start(&settings, true, at("2026-03-10T12:30:00Z"));
+        settings.enabled = true;
+        assert_eq!(
+            settings.last_sent_at.as_deref(),
+            Some("2026-03-10 12:30:00")
+        );
+
+        // Saving other settings while enabled leaves the window alone
+        assert_eq!(
+            window_start(&settings, true, at("2026-03-11T00:00:00Z")).as_deref(),
+            Some("2026-03-10 12:30:00")
+        );
+    }
+}
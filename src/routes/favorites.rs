@@ -1,29 +1,30 @@
 use crate::auth::UserId;
 use crate::error::AppError;
 use crate::modules::ModuleType;
+use crate::repo::{
+    Favorite, FavoriteSort, FavoriteStatus, FavoritesCursor, FavoritesFilter, FavoritesRepo,
+    NewNovel, Progress, ReadingEventsRepo, RecentReadsRepo, TagsRepo, PROGRESS_TIME_FORMAT,
+};
 use crate::state::AppState;
-use axum::extract::{Path, State};
+use axum::extract::{Path, Query, State};
 use axum::routing::{delete, get, patch, put};
 use axum::{Extension, Json, Router};
+use chrono::{DateTime, Utc};
 use serde::Deserialize;
 use serde_json::{json, Value};
-
-fn map_favorite_row(row: &rusqlite::Row) -> rusqlite::Result<Value> {
-    Ok(json!({
-        "type": row.get::<_, String>(0)?,
-        "id": row.get::<_, String>(1)?,
-        "title": row.get::<_, String>(2)?,
-        "novelupdated_at": row.get::<_, Option<String>>(3)?,
-        "page": row.get::<_, i64>(4)?,
-        "read": row.get::<_, i64>(5)?,
-    }))
-}
+use utoipa::IntoParams;
 
 pub fn routes() -> Router<AppState> {
     Router::new()
         .route("/api/favorites", get(get_favorites))
+        .route("/api/favorites/order", put(put_order))
         .route("/api/favorites/{type}/{id}", put(put_favorite))
         .route("/api/favorites/{type}/{id}", delete(delete_favorite))
+        .route("/api/favorites/{type}/{id}/tags", put(put_favorite_tags))
+        .route("/api/favorites/{type}/{id}/notes", get(get_notes))
+        .route("/api/favorites/{type}/{id}/notes", put(put_notes))
+        .route("/api/favorites/{type}/{id}/notes", delete(delete_notes))
+        .route("/api/favorites/{type}/{id}/progress", get(get_progress))
         .route("/api/favorites/{type}/{id}/progress", patch(patch_progress))
 }
 
@@ -37,6 +38,116 @@ struct FavoriteBody {
 #[derive(Deserialize)]
 struct ProgressBody {
     read: Option<i64>,
+    paragraph: Option<i64>,
+    fraction: Option<f64>,
+    updated_at: Option<String>,
+    device: Option<String>,
+    /// Kept with a non-favorite's progress so it can be promoted later
+    title: Option<String>,
+    page: Option<i64>,
+}
+
+/// Longest device label kept in the reading history
+const MAX_DEVICE_LEN: usize = 64;
+
+/// Normalize the client's `updated_at` for last-write-wins comparison. Clocks
+/// ahead of the server are clamped to now, so one bad device can't pin the
+/// progress in the future.
+fn progress_time(updated_at: Option<&str>, now: DateTime<Utc>) -> Result<String, AppError> {
+    let at = match updated_at {
+        Some(s) => DateTime::parse_from_rfc3339(s)
+            .map_err(|_| AppError::BadRequest("updated_at must be RFC 3339".into()))?
+            .with_timezone(&Utc)
+            .min(now),
+        None => now,
+    };
+    Ok(at.format(PROGRESS_TIME_FORMAT).to_string())
+}
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+struct FavoritesQuery {
+    /// このタグが付いたお気に入りに限定する
+    tag: Option<String>,
+    /// 掲載状態（active / gone）で絞り込む
+    status: Option<String>,
+    /// 対象サイト（narou / nocturne / kakuyomu、カンマ区切りで複数指定可）
+    site: Option<String>,
+    /// `true` で未読話のあるもの、`false` で未読話のないものに限定する
+    unread: Option<bool>,
+    /// タイトル・メモ・タグ名の検索語（空白区切りで全てを含むもの、大文字小文字を区別しない）
+    q: Option<String>,
+    /// 並び順: `updated`（小説更新日時の降順、デフォルト）、`added`（登録日時の降順）、`title`（タイトル順）、`unread`（未読話数の降順）、`manual`（`PUT /api/favorites/order` で保存した順）
+    sort: Option<String>,
+    /// 1ページの件数（デフォルト50、最大200）。指定するとページング形式のレスポンスになる
+    limit: Option<usize>,
+    /// 前のページの `next_cursor`。指定するとページング形式のレスポンスになる
+    cursor: Option<String>,
+}
+
+impl FavoritesQuery {
+    fn status(&self) -> Result<Option<FavoriteStatus>, AppError> {
+        match self.status.as_deref() {
+            None => Ok(None),
+            Some("active") => Ok(Some(FavoriteStatus::Active)),
+            Some("gone") => Ok(Some(FavoriteStatus::Gone)),
+            Some(other) => Err(AppError::BadRequest(format!(
+                "status must be active or gone, got {}",
+                other
+            ))),
+        }
+    }
+
+    fn sort(&self) -> Result<FavoriteSort, AppError> {
+        match self.sort.as_deref() {
+            None | Some("updated") => Ok(FavoriteSort::Updated),
+            Some("added") => Ok(FavoriteSort::Added),
+            Some("title") => Ok(FavoriteSort::Title),
+            Some("unread") => Ok(FavoriteSort::Unread),
+            Some("manual") => Ok(FavoriteSort::Manual),
+            Some(other) => Err(AppError::BadRequest(format!(
+                "sort must be updated, added, title, unread or manual, got {}",
+                other
+            ))),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct TagsBody {
+    tags: Option<Vec<String>>,
+}
+
+#[derive(Deserialize)]
+struct NotesBody {
+    notes: Option<String>,
+    rating: Option<i64>,
+    tags: Option<Vec<String>>,
+}
+
+pub(super) const MAX_NOTES_LEN: usize = 10_000;
+
+/// Trim, check and dedupe tag names from a request body.
+fn tag_names(tags: &[String]) -> Result<Vec<String>, AppError> {
+    let mut names = tags
+        .iter()
+        .map(|name| super::tags::tag_name(name))
+        .collect::<Result<Vec<_>, _>>()?;
+    names.sort();
+    names.dedup();
+    Ok(names)
+}
+
+#[derive(Deserialize)]
+struct OrderEntry {
+    #[serde(rename = "type")]
+    type_str: String,
+    id: String,
+}
+
+#[derive(Deserialize)]
+struct OrderBody {
+    favorites: Option<Vec<OrderEntry>>,
 }
 
 #[utoipa::path(
@@ -44,29 +155,296 @@ struct ProgressBody {
     path = "/api/favorites",
     tag = "お気に入り",
     summary = "お気に入り一覧取得",
-    description = "お気に入りに登録された小説の一覧を取得する。小説更新日時の降順でソートされる（更新日時のないものは末尾）。キャッシュなし。",
+    description = "お気に入りに登録された小説の一覧を取得する。デフォルトでは小説更新日時の降順でソートされる（更新日時のないものは末尾）。`sort` で登録日時・タイトル・未読話数の順、`sort=manual` で保存した手動の並び順（未設定のものは末尾に更新日時順）になる。`tag`・`status`・`site`・`unread` で絞り込み、`q` でタイトル・メモ・タグ名を検索できる。キャッシュなし。\n\n## ページング\n`limit` または `cursor` を指定すると、配列ではなく `{\"favorites\": [...], \"next_cursor\": ..., \"total\": ...}` 形式（`FavoritesPage`）で返す。`total` は絞り込み条件に一致する総件数。`next_cursor` を次のリクエストの `cursor` に、`sort` ・絞り込み条件を変えずに指定すると続きを取得できる。`next_cursor` が null なら最後のページ。どちらも指定しない場合は従来どおり全件を配列で返す。\n\n掲載元で削除・非公開になった小説は、同期で3回連続「見つからない」と判定された時点で `status: \"gone\"` となり、`gone_at` に消失を検知した日付が入る。お気に入り自体は削除されない。",
+    params(FavoritesQuery),
     responses(
         (status = 200, description = "お気に入り一覧", body = Vec<crate::openapi::Favorite>,
-            example = json!([{"type": "narou", "id": "n1234ab", "title": "小説タイトル", "novelupdated_at": "2026-02-15T00:00:00", "page": 150, "read": 42}])),
+            example = json!([{"type": "narou", "id": "n1234ab", "title": "小説タイトル", "novelupdated_at": "2026-02-15T00:00:00", "page": 150, "read": 42, "status": "active", "gone_at": null, "tags": ["読書中"], "sort_order": null}])),
+        (status = 400, description = "パラメータ不正", body = crate::openapi::ErrorResponse,
+            example = json!({"error": "status must be active or gone, got all"})),
         (status = 500, description = "DBエラー", body = crate::openapi::ErrorResponse),
     ),
 )]
 async fn get_favorites(
     State(state): State<AppState>,
     Extension(user_id): Extension<UserId>,
+    Query(query): Query<FavoritesQuery>,
 ) -> Result<Json<Value>, AppError> {
-    let rows = {
-        let db = state.db.lock().unwrap();
-        let mut stmt = db.prepare(
-            "SELECT type, id, title, novelupdated_at, page, read FROM favorites
-             WHERE user_id = ?1 ORDER BY novelupdated_at DESC NULLS LAST",
-        )?;
-        let rows = stmt
-            .query_map([user_id.0], map_favorite_row)?
-            .collect::<Result<Vec<Value>, _>>()?;
-        rows
+    let status = query.status()?;
+    let sort = query.sort()?;
+    let sites = super::rss::site_list(query.site.as_deref())?;
+    let after = query
+        .cursor
+        .as_deref()
+        .map(|cursor| {
+            FavoritesCursor::decode(sort, cursor)
+                .ok_or_else(|| AppError::BadRequest("invalid cursor".into()))
+        })
+        .transpose()?;
+    // Without paging parameters the whole list is returned as a plain array, as before
+    let paged = query.limit.is_some() || after.is_some();
+    let limit = super::page_limit(query.limit);
+    let unread = query.unread;
+    let tag = query.tag.filter(|t| !t.is_empty());
+    let q = query.q.filter(|q| !q.trim().is_empty());
+    let (mut favorites, total) = state
+        .db
+        .read(move |conn| {
+            let repo = FavoritesRepo::new(conn);
+            let filter = FavoritesFilter {
+                tag: tag.as_deref(),
+                status,
+                query: q.as_deref(),
+                sites: sites.as_deref(),
+                unread,
+                sort,
+                after: after.as_ref(),
+                limit: paged.then_some(limit + 1),
+            };
+            let favorites = repo.list(user_id, &filter)?;
+            let total = if paged {
+                Some(repo.count(user_id, &filter)?)
+            } else {
+                None
+            };
+            Ok::<_, rusqlite::Error>((favorites, total))
+        })
+        .await?;
+    let Some(total) = total else {
+        return Ok(Json(favorites.iter().map(|f| f.to_json()).collect()));
+    };
+    let next_cursor = if favorites.len() > limit {
+        favorites.truncate(limit);
+        favorites
+            .last()
+            .map(|f| FavoritesCursor::after(sort, f).encode())
+    } else {
+        None
     };
-    Ok(Json(Value::Array(rows)))
+    Ok(Json(json!({
+        "favorites": favorites.iter().map(|f| f.to_json()).collect::<Vec<_>>(),
+        "next_cursor": next_cursor,
+        "total": total,
+    })))
+}
+
+#[utoipa::path(
+    put,
+    path = "/api/favorites/{type}/{id}/tags",
+    tag = "お気に入り",
+    summary = "お気に入りのタグ設定",
+    description = "お気に入りのタグを指定したものに置き換える。未作成のタグは作成される。空配列で全てのタグを外す。",
+    params(
+        ("type" = String, Path, description = "対象サイト", example = "narou"),
+        ("id" = String, Path, description = "小説ID", example = "n1234ab"),
+    ),
+    request_body(content = crate::openapi::FavoriteTagsRequest, description = "タグ名の配列",
+        example = json!({"tags": ["読書中", "異世界"]})),
+    responses(
+        (status = 200, description = "更新されたお気に入り", body = crate::openapi::Favorite),
+        (status = 400, description = "tagsフィールド不足、またはタグ名が不正", body = crate::openapi::ErrorResponse,
+            example = json!({"error": "tags is required"})),
+        (status = 404, description = "お気に入りが存在しない", body = crate::openapi::ErrorResponse),
+        (status = 500, description = "DBエラー", body = crate::openapi::ErrorResponse),
+    ),
+)]
+async fn put_favorite_tags(
+    State(state): State<AppState>,
+    Extension(user_id): Extension<UserId>,
+    Path((type_str, id)): Path<(String, String)>,
+    Json(body): Json<TagsBody>,
+) -> Result<Json<Value>, AppError> {
+    ModuleType::resolve(&type_str)?;
+    let names = tag_names(
+        &body
+            .tags
+            .ok_or_else(|| AppError::BadRequest("tags is required".into()))?,
+    )?;
+
+    let favorite = state
+        .db
+        .write(move |conn| {
+            let tx = conn.transaction()?;
+            let favorites = FavoritesRepo::new(&tx);
+            if favorites.get(user_id, &type_str, &id)?.is_none() {
+                return Ok(None);
+            }
+            TagsRepo::new(&tx).set_for_favorite(user_id, &type_str, &id, &names)?;
+            let favorite = favorites.get(user_id, &type_str, &id)?;
+            tx.commit()?;
+            Ok::<_, rusqlite::Error>(favorite)
+        })
+        .await?
+        .ok_or_else(|| AppError::NotFound("Not found".into()))?;
+    Ok(Json(favorite.to_json()))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/favorites/{type}/{id}/notes",
+    tag = "お気に入り",
+    summary = "メモ・評価取得",
+    description = "お気に入りに付けた自分だけのメモ・評価（1〜5）・タグを取得する。",
+    params(
+        ("type" = String, Path, description = "対象サイト", example = "narou"),
+        ("id" = String, Path, description = "小説ID", example = "n1234ab"),
+    ),
+    responses(
+        (status = 200, description = "メモ・評価・タグ", body = crate::openapi::Notes,
+            example = json!({"notes": "3章から面白くなる", "rating": 4, "tags": ["異世界"]})),
+        (status = 404, description = "お気に入りが存在しない", body = crate::openapi::ErrorResponse),
+        (status = 500, description = "DBエラー", body = crate::openapi::ErrorResponse),
+    ),
+)]
+async fn get_notes(
+    State(state): State<AppState>,
+    Extension(user_id): Extension<UserId>,
+    Path((type_str, id)): Path<(String, String)>,
+) -> Result<Json<Value>, AppError> {
+    ModuleType::resolve(&type_str)?;
+    let favorite = state
+        .db
+        .read(move |conn| FavoritesRepo::new(conn).get(user_id, &type_str, &id))
+        .await?
+        .ok_or_else(|| AppError::NotFound("Not found".into()))?;
+    Ok(Json(favorite.notes_json()))
+}
+
+#[utoipa::path(
+    put,
+    path = "/api/favorites/{type}/{id}/notes",
+    tag = "お気に入り",
+    summary = "メモ・評価保存",
+    description = "お気に入りのメモ（10000文字まで）と評価（1〜5）を保存する。省略した項目は消去される。`tags` を指定するとタグも置き換える（省略時は変更しない）。",
+    params(
+        ("type" = String, Path, description = "対象サイト", example = "narou"),
+        ("id" = String, Path, description = "小説ID", example = "n1234ab"),
+    ),
+    request_body(content = crate::openapi::Notes, description = "メモ・評価・タグ",
+        example = json!({"notes": "3章から面白くなる", "rating": 4, "tags": ["異世界"]})),
+    responses(
+        (status = 200, description = "保存されたメモ・評価・タグ", body = crate::openapi::Notes),
+        (status = 400, description = "値が範囲外", body = crate::openapi::ErrorResponse,
+            example = json!({"error": "rating must be between 1 and 5"})),
+        (status = 404, description = "お気に入りが存在しない", body = crate::openapi::ErrorResponse),
+        (status = 500, description = "DBエラー", body = crate::openapi::ErrorResponse),
+    ),
+)]
+async fn put_notes(
+    State(state): State<AppState>,
+    Extension(user_id): Extension<UserId>,
+    Path((type_str, id)): Path<(String, String)>,
+    Json(body): Json<NotesBody>,
+) -> Result<Json<Value>, AppError> {
+    ModuleType::resolve(&type_str)?;
+    if body.rating.is_some_and(|r| !(1..=5).contains(&r)) {
+        return Err(AppError::BadRequest(
+            "rating must be between 1 and 5".into(),
+        ));
+    }
+    let notes = body.notes.filter(|n| !n.trim().is_empty());
+    if notes
+        .as_ref()
+        .is_some_and(|n| n.chars().count() > MAX_NOTES_LEN)
+    {
+        return Err(AppError::BadRequest(format!(
+            "notes must be at most {} characters",
+            MAX_NOTES_LEN
+        )));
+    }
+    let rating = body.rating;
+    let tags = body.tags.as_deref().map(tag_names).transpose()?;
+
+    let favorite = state
+        .db
+        .write(move |conn| {
+            let tx = conn.transaction()?;
+            let favorites = FavoritesRepo::new(&tx);
+            if !favorites.set_notes(user_id, &type_str, &id, notes.as_deref(), rating)? {
+                return Ok(None);
+            }
+            if let Some(tags) = &tags {
+                TagsRepo::new(&tx).set_for_favorite(user_id, &type_str, &id, tags)?;
+            }
+            let favorite = favorites.get(user_id, &type_str, &id)?;
+            tx.commit()?;
+            Ok::<_, rusqlite::Error>(favorite)
+        })
+        .await?
+        .ok_or_else(|| AppError::NotFound("Not found".into()))?;
+    Ok(Json(favorite.notes_json()))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/api/favorites/{type}/{id}/notes",
+    tag = "お気に入り",
+    summary = "メモ・評価削除",
+    description = "お気に入りのメモと評価を消去する。タグはそのまま。",
+    params(
+        ("type" = String, Path, description = "対象サイト", example = "narou"),
+        ("id" = String, Path, description = "小説ID", example = "n1234ab"),
+    ),
+    responses(
+        (status = 200, description = "削除成功", body = crate::openapi::OkResponse,
+            example = json!({"ok": true})),
+        (status = 404, description = "お気に入りが存在しない", body = crate::openapi::ErrorResponse),
+        (status = 500, description = "DBエラー", body = crate::openapi::ErrorResponse),
+    ),
+)]
+async fn delete_notes(
+    State(state): State<AppState>,
+    Extension(user_id): Extension<UserId>,
+    Path((type_str, id)): Path<(String, String)>,
+) -> Result<Json<Value>, AppError> {
+    ModuleType::resolve(&type_str)?;
+    let found = state
+        .db
+        .write(move |conn| FavoritesRepo::new(conn).set_notes(user_id, &type_str, &id, None, None))
+        .await?;
+    if !found {
+        return Err(AppError::NotFound("Not found".into()));
+    }
+    Ok(Json(json!({ "ok": true })))
+}
+
+#[utoipa::path(
+    put,
+    path = "/api/favorites/order",
+    tag = "お気に入り",
+    summary = "お気に入りの並び順保存",
+    description = "お気に入りの手動の並び順を保存する。指定した順に `sort_order` が0から振られ、指定しなかったお気に入りは並び順が解除される（`sort=manual` では末尾に更新日時順）。お気に入りに存在しない項目は無視される。",
+    request_body(content = crate::openapi::OrderRequest, description = "先頭からの並び順",
+        example = json!({"favorites": [{"type": "narou", "id": "n1234ab"}, {"type": "kakuyomu", "id": "16816452219000000000"}]})),
+    responses(
+        (status = 200, description = "並び順が保存された件数", body = crate::openapi::OrderResponse,
+            example = json!({"ordered": 2})),
+        (status = 400, description = "favoritesフィールド不足", body = crate::openapi::ErrorResponse,
+            example = json!({"error": "favorites is required"})),
+        (status = 500, description = "DBエラー", body = crate::openapi::ErrorResponse),
+    ),
+)]
+async fn put_order(
+    State(state): State<AppState>,
+    Extension(user_id): Extension<UserId>,
+    Json(body): Json<OrderBody>,
+) -> Result<Json<Value>, AppError> {
+    let order: Vec<(String, String)> = body
+        .favorites
+        .ok_or_else(|| AppError::BadRequest("favorites is required".into()))?
+        .into_iter()
+        .map(|entry| (entry.type_str, entry.id))
+        .collect();
+    let ordered = state
+        .db
+        .write(move |conn| {
+            let tx = conn.transaction()?;
+            let ordered = FavoritesRepo::new(&tx).set_order(user_id, &order)?;
+            tx.commit()?;
+            Ok::<_, rusqlite::Error>(ordered)
+        })
+        .await?;
+    Ok(Json(json!({ "ordered": ordered })))
 }
 
 #[utoipa::path(
@@ -74,7 +452,7 @@ async fn get_favorites(
     path = "/api/favorites/{type}/{id}",
     tag = "お気に入り",
     summary = "お気に入り登録・更新",
-    description = "お気に入りを追加または更新する（UPSERT動作）。登録後、バックグラウンドで小説のメタデータを非同期取得し、タイトル・ページ数・更新日時を最新化する。",
+    description = "お気に入りを追加する（登録済みの場合は何もしない）。最近読んだ小説に含まれていた場合は既読位置を引き継ぎ、一覧から外す。小説のメタデータ（タイトル・ページ数・更新日時）は全ユーザーで共有されるため、既に登録されている小説ではリクエストの値で上書きしない。登録後、バックグラウンドで小説のメタデータを非同期取得し、タイトル・ページ数・更新日時を最新化する。",
     params(
         ("type" = String, Path, description = "対象サイト（narou / nocturne / kakuyomu）", example = "narou"),
         ("id" = String, Path, description = "小説ID", example = "n1234ab"),
@@ -101,43 +479,61 @@ async fn put_favorite(
     let page = body
         .page
         .ok_or_else(|| AppError::BadRequest("title and page are required".into()))?;
-    let novelupdated_at = body.novelupdated_at;
+    let favorite = add_favorite(
+        &state,
+        user_id,
+        module,
+        id,
+        title,
+        page,
+        body.novelupdated_at,
+    )
+    .await?;
+    Ok(Json(favorite.to_json()))
+}
 
+/// Add a favorite, carrying over its position if it was recently read, and
+/// start fetching its metadata. Shared with promotion from the recent list.
+pub(super) async fn add_favorite(
+    state: &AppState,
+    user_id: UserId,
+    module: ModuleType,
+    id: String,
+    title: String,
+    page: i64,
+    novelupdated_at: Option<String>,
+) -> Result<Favorite, AppError> {
+    let type_str = module.as_str();
     let favorite = {
-        let db = state.db.lock().unwrap();
-        db.execute(
-            "INSERT INTO favorites (user_id, type, id, title, page, novelupdated_at, read) VALUES (?1, ?2, ?3, ?4, ?5, ?6, 0)
-             ON CONFLICT(user_id, type, id) DO UPDATE SET title = ?4, page = ?5, novelupdated_at = ?6",
-            rusqlite::params![user_id.0, type_str, id, title, page, novelupdated_at],
-        )?;
-        let mut stmt = db.prepare(
-            "SELECT type, id, title, novelupdated_at, page, read FROM favorites WHERE user_id = ?1 AND type = ?2 AND id = ?3",
-        )?;
-        stmt.query_row(rusqlite::params![user_id.0, type_str, id], map_favorite_row)?
+        let id = id.clone();
+        state
+            .db
+            .write(move |conn| {
+                let tx = conn.transaction()?;
+                let favorites = FavoritesRepo::new(&tx);
+                favorites.upsert(
+                    user_id,
+                    &NewNovel {
+                        type_str,
+                        id: &id,
+                        title: &title,
+                        page,
+                        novelupdated_at: novelupdated_at.as_deref(),
+                    },
+                )?;
+                RecentReadsRepo::new(&tx).carry_over(user_id, type_str, &id)?;
+                let favorite = favorites.get(user_id, type_str, &id)?;
+                tx.commit()?;
+                Ok::<_, rusqlite::Error>(favorite)
+            })
+            .await?
+            .ok_or_else(|| AppError::Internal("favorite vanished after insert".into()))?
     };
 
     // Fire-and-forget: fetch metadata immediately after adding
-    let state_clone = state.clone();
-    let id_clone = id.clone();
-    let type_clone = type_str.clone();
-    tokio::spawn(async move {
-        match module.fetch_datum(&state_clone.http, &id_clone).await {
-            Ok(datum) => {
-                crate::sync::update_favorite_from_datum(&state_clone.db, &type_clone, &datum);
-                tracing::info!("[sync] initial fetch for {}/{}", type_clone, id_clone);
-            }
-            Err(e) => {
-                tracing::error!(
-                    "[sync] initial fetch failed for {}/{}: {}",
-                    type_clone,
-                    id_clone,
-                    e
-                );
-            }
-        }
-    });
+    crate::sync::spawn_initial_fetch(state.clone(), module, vec![id]);
 
-    Ok(Json(favorite))
+    Ok(favorite)
 }
 
 #[utoipa::path(
@@ -163,34 +559,68 @@ async fn delete_favorite(
     Path((type_str, id)): Path<(String, String)>,
 ) -> Result<Json<Value>, AppError> {
     ModuleType::resolve(&type_str)?;
-    let changes = {
-        let db = state.db.lock().unwrap();
-        db.execute(
-            "DELETE FROM favorites WHERE user_id = ?1 AND type = ?2 AND id = ?3",
-            rusqlite::params![user_id.0, type_str, id],
-        )?
-    };
-    if changes == 0 {
+    let deleted = state
+        .db
+        .write(move |conn| FavoritesRepo::new(conn).delete(user_id, &type_str, &id))
+        .await?;
+    if !deleted {
         return Err(AppError::NotFound("Not found".into()));
     }
     Ok(Json(json!({ "ok": true })))
 }
 
+#[utoipa::path(
+    get,
+    path = "/api/favorites/{type}/{id}/progress",
+    tag = "お気に入り",
+    summary = "既読位置取得",
+    description = "既読ページ番号と章内の読書位置を取得する。お気に入りでない小説は最近読んだ小説（`GET /api/recent`）の位置を返す。",
+    params(
+        ("type" = String, Path, description = "対象サイト", example = "narou"),
+        ("id" = String, Path, description = "小説ID", example = "n1234ab"),
+    ),
+    responses(
+        (status = 200, description = "既読位置", body = crate::openapi::Progress,
+            example = json!({"read": 42, "position": {"paragraph": 120, "fraction": 0.35}, "progress_at": "2026-03-14T09:00:00.000Z"})),
+        (status = 404, description = "お気に入りにも最近読んだ小説にも存在しない", body = crate::openapi::ErrorResponse),
+        (status = 500, description = "DBエラー", body = crate::openapi::ErrorResponse),
+    ),
+)]
+async fn get_progress(
+    State(state): State<AppState>,
+    Extension(user_id): Extension<UserId>,
+    Path((type_str, id)): Path<(String, String)>,
+) -> Result<Json<Value>, AppError> {
+    ModuleType::resolve(&type_str)?;
+    let progress = state
+        .db
+        .read(move |conn| {
+            if let Some(favorite) = FavoritesRepo::new(conn).get(user_id, &type_str, &id)? {
+                return Ok(Some(favorite.progress_json()));
+            }
+            let recent = RecentReadsRepo::new(conn).get(user_id, &type_str, &id)?;
+            Ok::<_, rusqlite::Error>(recent.map(|r| r.progress_json()))
+        })
+        .await?
+        .ok_or_else(|| AppError::NotFound("Not found".into()))?;
+    Ok(Json(progress))
+}
+
 #[utoipa::path(
     patch,
     path = "/api/favorites/{type}/{id}/progress",
     tag = "お気に入り",
     summary = "既読位置更新",
-    description = "既読ページ位置を更新する。お気に入りに登録されていない場合は何もせず `{\"ok\": true}` を返す。",
+    description = "既読ページ位置と章内の読書位置（段落番号・スクロール割合）を更新する。位置を省略すると章の先頭扱いとなり、保存済みの位置は消去される。お気に入りに登録されていない場合は最近読んだ小説（`GET /api/recent`）に保存し、その項目を返す。このとき `title`・`page`（総ページ数）も送ると、後でお気に入りに昇格するときに使われる。\n\n採用された更新は閲覧履歴（`GET /api/history`）にお気に入りかどうかに関わらず記録される。同じ端末で同じ話を読み続けている間（直前の履歴から1時間以内）は新しい履歴を追加しない。\n\n複数端末からの更新は `updated_at`（端末で読んだ日時、省略時はサーバーの受信日時）で比較し、保存済みより古い更新は無視し、履歴にも記録しない（後勝ち）。無視された場合も現在のお気に入りを返す。",
     params(
         ("type" = String, Path, description = "対象サイト", example = "narou"),
         ("id" = String, Path, description = "小説ID", example = "n1234ab"),
     ),
-    request_body(content = crate::openapi::ProgressRequest, description = "既読ページ番号",
-        example = json!({"read": 42})),
+    request_body(content = crate::openapi::ProgressRequest, description = "既読ページ番号と章内の位置",
+        example = json!({"read": 42, "paragraph": 120, "fraction": 0.35, "updated_at": "2026-03-14T09:00:00Z", "device": "iPhone"})),
     responses(
-        (status = 200, description = "更新されたお気に入り（未登録の場合は {ok: true}）", body = crate::openapi::Favorite),
-        (status = 400, description = "readフィールド不足", body = crate::openapi::ErrorResponse,
+        (status = 200, description = "現在のお気に入り（未登録の場合は最近読んだ小説の項目: RecentRead）", body = crate::openapi::Favorite),
+        (status = 400, description = "readフィールド不足、または値が範囲外", body = crate::openapi::ErrorResponse,
             example = json!({"error": "read is required"})),
         (status = 500, description = "DBエラー", body = crate::openapi::ErrorResponse),
     ),
@@ -205,23 +635,99 @@ async fn patch_progress(
     let read = body
         .read
         .ok_or_else(|| AppError::BadRequest("read is required".into()))?;
-
-    let result = {
-        let db = state.db.lock().unwrap();
-        let changes = db.execute(
-            "UPDATE favorites SET read = ?1 WHERE user_id = ?2 AND type = ?3 AND id = ?4",
-            rusqlite::params![read, user_id.0, type_str, id],
-        )?;
-        if changes == 0 {
-            return Ok(Json(json!({ "ok": true })));
-        }
-        let mut stmt = db.prepare(
-            "SELECT type, id, title, novelupdated_at, page, read FROM favorites WHERE user_id = ?1 AND type = ?2 AND id = ?3",
-        )?;
-        stmt.query_row(
-            rusqlite::params![user_id.0, type_str, id],
-            map_favorite_row,
-        )?
+    if read < 0 || body.paragraph.is_some_and(|p| p < 0) {
+        return Err(AppError::BadRequest(
+            "read and paragraph must not be negative".into(),
+        ));
+    }
+    if body.fraction.is_some_and(|f| !(0.0..=1.0).contains(&f)) {
+        return Err(AppError::BadRequest(
+            "fraction must be between 0 and 1".into(),
+        ));
+    }
+    let progress = Progress {
+        read,
+        paragraph: body.paragraph,
+        fraction: body.fraction,
+        at: progress_time(body.updated_at.as_deref(), Utc::now())?,
     };
-    Ok(Json(result))
+    let device = body
+        .device
+        .map(|d| d.trim().chars().take(MAX_DEVICE_LEN).collect::<String>())
+        .filter(|d| !d.is_empty());
+    let title = body.title.filter(|t| !t.is_empty());
+    let page = body.page;
+
+    let result = state
+        .db
+        .write(move |conn| {
+            let tx = conn.transaction()?;
+            // A stale update still answers with the winning position
+            let favorites = FavoritesRepo::new(&tx);
+            let (accepted, result) = if favorites.get(user_id, &type_str, &id)?.is_some() {
+                let accepted = favorites.set_progress(user_id, &type_str, &id, &progress)?;
+                (
+                    accepted,
+                    favorites.get(user_id, &type_str, &id)?.map(|f| f.to_json()),
+                )
+            } else {
+                let recent = RecentReadsRepo::new(&tx);
+                let accepted = recent.set_progress(
+                    user_id,
+                    &type_str,
+                    &id,
+                    title.as_deref(),
+                    page,
+                    &progress,
+                )?;
+                (
+                    accepted,
+                    recent.get(user_id, &type_str, &id)?.map(|r| r.to_json()),
+                )
+            };
+            // Only positions that won make it into the history
+            if accepted {
+                ReadingEventsRepo::new(&tx).record(
+                    user_id,
+                    &type_str,
+                    &id,
+                    progress.read,
+                    device.as_deref(),
+                    &progress.at,
+                )?;
+            }
+            tx.commit()?;
+            Ok::<_, rusqlite::Error>(result)
+        })
+        .await?;
+    Ok(Json(result.unwrap_or(Value::Null)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn progress_time_normalizes_to_utc_millis() {
+        let now = Utc.with_ymd_and_hms(2026, 3, 14, 12, 0, 0).unwrap();
+        assert_eq!(
+            progress_time(Some("2026-03-14T18:30:00+09:00"), now).unwrap(),
+            "2026-03-14T09:30:00.000Z"
+        );
+        assert_eq!(
+            progress_time(None, now).unwrap(),
+            "2026-03-14T12:00:00.000Z"
+        );
+    }
+
+    #[test]
+    fn progress_time_clamps_future_clocks() {
+        let now = Utc.with_ymd_and_hms(2026, 3, 14, 12, 0, 0).unwrap();
+        assert_eq!(
+            progress_time(Some("2027-01-01T00:00:00Z"), now).unwrap(),
+            "2026-03-14T12:00:00.000Z"
+        );
+        assert!(progress_time(Some("yesterday"), now).is_err());
+    }
 }
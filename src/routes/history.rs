@@ -0,0 +1,84 @@
+use crate::auth::UserId;
+use crate::error::AppError;
+use crate::modules::ModuleType;
+use crate::repo::{HistoryFilter, ReadingEventsRepo};
+use crate::state::AppState;
+use axum::extract::{Query, State};
+use axum::routing::get;
+use axum::{Extension, Json, Router};
+use serde::Deserialize;
+use serde_json::{json, Value};
+use utoipa::IntoParams;
+
+pub fn routes() -> Router<AppState> {
+    Router::new().route("/api/history", get(get_history))
+}
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+struct HistoryQuery {
+    /// 1作品に限定する（`{type}:{id}` 形式、例: `narou:n1234ab`）
+    novel: Option<String>,
+    /// この履歴IDより古いものを取得する（前のページの `next_before`）
+    before: Option<i64>,
+    /// 取得件数（デフォルト50、最大200）
+    limit: Option<usize>,
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/history",
+    tag = "閲覧履歴",
+    summary = "閲覧履歴取得",
+    description = "既読位置の更新（`PATCH /api/favorites/{type}/{id}/progress`）から記録された閲覧履歴を新しい順に取得する。お気に入りに登録していない小説も含む。\n\n## ページング\nレスポンスの `next_before` を次のリクエストの `before` に指定すると続きを取得できる。`next_before` が null なら最後のページ。",
+    params(HistoryQuery),
+    responses(
+        (status = 200, description = "閲覧履歴", body = crate::openapi::HistoryResponse,
+            example = json!({"events": [{"id": 120, "type": "narou", "novel_id": "n1234ab", "title": "小説タイトル", "page": 42, "device": "iPhone", "read_at": "2026-03-14T09:00:00.000Z", "favorite": true}], "next_before": 120})),
+        (status = 400, description = "パラメータ不正", body = crate::openapi::ErrorResponse,
+            example = json!({"error": "novel must be in the form {type}:{id}"})),
+        (status = 500, description = "DBエラー", body = crate::openapi::ErrorResponse),
+    ),
+)]
+async fn get_history(
+    State(state): State<AppState>,
+    Extension(user_id): Extension<UserId>,
+    Query(query): Query<HistoryQuery>,
+) -> Result<Json<Value>, AppError> {
+    let novel = match query.novel.as_deref() {
+        None => None,
+        Some(novel) => {
+            let (type_str, id) = novel.split_once(':').ok_or_else(|| {
+                AppError::BadRequest("novel must be in the form {type}:{id}".into())
+            })?;
+            Some((
+                ModuleType::resolve(type_str)?.as_str().to_string(),
+                id.to_string(),
+            ))
+        }
+    };
+    let limit = super::page_limit(query.limit);
+    let before = query.before;
+
+    let mut events = state
+        .db
+        .read(move |conn| {
+            let filter = HistoryFilter {
+                novel: novel.as_ref().map(|(t, id)| (t.as_str(), id.as_str())),
+                before,
+                limit: limit + 1,
+            };
+            ReadingEventsRepo::new(conn).list(user_id, &filter)
+        })
+        .await?;
+    let next_before = if events.len() > limit {
+        events.truncate(limit);
+        events.last().map(|e| e.id)
+    } else {
+        None
+    };
+    Ok(Json(json!({
+        "events": events.iter().map(|e| e.to_json()).collect::<Vec<_>>(),
+        "next_before": next_before,
+    })))
+}
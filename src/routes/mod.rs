@@ -1,11 +1,23 @@
 mod auth;
+mod backup;
+mod bookmarks;
 mod detail;
+mod digest;
 mod favorites;
+mod history;
+mod opml;
 mod pages;
+mod push;
 mod ranking;
+mod recent;
+mod resolve;
 mod rss;
 mod search;
+mod stats;
+mod tags;
 mod toc;
+mod url_import;
+mod webhooks;
 
 use crate::error::AppError;
 use crate::openapi;
@@ -34,14 +46,55 @@ use utoipa_swagger_ui::SwaggerUi;
         search::get_search,
         detail::get_detail,
         toc::get_toc,
+        resolve::get_resolve,
         pages::get_page,
         pages::patch_page,
         favorites::get_favorites,
+        favorites::put_favorite_tags,
+        favorites::get_notes,
+        favorites::put_notes,
+        favorites::delete_notes,
+        favorites::put_order,
+        tags::get_tags,
+        tags::post_tag,
+        tags::patch_tag,
+        tags::delete_tag,
         favorites::put_favorite,
         favorites::delete_favorite,
+        favorites::get_progress,
         favorites::patch_progress,
+        recent::get_recent,
+        recent::delete_recent,
+        recent::post_promote,
+        history::get_history,
+        bookmarks::get_bookmarks,
+        bookmarks::get_novel_bookmarks,
+        bookmarks::get_page_bookmarks,
+        bookmarks::post_bookmark,
+        bookmarks::patch_bookmark,
+        bookmarks::delete_bookmark,
+        stats::get_stats,
+        backup::get_export,
+        backup::post_import,
+        opml::get_opml,
+        opml::post_opml,
+        url_import::post_import_urls,
         rss::get_rss,
+        rss::get_atom,
+        rss::get_json_feed,
         auth::get_me,
+        auth::get_feed_token,
+        auth::post_feed_token,
+        auth::delete_feed_token,
+        webhooks::get_webhooks,
+        webhooks::post_webhook,
+        webhooks::delete_webhook,
+        webhooks::get_deliveries,
+        push::get_vapid_public_key,
+        push::post_subscription,
+        push::delete_subscription,
+        digest::get_digest,
+        digest::put_digest,
     ),
     components(schemas(
         openapi::ErrorResponse,
@@ -50,12 +103,61 @@ use utoipa_swagger_ui::SwaggerUi;
         openapi::DetailResponse,
         openapi::Episode,
         openapi::TocResponse,
+        openapi::ResolveResponse,
         openapi::PageResponse,
         openapi::Favorite,
+        openapi::FavoritesPage,
         openapi::FavoriteRequest,
         openapi::ProgressRequest,
+        openapi::Notes,
+        openapi::Tag,
+        openapi::TagRequest,
+        openapi::FavoriteTagsRequest,
+        openapi::OrderEntry,
+        openapi::OrderRequest,
+        openapi::OrderResponse,
+        openapi::Progress,
+        openapi::ReadingPosition,
+        openapi::RecentRead,
+        openapi::ReadingEvent,
+        openapi::HistoryResponse,
+        openapi::Bookmark,
+        openapi::LocatedBookmark,
+        openapi::BookmarksResponse,
+        openapi::BookmarkRequest,
+        openapi::BookmarkUpdateRequest,
+        openapi::PeriodStats,
+        openapi::SiteStats,
+        openapi::NovelStats,
+        openapi::CatchUpEstimate,
+        openapi::ReadTotal,
+        openapi::Streak,
+        openapi::SessionStats,
+        openapi::ReadingSpeed,
+        openapi::CatchUp,
+        openapi::StatsResponse,
+        openapi::BackupFile,
+        openapi::BackupRecord,
+        openapi::ImportSummary,
+        openapi::ImportError,
+        openapi::OpmlImportResult,
+        openapi::OpmlUnresolved,
+        openapi::UrlImportResult,
+        openapi::NovelRef,
         openapi::OkResponse,
         openapi::UserInfo,
+        openapi::FeedTokenStatus,
+        openapi::FeedToken,
+        openapi::FeedUrls,
+        openapi::Webhook,
+        openapi::WebhookRequest,
+        openapi::WebhookDelivery,
+        openapi::VapidPublicKey,
+        openapi::PushSubscriptionRequest,
+        openapi::PushSubscriptionKeys,
+        openapi::PushUnsubscribeRequest,
+        openapi::DigestSettings,
+        openapi::DigestRequest,
     )),
     tags(
         (name = "ランキング", description = "ランキング取得・再取得"),
@@ -63,12 +165,26 @@ use utoipa_swagger_ui::SwaggerUi;
         (name = "小説情報", description = "小説の詳細情報・目次の取得"),
         (name = "小説本文", description = "小説の本文HTML取得"),
         (name = "お気に入り", description = "お気に入りのCRUD操作・既読管理"),
+        (name = "閲覧履歴", description = "既読位置の更新履歴と読書統計"),
+        (name = "しおり", description = "本文中の一節へのしおり"),
         (name = "RSS", description = "お気に入り更新のRSSフィード"),
         (name = "認証", description = "ユーザー認証情報"),
+        (name = "Webhook", description = "お気に入り更新のWebhook通知"),
+        (name = "Push通知", description = "お気に入り更新のWeb Push通知"),
+        (name = "メールダイジェスト", description = "お気に入り更新の定期メール"),
     ),
 )]
 struct ApiDoc;
 
+const DEFAULT_LIMIT: usize = 50;
+const MAX_LIMIT: usize = 200;
+
+/// Page size for a `limit` query parameter. Handlers fetch one row more than
+/// this; the extra row tells whether another page follows.
+fn page_limit(limit: Option<usize>) -> usize {
+    limit.unwrap_or(DEFAULT_LIMIT).clamp(1, MAX_LIMIT)
+}
+
 /// Only the route handler layer retries. Module layer does plain fetch;
 /// background sync retries implicitly by continuing its loop.
 async fn with_retry<F, Fut, T>(label: &str, f: F) -> Result<T, AppError>
@@ -102,10 +218,22 @@ pub fn build_router(state: AppState) -> Router {
         .merge(pages::routes())
         .merge(detail::routes())
         .merge(favorites::routes())
+        .merge(recent::routes())
+        .merge(tags::routes())
+        .merge(history::routes())
+        .merge(bookmarks::routes())
+        .merge(stats::routes())
+        .merge(opml::routes())
+        .merge(backup::routes())
+        .merge(url_import::routes())
         .merge(search::routes())
         .merge(toc::routes())
+        .merge(resolve::routes())
         .merge(rss::routes())
         .merge(auth::routes())
+        .merge(webhooks::routes())
+        .merge(push::routes())
+        .merge(digest::routes())
         .layer(middleware::from_fn_with_state(
             state.db.clone(),
             crate::auth::resolve_user,
@@ -119,6 +247,10 @@ pub fn build_router(state: AppState) -> Router {
             "/favicon.svg",
             tower_http::services::ServeFile::new("client/build/favicon.svg"),
         )
+        .nest_service(
+            "/sw.js",
+            tower_http::services::ServeFile::new("client/build/sw.js"),
+        )
         .fallback(get(move || {
             let bp = base_path.clone();
             async move { spa_fallback(&bp) }
@@ -0,0 +1,434 @@
+use crate::auth::UserId;
+use crate::error::AppError;
+use crate::modules::ModuleType;
+use crate::repo::{FavoritesRepo, FeedTokensRepo, NewNovel};
+use crate::state::AppState;
+use axum::extract::{Query, State};
+use axum::http::{header, HeaderMap};
+use axum::response::IntoResponse;
+use axum::routing::get;
+use axum::{Extension, Json, Router};
+use chrono::Utc;
+use quick_xml::events::Event;
+use quick_xml::Reader;
+use rusqlite::Connection;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::collections::HashSet;
+use utoipa::IntoParams;
+
+/// Outlines accepted per import; each new novel costs an upstream fetch
+const MAX_IMPORT_OUTLINES: usize = 2000;
+
+pub fn routes() -> Router<AppState> {
+    Router::new().route("/api/favorites/opml", get(get_opml).post(post_opml))
+}
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+struct ExportQuery {
+    /// フィードトークン。指定すると各作品のフィードURLに `token` を含める
+    token: Option<String>,
+}
+
+/// Novel entry in an OPML export
+struct OpmlNovel {
+    type_str: String,
+    id: String,
+    title: String,
+}
+
+/// Feed-like `<outline>` from an imported OPML document
+#[derive(Debug, Default, PartialEq)]
+struct Outline {
+    text: Option<String>,
+    html_url: Option<String>,
+    xml_url: Option<String>,
+    url: Option<String>,
+}
+
+impl Outline {
+    fn label(&self) -> Option<&str> {
+        self.text.as_deref()
+    }
+
+    /// Candidate URLs, most specific first
+    fn urls(&self) -> impl Iterator<Item = &str> {
+        [&self.html_url, &self.url, &self.xml_url]
+            .into_iter()
+            .filter_map(|u| u.as_deref())
+    }
+
+    fn resolve(&self) -> Option<(ModuleType, String)> {
+        self.urls().find_map(ModuleType::parse_url)
+    }
+}
+
+#[derive(Debug, Default)]
+struct ImportResult {
+    added: usize,
+    existing: usize,
+    unresolved: Vec<Value>,
+    /// Novels created as placeholders, to be filled in by an initial fetch
+    fetch: Vec<(ModuleType, String)>,
+}
+
+fn load_novels(conn: &Connection, user_id: UserId) -> rusqlite::Result<Vec<OpmlNovel>> {
+    let favorites = FavoritesRepo::new(conn).export_rows(user_id)?;
+    Ok(favorites
+        .into_iter()
+        .map(|f| OpmlNovel {
+            type_str: f.type_str,
+            id: f.id,
+            title: f.title,
+        })
+        .collect())
+}
+
+fn build_opml(novels: &[OpmlNovel], base: &str, token: Option<&str>) -> String {
+    use super::rss::escape_xml;
+
+    let mut xml = String::from(
+        r#"<?xml version="1.0" encoding="UTF-8"?>
+<opml version="2.0">
+<head>
+<title>お気に入り</title>
+"#,
+    );
+    xml.push_str(&format!(
+        "<dateCreated>{}</dateCreated>\n</head>\n<body>\n",
+        Utc::now().to_rfc2822()
+    ));
+    for novel in novels {
+        let Ok(module) = ModuleType::resolve(&novel.type_str) else {
+            continue;
+        };
+        let mut feed_url = format!(
+            "{}/api/rss?mode=chapters&novel={}:{}",
+            base, novel.type_str, novel.id
+        );
+        if let Some(token) = token {
+            feed_url.push_str(&format!("&token={}", urlencoding::encode(token)));
+        }
+        xml.push_str(&format!(
+            "<outline type=\"rss\" text=\"{title}\" title=\"{title}\" htmlUrl=\"{}\" xmlUrl=\"{}\"/>\n",
+            escape_xml(&module.site_url(&novel.id)),
+            escape_xml(&feed_url),
+            title = escape_xml(&novel.title),
+        ));
+    }
+    xml.push_str("</body>\n</opml>\n");
+    xml
+}
+
+/// Collect every `<outline>` that carries a URL. Outlines without one are folders
+/// (categories) and are skipped; their children are still read.
+fn parse_opml(xml: &str) -> Result<Vec<Outline>, String> {
+    let mut reader = Reader::from_str(xml);
+    let mut outlines = Vec::new();
+    let mut saw_opml = false;
+    loop {
+        match reader.read_event().map_err(|e| e.to_string())? {
+            Event::Start(e) | Event::Empty(e) => match e.name().as_ref() {
+                b"opml" => saw_opml = true,
+                b"outline" => {
+                    let mut outline = Outline::default();
+                    for attr in e.attributes() {
+                        let attr = attr.map_err(|e| e.to_string())?;
+                        let value = attr
+                            .unescape_value()
+                            .map_err(|e| e.to_string())?
+                            .trim()
+                            .to_string();
+                        if value.is_empty() {
+                            continue;
+                        }
+                        match attr.key.as_ref() {
+                            b"text" => outline.text = Some(value),
+                            b"title" if outline.text.is_none() => outline.text = Some(value),
+                            b"htmlUrl" => outline.html_url = Some(value),
+                            b"xmlUrl" => outline.xml_url = Some(value),
+                            b"url" => outline.url = Some(value),
+                            _ => {}
+                        }
+                    }
+                    if outline.urls().next().is_some() {
+                        outlines.push(outline);
+                    }
+                }
+                _ => {}
+            },
+            Event::Eof => break,
+            _ => {}
+        }
+    }
+    if !saw_opml {
+        return Err("not an OPML document".into());
+    }
+    Ok(outlines)
+}
+
+/// Add favorites for every resolvable outline. Unknown novels are inserted with the
+/// outline's title and page 0 until the initial fetch fills them in.
+fn import_outlines(
+    conn: &mut Connection,
+    user_id: UserId,
+    outlines: &[Outline],
+) -> rusqlite::Result<ImportResult> {
+    let mut result = ImportResult::default();
+    let mut seen = HashSet::new();
+    let tx = conn.transaction()?;
+    for outline in outlines {
+        let Some((module, id)) = outline.resolve() else {
+            result.unresolved.push(json!({
+                "text": outline.label(),
+                "url": outline.urls().next(),
+            }));
+            continue;
+        };
+        if !seen.insert((module, id.clone())) {
+            continue;
+        }
+        let upserted = FavoritesRepo::new(&tx).upsert(
+            user_id,
+            &NewNovel {
+                type_str: module.as_str(),
+                id: &id,
+                title: outline.label().unwrap_or(&id),
+                page: 0,
+                novelupdated_at: None,
+            },
+        )?;
+        if upserted.favorite_added {
+            result.added += 1;
+        } else {
+            result.existing += 1;
+        }
+        if upserted.novel_created {
+            result.fetch.push((module, id));
+        }
+    }
+    tx.commit()?;
+    Ok(result)
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/favorites/opml",
+    tag = "お気に入り",
+    summary = "お気に入りOPMLエクスポート",
+    description = "お気に入りをOPML 2.0形式で出力する。1作品につき1つの `outline` で、`htmlUrl` は掲載サイトの作品ページ、`xmlUrl` はその作品だけのフィード（`/api/rss?mode=chapters&novel={type}:{id}`）。\n\nフィードリーダーに取り込む場合は `token` にフィードトークンを指定すると、各フィードURLに含まれる。",
+    params(ExportQuery),
+    responses(
+        (status = 200, description = "OPML 2.0 XML", content_type = "text/x-opml"),
+        (status = 400, description = "トークンがこのユーザーのものではない", body = crate::openapi::ErrorResponse,
+            example = json!({"error": "token does not belong to this user"})),
+        (status = 500, description = "DBエラー", body = crate::openapi::ErrorResponse),
+    ),
+)]
+async fn get_opml(
+    State(state): State<AppState>,
+    Extension(user_id): Extension<UserId>,
+    Query(query): Query<ExportQuery>,
+    headers: HeaderMap,
+) -> Result<impl IntoResponse, AppError> {
+    let base = super::rss::resolve_base_url(&headers, &state.config);
+    let token = query.token.clone();
+    let novels = state
+        .db
+        .read(move |db| {
+            if let Some(token) = &token {
+                if FeedTokensRepo::new(db).user(token)? != Some(user_id) {
+                    return Err(AppError::BadRequest(
+                        "token does not belong to this user".into(),
+                    ));
+                }
+            }
+            Ok(load_novels(db, user_id)?)
+        })
+        .await?;
+    Ok((
+        [
+            (header::CONTENT_TYPE, "text/x-opml; charset=utf-8"),
+            (
+                header::CONTENT_DISPOSITION,
+                "attachment; filename=\"favorites.opml\"",
+            ),
+        ],
+        build_opml(&novels, &base, query.token.as_deref()),
+    ))
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/favorites/opml",
+    tag = "お気に入り",
+    summary = "お気に入りOPMLインポート",
+    description = "OPMLファイルの内容（リクエストボディにXMLをそのまま送る）からお気に入りを一括登録する。各 `outline` の `htmlUrl`・`url`・`xmlUrl` のうち、なろう・ノクターン・カクヨムの作品URL（話のURLも可）として解釈できたものを登録する。\n\n登録済みの作品は `existing` として数え、既読位置は変更しない。新しい作品は `outline` のタイトルで仮登録し、バックグラウンドでメタデータを取得する。作品URLとして解釈できなかった `outline` は `unresolved` に返す。URLを持たない `outline`（フォルダ）は無視する。",
+    request_body(content = String, description = "OPML 2.0 XML", content_type = "text/x-opml"),
+    responses(
+        (status = 200, description = "インポート結果", body = crate::openapi::OpmlImportResult,
+            example = json!({"added": 2, "existing": 1, "unresolved": [{"text": "技術ブログ", "url": "https://example.com/feed.xml"}]})),
+        (status = 400, description = "OPMLとして解釈できない、または件数超過", body = crate::openapi::ErrorResponse,
+            example = json!({"error": "invalid OPML: not an OPML document"})),
+        (status = 500, description = "DBエラー", body = crate::openapi::ErrorResponse),
+    ),
+)]
+async fn post_opml(
+    State(state): State<AppState>,
+    Extension(user_id): Extension<UserId>,
+    body: String,
+) -> Result<Json<Value>, AppError> {
+    let outlines =
+        parse_opml(&body).map_err(|e| AppError::BadRequest(format!("invalid OPML: {}", e)))?;
+    if outlines.len() > MAX_IMPORT_OUTLINES {
+        return Err(AppError::BadRequest(format!(
+            "too many outlines (max {})",
+            MAX_IMPORT_OUTLINES
+        )));
+    }
+    let result = state
+        .db
+        .write(move |db| import_outlines(db, user_id, &outlines))
+        .await?;
+
+    for module in [
+        ModuleType::Narou,
+        ModuleType::Nocturne,
+        ModuleType::Kakuyomu,
+    ] {
+        let ids = result
+            .fetch
+            .iter()
+            .filter(|(m, _)| *m == module)
+            .map(|(_, id)| id.clone())
+            .collect();
+        crate::sync::spawn_initial_fetch(state.clone(), module, ids);
+    }
+
+    Ok(Json(json!({
+        "added": result.added,
+        "existing": result.existing,
+        "unresolved": result.unresolved,
+    })))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SAMPLE: &str = r#"<?xml version="1.0"?>
+<opml version="1.0">
+  <head><title>subs</title></head>
+  <body>
+    <outline text="Novels">
+      <outline type="rss" text="A &amp; B" htmlUrl="https://ncode.syosetu.com/n1111aa/" xmlUrl="https://example.com/a.xml"/>
+      <outline type="rss" title="K" xmlUrl="https://kakuyomu.jp/works/1177354054880000001"/>
+    </outline>
+    <outline type="link" text="Chapter" url="https://novel18.syosetu.com/n2222bb/3/"/>
+    <outline type="rss" text="Blog" xmlUrl="https://example.com/feed.xml"/>
+    <outline type="rss" text="dup" htmlUrl="https://ncode.syosetu.com/N1111AA"/>
+  </body>
+</opml>"#;
+
+    #[test]
+    fn parse_opml_reads_nested_outlines_and_skips_folders() {
+        let outlines = parse_opml(SAMPLE).unwrap();
+        assert_eq!(outlines.len(), 5);
+        assert_eq!(outlines[0].text.as_deref(), Some("A & B"));
+        assert_eq!(outlines[1].text.as_deref(), Some("K"));
+        assert_eq!(
+            outlines
+                .iter()
+                .map(|o| o.resolve().map(|(m, id)| format!("{}:{}", m.as_str(), id)))
+                .collect::<Vec<_>>(),
+            [
+                Some("narou:n1111aa".to_string()),
+                Some("kakuyomu:1177354054880000001".to_string()),
+                Some("nocturne:n2222bb".to_string()),
+                None,
+                Some("narou:n1111aa".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn parse_opml_rejects_other_documents() {
+        assert!(parse_opml("<rss version=\"2.0\"><channel/></rss>").is_err());
+        assert!(parse_opml("<opml><body><outline text=\"x\" url=\"a\"></body>").is_err());
+    }
+
+    #[test]
+    fn import_adds_new_favorites_and_reports_the_rest() {
+        let mut conn = crate::db::open_memory();
+        conn.execute_batch(
+            "INSERT INTO novels (type, id, title, page) VALUES ('narou', 'n1111aa', 'Known', 40);
+             INSERT INTO favorites (user_id, type, id, read) VALUES (1, 'narou', 'n1111aa', 12);",
+        )
+        .unwrap();
+
+        let outlines = parse_opml(SAMPLE).unwrap();
+        let result = import_outlines(&mut conn, UserId(1), &outlines).unwrap();
+        assert_eq!((result.added, result.existing), (2, 1));
+        assert_eq!(
+            result.unresolved,
+            [json!({"text": "Blog", "url": "https://example.com/feed.xml"})]
+        );
+        assert_eq!(
+            result.fetch,
+            [
+                (ModuleType::Kakuyomu, "1177354054880000001".to_string()),
+                (ModuleType::Nocturne, "n2222bb".to_string()),
+            ]
+        );
+
+        let (title, page): (String, i64) = conn
+            .query_row(
+                "SELECT title, page FROM novels WHERE type = 'nocturne' AND id = 'n2222bb'",
+                [],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .unwrap();
+        assert_eq!((title.as_str(), page), ("Chapter", 0));
+        let read: i64 = conn
+            .query_row(
+                "SELECT read FROM favorites WHERE user_id = 1 AND id = 'n1111aa'",
+                [],
+                |row| row.get(0),
+            )
+            .unwrap();
+        assert_eq!(read, 12);
+    }
+
+    #[test]
+    fn export_round_trips_through_import() {
+        let novels = vec![
+            OpmlNovel {
+                type_str: "narou".into(),
+                id: "n1111aa".into(),
+                title: "<Title> & \"quotes\"".into(),
+            },
+            OpmlNovel {
+                type_str: "kakuyomu".into(),
+                id: "1177354054880000001".into(),
+                title: "K".into(),
+            },
+        ];
+        let xml = build_opml(&novels, "http://localhost:3000", Some("abc"));
+        assert!(xml.contains(
+            "xmlUrl=\"http://localhost:3000/api/rss?mode=chapters&amp;novel=narou:n1111aa&amp;token=abc\""
+        ));
+
+        let outlines = parse_opml(&xml).unwrap();
+        assert_eq!(outlines.len(), 2);
+        assert_eq!(outlines[0].text.as_deref(), Some("<Title> & \"quotes\""));
+        assert_eq!(
+            outlines[0].html_url.as_deref(),
+            Some("https://ncode.syosetu.com/n1111aa/")
+        );
+        assert_eq!(
+            outlines[1].resolve(),
+            Some((ModuleType::Kakuyomu, "1177354054880000001".to_string()))
+        );
+    }
+}
@@ -1,5 +1,6 @@
 use crate::error::AppError;
 use crate::modules::ModuleType;
+use crate::repo::ChapterLengthsRepo;
 use crate::sanitize;
 use crate::state::AppState;
 use axum::extract::{Path, State};
@@ -8,6 +9,8 @@ use axum::{Json, Router};
 use serde_json::{json, Value};
 
 const PAGE_TTL: u64 = 60 * 60 * 24; // 24 hours
+/// Larger page numbers are Kakuyomu episode IDs, which have no chapter position
+const MAX_CHAPTER_NUMBER: i64 = 100_000;
 
 pub fn routes() -> Router<AppState> {
     Router::new()
@@ -38,13 +41,22 @@ async fn get_page(
     Path((type_str, id, num)): Path<(String, String, String)>,
 ) -> Result<Json<Value>, AppError> {
     let module = ModuleType::resolve(&type_str)?;
-    let key = format!("novel:{}:{}:page:{}", type_str, id, num);
+    let html = page_html(&state, &module, &id, &num).await?;
+    Ok(Json(json!({ "html": html })))
+}
 
-    if let Some(cached) = state.cache.get(&key) {
-        return Ok(Json(json!({ "html": cached })));
+/// Sanitized page HTML from the cache, fetching it on a miss. Shared with the feeds.
+pub(super) async fn page_html(
+    state: &AppState,
+    module: &ModuleType,
+    id: &str,
+    num: &str,
+) -> Result<String, AppError> {
+    let key = format!("novel:{}:{}:page:{}", module.as_str(), id, num);
+    if let Some(Value::String(cached)) = state.cache.get(&key) {
+        return Ok(cached);
     }
-
-    fetch_and_cache(&state, &module, &id, &num, &key).await
+    fetch_and_cache(state, module, id, num, &key).await
 }
 
 #[utoipa::path(
@@ -71,7 +83,8 @@ async fn patch_page(
     let module = ModuleType::resolve(&type_str)?;
     let key = format!("novel:{}:{}:page:{}", type_str, id, num);
 
-    fetch_and_cache(&state, &module, &id, &num, &key).await
+    let html = fetch_and_cache(&state, &module, &id, &num, &key).await?;
+    Ok(Json(json!({ "html": html })))
 }
 
 async fn fetch_and_cache(
@@ -80,12 +93,38 @@ async fn fetch_and_cache(
     id: &str,
     num: &str,
     key: &str,
-) -> Result<Json<Value>, AppError> {
+) -> Result<String, AppError> {
     let label = format!("fetchPage {}/{}/{}", id, num, key);
     let raw = super::with_retry(&label, || module.fetch_page(&state.http, id, num)).await?;
     let html = sanitize::clean(raw.as_deref().unwrap_or(""));
     state
         .cache
         .set(key, Value::String(html.clone()), Some(PAGE_TTL));
-    Ok(Json(json!({ "html": html })))
+    record_length(state, module, id, num, &html).await;
+    Ok(html)
+}
+
+/// Keep the chapter's length for reading statistics. Losing one only makes
+/// the estimates rougher, so failures are logged rather than returned.
+async fn record_length(state: &AppState, module: &ModuleType, id: &str, num: &str, html: &str) {
+    let Some(page) = num
+        .parse::<i64>()
+        .ok()
+        .filter(|page| (1..MAX_CHAPTER_NUMBER).contains(page))
+    else {
+        return;
+    };
+    if html.is_empty() {
+        return;
+    }
+    let chars = sanitize::text_length(html) as i64;
+    let type_str = module.as_str().to_string();
+    let id = id.to_string();
+    let result = state
+        .db
+        .write(move |conn| ChapterLengthsRepo::new(conn).record(&type_str, &id, page, chars))
+        .await;
+    if let Err(e) = result {
+        tracing::warn!("Failed to record chapter length: {}", e);
+    }
 }
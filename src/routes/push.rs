@@ -0,0 +1,153 @@
+use crate::auth::UserId;
+use crate::error::AppError;
+use crate::push;
+use crate::repo::PushSubscriptionsRepo;
+use crate::state::AppState;
+use axum::extract::State;
+use axum::routing::{get, post};
+use axum::{Extension, Json, Router};
+use p256::PublicKey;
+use serde::Deserialize;
+use serde_json::{json, Value};
+
+pub fn routes() -> Router<AppState> {
+    Router::new()
+        .route("/api/push/vapid-public-key", get(get_vapid_public_key))
+        .route(
+            "/api/push/subscriptions",
+            post(post_subscription).delete(delete_subscription),
+        )
+}
+
+#[derive(Deserialize)]
+struct SubscriptionKeys {
+    p256dh: Option<String>,
+    auth: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct SubscriptionBody {
+    endpoint: Option<String>,
+    keys: Option<SubscriptionKeys>,
+}
+
+#[derive(Deserialize)]
+struct UnsubscribeBody {
+    endpoint: Option<String>,
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/push/vapid-public-key",
+    tag = "Push通知",
+    summary = "VAPID公開鍵取得",
+    description = "`PushManager.subscribe()` の `applicationServerKey` に渡す公開鍵（base64url、非圧縮形式）を取得する。`VAPID_PRIVATE_KEY` 未設定時は初回アクセスで鍵を生成しDBに保存する。",
+    responses(
+        (status = 200, description = "VAPID公開鍵", body = crate::openapi::VapidPublicKey,
+            example = json!({"public_key": "BEl62iUYgUivxIkv69yViEuiBIa-Ib9-SkvMeAtA3LFgDzkrxZJjSgSnfckjBJuBkr3qBUYIHBQFLXYp5Nksh8U"})),
+        (status = 500, description = "鍵の読み込みに失敗", body = crate::openapi::ErrorResponse),
+    ),
+)]
+async fn get_vapid_public_key(State(state): State<AppState>) -> Result<Json<Value>, AppError> {
+    let config = state.config.clone();
+    let vapid = state
+        .db
+        .write(move |conn| push::load_vapid(conn, &config))
+        .await?;
+    Ok(Json(json!({ "public_key": vapid.public_key() })))
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/push/subscriptions",
+    tag = "Push通知",
+    summary = "Push購読登録",
+    description = "ブラウザの `PushSubscription`（`subscription.toJSON()` の値）を登録する。同期でお気に入り小説の新着話が検出されると、このブラウザへWeb Push通知が送信される。\n\n同じエンドポイントを再登録すると鍵が更新される。他のユーザーが登録済みのエンドポイントは 409 を返す。プッシュサービスが404/410を返した購読は自動で削除される。\n\n## 通知ペイロード\nService Workerの `push` イベントで受け取るJSON: `title`（小説タイトル）、`body`（通知本文）、`url`（続きの話のリーダーURL）、`tag`（`{type}:{id}`）。",
+    request_body(content = crate::openapi::PushSubscriptionRequest, description = "PushSubscription",
+        example = json!({"endpoint": "https://fcm.googleapis.com/fcm/send/abc123", "keys": {"p256dh": "BNcRdreALRFXTkOOUHK1EtK2wtaz5Ry4YfYCA_0QTpQtUbVlUls0VJXg7A8u-Ts1XbjhazAkj7I99e8QcYP7DkM", "auth": "tBHItJI5svbpez7KI4CCXg"}})),
+    responses(
+        (status = 200, description = "登録成功", body = crate::openapi::OkResponse,
+            example = json!({"ok": true})),
+        (status = 400, description = "エンドポイントまたは鍵が不正", body = crate::openapi::ErrorResponse,
+            example = json!({"error": "invalid p256dh key"})),
+        (status = 409, description = "エンドポイントが他のユーザーに登録済み", body = crate::openapi::ErrorResponse,
+            example = json!({"error": "endpoint is registered to another user"})),
+        (status = 500, description = "DBエラー", body = crate::openapi::ErrorResponse),
+    ),
+)]
+async fn post_subscription(
+    State(state): State<AppState>,
+    Extension(user_id): Extension<UserId>,
+    Json(body): Json<SubscriptionBody>,
+) -> Result<Json<Value>, AppError> {
+    let endpoint = body
+        .endpoint
+        .filter(|u| {
+            reqwest::Url::parse(u)
+                .map(|u| u.scheme() == "https")
+                .unwrap_or(false)
+        })
+        .ok_or_else(|| AppError::BadRequest("endpoint must be an https URL".into()))?;
+    let keys = body
+        .keys
+        .ok_or_else(|| AppError::BadRequest("keys is required".into()))?;
+    let p256dh = keys
+        .p256dh
+        .filter(|k| {
+            push::decode_key(k)
+                .map(|b| PublicKey::from_sec1_bytes(&b).is_ok())
+                .unwrap_or(false)
+        })
+        .ok_or_else(|| AppError::BadRequest("invalid p256dh key".into()))?;
+    let auth = keys
+        .auth
+        .filter(|a| push::decode_key(a).is_some_and(|b| b.len() == 16))
+        .ok_or_else(|| AppError::BadRequest("invalid auth secret".into()))?;
+
+    let saved = state
+        .db
+        .write(move |conn| {
+            PushSubscriptionsRepo::new(conn).upsert(user_id, &endpoint, &p256dh, &auth)
+        })
+        .await?;
+    if !saved {
+        return Err(AppError::Conflict(
+            "endpoint is registered to another user".into(),
+        ));
+    }
+    Ok(Json(json!({ "ok": true })))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/api/push/subscriptions",
+    tag = "Push通知",
+    summary = "Push購読解除",
+    description = "指定エンドポイントの購読を削除する。",
+    request_body(content = crate::openapi::PushUnsubscribeRequest, description = "解除するエンドポイント",
+        example = json!({"endpoint": "https://fcm.googleapis.com/fcm/send/abc123"})),
+    responses(
+        (status = 200, description = "削除成功", body = crate::openapi::OkResponse,
+            example = json!({"ok": true})),
+        (status = 400, description = "endpoint未指定", body = crate::openapi::ErrorResponse),
+        (status = 404, description = "購読が存在しない", body = crate::openapi::ErrorResponse),
+        (status = 500, description = "DBエラー", body = crate::openapi::ErrorResponse),
+    ),
+)]
+async fn delete_subscription(
+    State(state): State<AppState>,
+    Extension(user_id): Extension<UserId>,
+    Json(body): Json<UnsubscribeBody>,
+) -> Result<Json<Value>, AppError> {
+    let endpoint = body
+        .endpoint
+        .ok_or_else(|| AppError::BadRequest("endpoint is required".into()))?;
+    let deleted = state
+        .db
+        .write(move |conn| PushSubscriptionsRepo::new(conn).delete(user_id, &endpoint))
+        .await?;
+    if !deleted {
+        return Err(AppError::NotFound("Not found".into()));
+    }
+    Ok(Json(json!({ "ok": true })))
+}
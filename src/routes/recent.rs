@@ -0,0 +1,114 @@
+use super::favorites::add_favorite;
+use crate::auth::UserId;
+use crate::error::AppError;
+use crate::modules::ModuleType;
+use crate::repo::RecentReadsRepo;
+use crate::state::AppState;
+use axum::extract::{Path, State};
+use axum::routing::{delete, get, post};
+use axum::{Extension, Json, Router};
+use serde_json::{json, Value};
+
+pub fn routes() -> Router<AppState> {
+    Router::new()
+        .route("/api/recent", get(get_recent))
+        .route("/api/recent/{type}/{id}", delete(delete_recent))
+        .route("/api/recent/{type}/{id}/favorite", post(post_promote))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/recent",
+    tag = "お気に入り",
+    summary = "最近読んだ小説一覧",
+    description = "お気に入りに登録していない小説の既読位置を、最後に読んだ順に取得する。既読位置の更新（`PATCH /api/favorites/{type}/{id}/progress`）で自動的に記録され、直近100作品まで保持される。お気に入りに登録すると一覧から外れる。",
+    responses(
+        (status = 200, description = "最近読んだ小説", body = Vec<crate::openapi::RecentRead>,
+            example = json!([{"type": "narou", "id": "n1234ab", "title": "小説タイトル", "page": 150, "read": 3, "position": {"paragraph": 12, "fraction": null}, "progress_at": "2026-03-14T09:00:00.000Z"}])),
+        (status = 500, description = "DBエラー", body = crate::openapi::ErrorResponse),
+    ),
+)]
+async fn get_recent(
+    State(state): State<AppState>,
+    Extension(user_id): Extension<UserId>,
+) -> Result<Json<Value>, AppError> {
+    let recent = state
+        .db
+        .read(move |conn| RecentReadsRepo::new(conn).list(user_id))
+        .await?;
+    Ok(Json(recent.iter().map(|r| r.to_json()).collect()))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/api/recent/{type}/{id}",
+    tag = "お気に入り",
+    summary = "最近読んだ小説から削除",
+    description = "最近読んだ小説の一覧から外す。",
+    params(
+        ("type" = String, Path, description = "対象サイト", example = "narou"),
+        ("id" = String, Path, description = "小説ID", example = "n1234ab"),
+    ),
+    responses(
+        (status = 200, description = "削除成功", body = crate::openapi::OkResponse,
+            example = json!({"ok": true})),
+        (status = 404, description = "一覧に存在しない", body = crate::openapi::ErrorResponse),
+        (status = 500, description = "DBエラー", body = crate::openapi::ErrorResponse),
+    ),
+)]
+async fn delete_recent(
+    State(state): State<AppState>,
+    Extension(user_id): Extension<UserId>,
+    Path((type_str, id)): Path<(String, String)>,
+) -> Result<Json<Value>, AppError> {
+    ModuleType::resolve(&type_str)?;
+    let deleted = state
+        .db
+        .write(move |conn| RecentReadsRepo::new(conn).delete(user_id, &type_str, &id))
+        .await?;
+    if !deleted {
+        return Err(AppError::NotFound("Not found".into()));
+    }
+    Ok(Json(json!({ "ok": true })))
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/recent/{type}/{id}/favorite",
+    tag = "お気に入り",
+    summary = "お気に入りに昇格",
+    description = "最近読んだ小説をお気に入りに登録し、既読位置を引き継ぐ。タイトル・総ページ数は小説のメタデータ、なければ既読位置の更新時に送られた値を使う。どちらもない場合は `PUT /api/favorites/{type}/{id}` で登録する（既読位置は同様に引き継がれる）。",
+    params(
+        ("type" = String, Path, description = "対象サイト", example = "narou"),
+        ("id" = String, Path, description = "小説ID", example = "n1234ab"),
+    ),
+    responses(
+        (status = 200, description = "登録されたお気に入り", body = crate::openapi::Favorite),
+        (status = 400, description = "タイトル・総ページ数が不明", body = crate::openapi::ErrorResponse,
+            example = json!({"error": "title and page are unknown; add it with PUT /api/favorites/{type}/{id}"})),
+        (status = 404, description = "一覧に存在しない", body = crate::openapi::ErrorResponse),
+        (status = 500, description = "DBエラー", body = crate::openapi::ErrorResponse),
+    ),
+)]
+async fn post_promote(
+    State(state): State<AppState>,
+    Extension(user_id): Extension<UserId>,
+    Path((type_str, id)): Path<(String, String)>,
+) -> Result<Json<Value>, AppError> {
+    let module = ModuleType::resolve(&type_str)?;
+    let recent = {
+        let id = id.clone();
+        state
+            .db
+            .read(move |conn| RecentReadsRepo::new(conn).get(user_id, &type_str, &id))
+            .await?
+            .ok_or_else(|| AppError::NotFound("Not found".into()))?
+    };
+    let (Some(title), Some(page)) = (recent.title, recent.page) else {
+        return Err(AppError::BadRequest(
+            "title and page are unknown; add it with PUT /api/favorites/{type}/{id}".into(),
+        ));
+    };
+    let favorite = add_favorite(&state, user_id, module, id, title, page, None).await?;
+    Ok(Json(favorite.to_json()))
+}
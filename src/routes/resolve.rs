@@ -0,0 +1,84 @@
+use crate::error::AppError;
+use crate::modules::{self, NovelLocation};
+use crate::state::AppState;
+use axum::extract::{Query, State};
+use axum::routing::get;
+use axum::{Json, Router};
+use serde::Deserialize;
+use serde_json::{json, Value};
+use utoipa::IntoParams;
+
+pub fn routes() -> Router<AppState> {
+    Router::new().route("/api/resolve", get(get_resolve))
+}
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+struct ResolveQuery {
+    /// なろう・ノクターン・カクヨムの作品・話・目次ページのURL
+    url: Option<String>,
+}
+
+/// Reader route for a novel: the chapter when known, otherwise the TOC.
+fn reader_path(location: &NovelLocation, page: Option<u64>) -> String {
+    let base = format!("/novel/{}/{}", location.module.as_str(), location.id);
+    match page {
+        Some(page) => format!("{}/{}", base, page),
+        None => format!("{}/toc", base),
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/resolve",
+    tag = "小説情報",
+    summary = "URL解決",
+    description = "掲載サイトのURLを、このアプリの小説種別・ID・話数とリーダーのパスに変換する。\n\n対応するURL:\n- なろう・ノクターン: 作品トップ（`/n1234ab/`）、話（`/n1234ab/15/`）、目次ページ（`/n1234ab/?p=3` はそのページの最初の話、1ページ100話）\n- カクヨム: 作品（`/works/{id}`）、話（`/works/{id}/episodes/{episode_id}`）。話IDは作品の目次を取得して話数に変換する（最大3回リトライ）\n\n話を特定できないURLでは `page` が `null`、`path` は目次になる。",
+    params(ResolveQuery),
+    responses(
+        (status = 200, description = "解決結果", body = crate::openapi::ResolveResponse,
+            example = json!({"type": "narou", "id": "n1234ab", "page": 15, "path": "/novel/narou/n1234ab/15"})),
+        (status = 400, description = "対応していないURL", body = crate::openapi::ErrorResponse,
+            example = json!({"error": "not a novel URL on a supported site"})),
+        (status = 404, description = "カクヨムの話IDが作品に存在しない", body = crate::openapi::ErrorResponse),
+        (status = 502, description = "外部サイトからの取得に失敗", body = crate::openapi::ErrorResponse),
+    ),
+)]
+async fn get_resolve(
+    State(state): State<AppState>,
+    Query(query): Query<ResolveQuery>,
+) -> Result<Json<Value>, AppError> {
+    let url = query
+        .url
+        .ok_or_else(|| AppError::BadRequest("url is required".into()))?;
+    let location = modules::parse_location(&url)
+        .ok_or_else(|| AppError::BadRequest("not a novel URL on a supported site".into()))?;
+    let module = location.module;
+
+    let page = if location.episode_id.is_some() {
+        let label = format!("resolveEpisode {}/{}", module.as_str(), location.id);
+        let page = super::with_retry(&label, || module.locate_page(&state.http, &location)).await?;
+        Some(page.ok_or_else(|| AppError::NotFound("Episode not found in this work".into()))?)
+    } else {
+        location.page
+    };
+
+    Ok(Json(json!({
+        "type": module.as_str(),
+        "id": location.id,
+        "page": page,
+        "path": reader_path(&location, page),
+    })))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reader_path_points_at_chapter_or_toc() {
+        let location = modules::parse_location("https://ncode.syosetu.com/n1234ab/").unwrap();
+        assert_eq!(reader_path(&location, None), "/novel/narou/n1234ab/toc");
+        assert_eq!(reader_path(&location, Some(7)), "/novel/narou/n1234ab/7");
+    }
+}
@@ -1,14 +1,157 @@
 use crate::auth::UserId;
 use crate::error::AppError;
+use crate::modules::ModuleType;
+use crate::repo::{Favorite, FavoritesRepo, FeedItemsFilter, FeedTokensRepo};
 use crate::state::AppState;
-use axum::extract::State;
+use axum::extract::{Query, State};
 use axum::http::{header, HeaderMap};
 use axum::response::IntoResponse;
 use axum::routing::get;
 use axum::{Extension, Router};
+use chrono::{DateTime, NaiveDate, NaiveDateTime, SecondsFormat, Utc};
+use rusqlite::Connection;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::time::Duration;
+use utoipa::IntoParams;
 
 pub fn routes() -> Router<AppState> {
-    Router::new().route("/api/rss", get(get_rss))
+    Router::new()
+        .route("/api/rss", get(get_rss))
+        .route("/api/rss.atom", get(get_atom))
+        .route("/api/feed.json", get(get_json_feed))
+}
+
+const FEED_TITLE: &str = "Novel Server - お気に入り更新";
+const FEED_DESCRIPTION: &str = "お気に入り小説の更新情報";
+const DEFAULT_MIN_UNREAD: i64 = 1;
+const DEFAULT_MAX_UNREAD: i64 = 9;
+/// Upper bound on items in chapter mode, however many chapters are unread
+const MAX_CHAPTER_ITEMS: usize = 100;
+/// Chapter mode reuses fetched TOCs for this long unless the novel has grown since
+const TOC_TTL: u64 = 60 * 60;
+const TOC_TIMEOUT: Duration = Duration::from_secs(10);
+const TOC_CONCURRENCY: usize = 4;
+/// With `content=full`, only the newest chapters carry their text to keep the feed responsive
+const MAX_FULL_CONTENT_ITEMS: usize = 20;
+const PAGE_TIMEOUT: Duration = Duration::from_secs(15);
+
+/// Query parameters shared by all feed formats
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+struct FeedQuery {
+    /// 未読話数の下限（デフォルト1）
+    min_unread: Option<i64>,
+    /// 未読話数の上限（デフォルト9、`novel` 指定時は上限なし）
+    max_unread: Option<i64>,
+    /// 対象サイト（narou / nocturne / kakuyomu、カンマ区切りで複数指定可）
+    site: Option<String>,
+    /// 1作品に限定する（`{type}:{id}` 形式、例: `narou:n1234ab`）
+    novel: Option<String>,
+    /// このタグが付いたお気に入りに限定する
+    tag: Option<String>,
+    /// `novels`（小説ごとに1件、デフォルト）または `chapters`（未読話ごとに1件）
+    mode: Option<String>,
+    /// `full` で本文HTMLを含める（`mode=chapters` のみ、新しい順に最大20件）
+    content: Option<String>,
+    /// フィードトークン（`POST /api/auth/feed-token` で発行）。指定時はヘッダーではなくトークンの持ち主のフィードを返す
+    token: Option<String>,
+}
+
+#[derive(Debug, PartialEq)]
+enum FeedMode {
+    Novels,
+    Chapters,
+}
+
+struct FeedFilter {
+    min_unread: i64,
+    max_unread: i64,
+    /// Comma-separated site list, validated
+    sites: Option<String>,
+    /// Single novel as `(type, id)`
+    novel: Option<(String, String)>,
+    tag: Option<String>,
+    mode: FeedMode,
+    full_content: bool,
+}
+
+/// Check a comma-separated `site` parameter, normalizing each site type.
+pub(super) fn site_list(site: Option<&str>) -> Result<Option<String>, AppError> {
+    match site.map(str::trim) {
+        None | Some("") => Ok(None),
+        Some(list) => {
+            let sites = list
+                .split(',')
+                .map(|s| ModuleType::resolve(s.trim()).map(|m| m.as_str()))
+                .collect::<Result<Vec<_>, _>>()?;
+            Ok(Some(sites.join(",")))
+        }
+    }
+}
+
+impl FeedQuery {
+    fn into_filter(self) -> Result<FeedFilter, AppError> {
+        let min_unread = self.min_unread.unwrap_or(DEFAULT_MIN_UNREAD);
+        // A single-novel feed should keep showing chapters however far behind the reader is
+        let max_unread = self.max_unread.unwrap_or(if self.novel.is_some() {
+            i64::MAX
+        } else {
+            DEFAULT_MAX_UNREAD
+        });
+        if min_unread < 0 || max_unread < min_unread {
+            return Err(AppError::BadRequest(
+                "min_unread must be >= 0 and <= max_unread".into(),
+            ));
+        }
+        let sites = site_list(self.site.as_deref())?;
+        let novel = match self.novel.as_deref() {
+            None => None,
+            Some(novel) => {
+                let (type_str, id) = novel.split_once(':').ok_or_else(|| {
+                    AppError::BadRequest("novel must be in the form {type}:{id}".into())
+                })?;
+                Some((
+                    ModuleType::resolve(type_str)?.as_str().to_string(),
+                    id.to_string(),
+                ))
+            }
+        };
+        let mode = match self.mode.as_deref() {
+            None | Some("novels") => FeedMode::Novels,
+            Some("chapters") => FeedMode::Chapters,
+            Some(other) => {
+                return Err(AppError::BadRequest(format!(
+                    "mode must be novels or chapters, got {}",
+                    other
+                )))
+            }
+        };
+        let full_content = match self.content.as_deref() {
+            None | Some("summary") => false,
+            Some("full") if mode == FeedMode::Chapters => true,
+            Some("full") => {
+                return Err(AppError::BadRequest(
+                    "content=full requires mode=chapters".into(),
+                ))
+            }
+            Some(other) => {
+                return Err(AppError::BadRequest(format!(
+                    "content must be summary or full, got {}",
+                    other
+                )))
+            }
+        };
+        Ok(FeedFilter {
+            min_unread,
+            max_unread,
+            sites,
+            novel,
+            tag: self.tag.filter(|t| !t.is_empty()),
+            mode,
+            full_content,
+        })
+    }
 }
 
 struct FeedItem {
@@ -20,65 +163,430 @@ struct FeedItem {
     read: i64,
 }
 
+/// One rendered feed item, independent of output format
+struct FeedEntry {
+    title: String,
+    link: String,
+    guid: String,
+    description: String,
+    updated: Option<DateTime<Utc>>,
+    /// Sanitized chapter HTML (`content=full` only)
+    content_html: Option<String>,
+    /// Source chapter, for filling in `content_html`
+    chapter: Option<(ModuleType, String, i64)>,
+}
+
+impl From<Favorite> for FeedItem {
+    fn from(f: Favorite) -> Self {
+        Self {
+            type_str: f.type_str,
+            id: f.id,
+            title: f.title,
+            novelupdated_at: f.novelupdated_at,
+            page: f.page,
+            read: f.read,
+        }
+    }
+}
+
+impl FeedItem {
+    fn updated(&self) -> Option<DateTime<Utc>> {
+        self.novelupdated_at.as_deref().and_then(parse_timestamp)
+    }
+
+    /// One entry per novel, linking to the first unread chapter.
+    fn to_entry(&self, base: &str) -> FeedEntry {
+        let next_page = (self.read + 1).min(self.page.max(1));
+        FeedEntry {
+            title: self.title.clone(),
+            link: format!("{}/novel/{}/{}/{}", base, self.type_str, self.id, next_page),
+            guid: format!("{}/{}/{}", base, self.type_str, self.id),
+            description: format!("{}話 / 既読{}話", self.page, self.read),
+            updated: self.updated(),
+            content_html: None,
+            chapter: None,
+        }
+    }
+
+    /// One entry per unread chapter, newest first. `toc` supplies chapter titles when available.
+    fn chapter_entries(&self, base: &str, toc: Option<&Value>) -> Vec<FeedEntry> {
+        let first = self.read.max(0) + 1;
+        (first..=self.page)
+            .rev()
+            .map(|num| {
+                let chapter = toc
+                    .and_then(|t| t["episodes"].as_array())
+                    .and_then(|eps| eps.iter().find(|e| e["num"].as_i64() == Some(num)))
+                    .and_then(|e| e["title"].as_str())
+                    .map(str::to_string)
+                    .unwrap_or_else(|| format!("第{}話", num));
+                FeedEntry {
+                    title: format!("{} - {}", self.title, chapter),
+                    link: format!("{}/novel/{}/{}/{}", base, self.type_str, self.id, num),
+                    guid: format!("{}/{}/{}/{}", base, self.type_str, self.id, num),
+                    description: format!("{} 第{}話 / 全{}話", self.title, num, self.page),
+                    updated: self.updated(),
+                    content_html: None,
+                    chapter: ModuleType::resolve(&self.type_str)
+                        .ok()
+                        .map(|m| (m, self.id.clone(), num)),
+                }
+            })
+            .collect()
+    }
+}
+
+/// Stored timestamps are `%Y-%m-%d %H:%M:%S` in UTC, but values saved from the client may use
+/// a `T` separator, carry an offset, or be a bare date.
+fn parse_timestamp(s: &str) -> Option<DateTime<Utc>> {
+    if let Ok(dt) = DateTime::parse_from_rfc3339(s) {
+        return Some(dt.with_timezone(&Utc));
+    }
+    ["%Y-%m-%d %H:%M:%S", "%Y-%m-%dT%H:%M:%S"]
+        .iter()
+        .find_map(|f| NaiveDateTime::parse_from_str(s, f).ok())
+        .or_else(|| {
+            NaiveDate::parse_from_str(s, "%Y-%m-%d")
+                .ok()
+                .and_then(|d| d.and_hms_opt(0, 0, 0))
+        })
+        .map(|dt| dt.and_utc())
+}
+
+/// Feed-level update time: the newest entry, or now when nothing has a date.
+fn last_updated(entries: &[FeedEntry]) -> DateTime<Utc> {
+    entries
+        .iter()
+        .filter_map(|e| e.updated)
+        .max()
+        .unwrap_or_else(Utc::now)
+}
+
+fn load_items(
+    conn: &Connection,
+    user_id: UserId,
+    filter: &FeedFilter,
+) -> rusqlite::Result<Vec<FeedItem>> {
+    let favorites = FavoritesRepo::new(conn).feed_items(
+        user_id,
+        &FeedItemsFilter {
+            min_unread: filter.min_unread,
+            max_unread: filter.max_unread,
+            sites: filter.sites.as_deref(),
+            novel: filter
+                .novel
+                .as_ref()
+                .map(|(t, id)| (t.as_str(), id.as_str())),
+            tag: filter.tag.as_deref(),
+        },
+    )?;
+    Ok(favorites.into_iter().map(FeedItem::from).collect())
+}
+
+/// TOC for chapter titles. Cached for `TOC_TTL`, but refetched early when the cached
+/// copy has fewer episodes than the novel now has. Failures just mean no titles.
+async fn cached_toc(state: &AppState, item: &FeedItem) -> Option<Value> {
+    let key = format!("novel:{}:{}:toc", item.type_str, item.id);
+    if let Some(toc) = state.cache.get(&key) {
+        let count = toc["episodes"].as_array().map_or(0, |e| e.len() as i64);
+        if count >= item.page {
+            return Some(toc);
+        }
+    }
+    let module = ModuleType::resolve(&item.type_str).ok()?;
+    match tokio::time::timeout(TOC_TIMEOUT, module.fetch_toc(&state.http, &item.id)).await {
+        Ok(Ok(toc)) => {
+            state.cache.set(&key, toc.clone(), Some(TOC_TTL));
+            Some(toc)
+        }
+        Ok(Err(e)) => {
+            tracing::warn!("[rss] toc {}/{} failed: {}", item.type_str, item.id, e);
+            None
+        }
+        Err(_) => {
+            tracing::warn!("[rss] toc {}/{} timed out", item.type_str, item.id);
+            None
+        }
+    }
+}
+
+/// Load and expand the feed for the requested mode.
+async fn load_entries(
+    state: &AppState,
+    user_id: UserId,
+    query: FeedQuery,
+    base: &str,
+) -> Result<Vec<FeedEntry>, AppError> {
+    let token = query.token.clone();
+    let filter = query.into_filter()?;
+    let (items, filter) = state
+        .db
+        .read(move |db| {
+            // A token always decides the user; an invalid one must not fall back to the header/guest feed
+            let user_id = match token.as_deref() {
+                Some(t) => FeedTokensRepo::new(db)
+                    .user(t)?
+                    .ok_or_else(|| AppError::Unauthorized("Invalid feed token".into()))?,
+                None => user_id,
+            };
+            Ok::<_, AppError>((load_items(db, user_id, &filter)?, filter))
+        })
+        .await?;
+    if filter.mode == FeedMode::Novels {
+        return Ok(items.iter().map(|item| item.to_entry(base)).collect());
+    }
+
+    // Only fetch TOCs for novels whose chapters will make it under the item cap
+    let mut budget = MAX_CHAPTER_ITEMS;
+    let mut wanted = Vec::new();
+    for item in &items {
+        if budget == 0 {
+            break;
+        }
+        budget = budget.saturating_sub((item.page - item.read).max(0) as usize);
+        wanted.push(item);
+    }
+    let mut tocs: Vec<Option<Value>> = Vec::with_capacity(wanted.len());
+    for chunk in wanted.chunks(TOC_CONCURRENCY) {
+        let fetches = chunk.iter().map(|item| cached_toc(state, item));
+        tocs.extend(futures::future::join_all(fetches).await);
+    }
+
+    let mut entries: Vec<FeedEntry> = wanted
+        .iter()
+        .zip(&tocs)
+        .flat_map(|(item, toc)| item.chapter_entries(base, toc.as_ref()))
+        .collect();
+    entries.truncate(MAX_CHAPTER_ITEMS);
+    if filter.full_content {
+        fill_content(state, &mut entries).await;
+    }
+    Ok(entries)
+}
+
+/// Attach chapter text to the newest entries via the page cache. A chapter that can't be
+/// fetched keeps its summary; the feed itself never fails because of it.
+async fn fill_content(state: &AppState, entries: &mut [FeedEntry]) {
+    let limit = entries.len().min(MAX_FULL_CONTENT_ITEMS);
+    for chunk in entries[..limit].chunks_mut(TOC_CONCURRENCY) {
+        let fetches = chunk.iter().map(|entry| async move {
+            let (module, id, num) = entry.chapter.as_ref()?;
+            let num_str = num.to_string();
+            let fetch = super::pages::page_html(state, module, id, &num_str);
+            match tokio::time::timeout(PAGE_TIMEOUT, fetch).await {
+                Ok(Ok(html)) => Some(html),
+                Ok(Err(e)) => {
+                    tracing::warn!(
+                        "[rss] page {}/{}/{} failed: {}",
+                        module.as_str(),
+                        id,
+                        num,
+                        e
+                    );
+                    None
+                }
+                Err(_) => {
+                    tracing::warn!("[rss] page {}/{}/{} timed out", module.as_str(), id, num);
+                    None
+                }
+            }
+        });
+        let pages = futures::future::join_all(fetches).await;
+        for (entry, html) in chunk.iter_mut().zip(pages) {
+            entry.content_html = html;
+        }
+    }
+}
+
 #[utoipa::path(
     get,
     path = "/api/rss",
     tag = "RSS",
     summary = "お気に入り更新RSSフィード",
-    description = "お気に入り小説のうち、未読が1〜9話（0 < 総ページ数 - 既読ページ < 10）の小説の更新情報をRSS 2.0形式で配信する。更新日時の降順。読み切った小説は表示されない。",
+    description = "お気に入り小説の更新情報をRSS 2.0形式で配信する。更新日時の降順。\n\n## フィルタ\nデフォルトでは未読が1〜9話の小説のみ（読み切った小説は表示されない）。`min_unread`・`max_unread` で範囲を、`site` で対象サイトを、`tag` でタグ（`GET /api/tags`）を絞り込める。`novel` を指定すると1作品のフィードになる（OPMLエクスポートの作品ごとのフィード）。\n\n## モード\n- `novels`（デフォルト）: 小説ごとに1件。リンクは最初の未読話\n- `chapters`: 未読話ごとに1件（最大100件）。タイトルは目次の話タイトル（取得できない場合は「第N話」）、リンクはその話、GUIDは `{base}/{type}/{id}/{話数}` で話ごとに固定\n\n## 本文\n`mode=chapters&content=full` で、新しい順に最大20件の話へサニタイズ済み本文HTMLを含める（RSSは `content:encoded`、Atomは `content`、JSON Feedは `content_html`）。本文はページ本文と同じキャッシュを使う。取得に失敗した話は概要のみ。\n\n日時はRFC 822形式。`lastBuildDate` は最も新しい更新日時。同じ内容をAtom（`/api/rss.atom`）・JSON Feed（`/api/feed.json`）でも配信する。\n\n## 認証\nフィードリーダーからは `?token=` にフィードトークン（`POST /api/auth/feed-token`）を指定する。",
+    params(FeedQuery),
     responses(
         (status = 200, description = "RSS 2.0 XML", content_type = "application/rss+xml"),
+        (status = 400, description = "パラメータ不正", body = crate::openapi::ErrorResponse,
+            example = json!({"error": "mode must be novels or chapters, got all"})),
+        (status = 401, description = "フィードトークンが無効", body = crate::openapi::ErrorResponse,
+            example = json!({"error": "Invalid feed token"})),
         (status = 500, description = "DBエラー", body = crate::openapi::ErrorResponse),
     ),
 )]
 async fn get_rss(
     State(state): State<AppState>,
     Extension(user_id): Extension<UserId>,
+    Query(query): Query<FeedQuery>,
     headers: HeaderMap,
 ) -> Result<impl IntoResponse, AppError> {
-    let items = {
-        let db = state.db.lock().unwrap();
-        let mut stmt = db.prepare(
-            "SELECT type, id, title, novelupdated_at, page, read FROM favorites
-             WHERE user_id = ?1 AND page - read > 0 AND page - read < 10
-             ORDER BY novelupdated_at DESC NULLS LAST",
-        )?;
-        let rows = stmt.query_map([user_id.0], |row| {
-            Ok(FeedItem {
-                type_str: row.get(0)?,
-                id: row.get(1)?,
-                title: row.get(2)?,
-                novelupdated_at: row.get(3)?,
-                page: row.get(4)?,
-                read: row.get(5)?,
-            })
-        })?;
-        rows.collect::<Result<Vec<_>, _>>()?
-    };
+    let base = resolve_base_url(&headers, &state.config);
+    let entries = load_entries(&state, user_id, query, &base).await?;
+    Ok((
+        [(header::CONTENT_TYPE, "application/rss+xml; charset=utf-8")],
+        build_rss(&entries, &base),
+    ))
+}
 
+#[utoipa::path(
+    get,
+    path = "/api/rss.atom",
+    tag = "RSS",
+    summary = "お気に入り更新Atomフィード",
+    description = "`/api/rss` と同じ内容をAtom 1.0形式で配信する。パラメータも共通。日時はRFC 3339形式。",
+    params(FeedQuery),
+    responses(
+        (status = 200, description = "Atom 1.0 XML", content_type = "application/atom+xml"),
+        (status = 400, description = "パラメータ不正", body = crate::openapi::ErrorResponse),
+        (status = 401, description = "フィードトークンが無効", body = crate::openapi::ErrorResponse),
+        (status = 500, description = "DBエラー", body = crate::openapi::ErrorResponse),
+    ),
+)]
+async fn get_atom(
+    State(state): State<AppState>,
+    Extension(user_id): Extension<UserId>,
+    Query(query): Query<FeedQuery>,
+    headers: HeaderMap,
+) -> Result<impl IntoResponse, AppError> {
     let base = resolve_base_url(&headers, &state.config);
+    let entries = load_entries(&state, user_id, query, &base).await?;
+    Ok((
+        [(header::CONTENT_TYPE, "application/atom+xml; charset=utf-8")],
+        build_atom(&entries, &base),
+    ))
+}
 
+#[utoipa::path(
+    get,
+    path = "/api/feed.json",
+    tag = "RSS",
+    summary = "お気に入り更新JSON Feed",
+    description = "`/api/rss` と同じ内容をJSON Feed 1.1形式で配信する。パラメータも共通。日時はRFC 3339形式。",
+    params(FeedQuery),
+    responses(
+        (status = 200, description = "JSON Feed 1.1", content_type = "application/feed+json"),
+        (status = 400, description = "パラメータ不正", body = crate::openapi::ErrorResponse),
+        (status = 401, description = "フィードトークンが無効", body = crate::openapi::ErrorResponse),
+        (status = 500, description = "DBエラー", body = crate::openapi::ErrorResponse),
+    ),
+)]
+async fn get_json_feed(
+    State(state): State<AppState>,
+    Extension(user_id): Extension<UserId>,
+    Query(query): Query<FeedQuery>,
+    headers: HeaderMap,
+) -> Result<impl IntoResponse, AppError> {
+    let base = resolve_base_url(&headers, &state.config);
+    let entries = load_entries(&state, user_id, query, &base).await?;
+    Ok((
+        [(header::CONTENT_TYPE, "application/feed+json; charset=utf-8")],
+        build_json_feed(&entries, &base).to_string(),
+    ))
+}
+
+fn build_rss(entries: &[FeedEntry], base: &str) -> String {
     let mut xml = String::from(
         r#"<?xml version="1.0" encoding="UTF-8"?>
-<rss version="2.0">
+<rss version="2.0" xmlns:content="http://purl.org/rss/1.0/modules/content/">
 <channel>
-<title>Novel Server - お気に入り更新</title>
-<description>お気に入り小説の更新情報</description>
 "#,
     );
-    xml.push_str(&format!("<link>{}</link>\n", escape_xml(&base)));
+    xml.push_str(&format!("<title>{}</title>\n", FEED_TITLE));
+    xml.push_str(&format!(
+        "<description>{}</description>\n",
+        FEED_DESCRIPTION
+    ));
+    xml.push_str(&format!("<link>{}</link>\n", escape_xml(base)));
+    xml.push_str(&format!(
+        "<lastBuildDate>{}</lastBuildDate>\n",
+        last_updated(entries).to_rfc2822()
+    ));
+    for entry in entries {
+        xml.push_str(&build_item_xml(entry));
+    }
+    xml.push_str("</channel>\n</rss>");
+    xml
+}
 
-    for item in &items {
-        xml.push_str(&build_item_xml(item, &base));
+fn build_atom(entries: &[FeedEntry], base: &str) -> String {
+    let updated = last_updated(entries);
+    let self_url = format!("{}/api/rss.atom", base);
+    let mut xml = String::from(
+        r#"<?xml version="1.0" encoding="UTF-8"?>
+<feed xmlns="http://www.w3.org/2005/Atom">
+"#,
+    );
+    xml.push_str(&format!("<title>{}</title>\n", FEED_TITLE));
+    xml.push_str(&format!("<subtitle>{}</subtitle>\n", FEED_DESCRIPTION));
+    xml.push_str(&format!("<link href=\"{}\"/>\n", escape_xml(base)));
+    xml.push_str(&format!(
+        "<link rel=\"self\" type=\"application/atom+xml\" href=\"{}\"/>\n",
+        escape_xml(&self_url)
+    ));
+    xml.push_str(&format!("<id>{}</id>\n", escape_xml(&self_url)));
+    xml.push_str(&format!("<updated>{}</updated>\n", atom_date(updated)));
+    xml.push_str("<author><name>Novel Server</name></author>\n");
+    for entry in entries {
+        xml.push_str("<entry>\n");
+        xml.push_str(&format!("<title>{}</title>\n", escape_xml(&entry.title)));
+        xml.push_str(&format!("<link href=\"{}\"/>\n", escape_xml(&entry.link)));
+        xml.push_str(&format!("<id>{}</id>\n", escape_xml(&entry.guid)));
+        // Atom requires <updated> on every entry; undated items fall back to the feed's time
+        xml.push_str(&format!(
+            "<updated>{}</updated>\n",
+            atom_date(entry.updated.unwrap_or(updated))
+        ));
+        xml.push_str(&format!(
+            "<summary>{}</summary>\n",
+            escape_xml(&entry.description)
+        ));
+        if let Some(html) = &entry.content_html {
+            xml.push_str(&format!(
+                "<content type=\"html\">{}</content>\n",
+                escape_xml(html)
+            ));
+        }
+        xml.push_str("</entry>\n");
     }
+    xml.push_str("</feed>");
+    xml
+}
 
-    xml.push_str("</channel>\n</rss>");
+fn atom_date(dt: DateTime<Utc>) -> String {
+    dt.to_rfc3339_opts(SecondsFormat::Secs, true)
+}
 
-    Ok(([(header::CONTENT_TYPE, "application/rss+xml; charset=utf-8")], xml))
+fn build_json_feed(entries: &[FeedEntry], base: &str) -> Value {
+    let items: Vec<Value> = entries
+        .iter()
+        .map(|entry| {
+            let mut item = json!({
+                "id": entry.guid,
+                "url": entry.link,
+                "title": entry.title,
+                "content_text": entry.description,
+            });
+            if let Some(dt) = entry.updated {
+                item["date_modified"] = json!(atom_date(dt));
+            }
+            if let Some(html) = &entry.content_html {
+                item["content_html"] = json!(html);
+            }
+            item
+        })
+        .collect();
+    json!({
+        "version": "https://jsonfeed.org/version/1.1",
+        "title": FEED_TITLE,
+        "description": FEED_DESCRIPTION,
+        "home_page_url": base,
+        "feed_url": format!("{}/api/feed.json", base),
+        "items": items,
+    })
 }
 
 /// Derive the base URL from request headers (reverse proxy or direct access).
-fn resolve_base_url(headers: &HeaderMap, config: &crate::config::Config) -> String {
+pub(super) fn resolve_base_url(headers: &HeaderMap, config: &crate::config::Config) -> String {
     let proto = headers
         .get("x-forwarded-proto")
         .and_then(|v| v.to_str().ok())
@@ -94,7 +602,7 @@ fn resolve_base_url(headers: &HeaderMap, config: &crate::config::Config) -> Stri
     format!("{}://{}{}", proto, host, config.base_path)
 }
 
-fn escape_xml(s: &str) -> String {
+pub(super) fn escape_xml(s: &str) -> String {
     s.replace('&', "&amp;")
         .replace('<', "&lt;")
         .replace('>', "&gt;")
@@ -102,25 +610,24 @@ fn escape_xml(s: &str) -> String {
         .replace('\'', "&apos;")
 }
 
-fn build_item_xml(item: &FeedItem, base: &str) -> String {
-    let next_page = (item.read + 1).min(item.page.max(1));
-    let link = format!("{}/novel/{}/{}/{}", base, item.type_str, item.id, next_page);
+fn build_item_xml(entry: &FeedEntry) -> String {
     let mut xml = String::from("<item>\n");
-    xml.push_str(&format!("<title>{}</title>\n", escape_xml(&item.title)));
-    xml.push_str(&format!("<link>{}</link>\n", escape_xml(&link)));
+    xml.push_str(&format!("<title>{}</title>\n", escape_xml(&entry.title)));
+    xml.push_str(&format!("<link>{}</link>\n", escape_xml(&entry.link)));
     xml.push_str(&format!(
-        "<description>{}話 / 既読{}話</description>\n",
-        item.page, item.read
+        "<description>{}</description>\n",
+        escape_xml(&entry.description)
     ));
-    if let Some(ref dt) = item.novelupdated_at {
-        xml.push_str(&format!("<pubDate>{}</pubDate>\n", escape_xml(dt)));
+    if let Some(dt) = entry.updated {
+        xml.push_str(&format!("<pubDate>{}</pubDate>\n", dt.to_rfc2822()));
+    }
+    xml.push_str(&format!("<guid>{}</guid>\n", escape_xml(&entry.guid)));
+    if let Some(html) = &entry.content_html {
+        xml.push_str(&format!(
+            "<content:encoded><![CDATA[{}]]></content:encoded>\n",
+            html.replace("]]>", "]]]]><![CDATA[>")
+        ));
     }
-    xml.push_str(&format!(
-        "<guid>{}/{}/{}</guid>\n",
-        escape_xml(base),
-        escape_xml(&item.type_str),
-        escape_xml(&item.id)
-    ));
     xml.push_str("</item>\n");
     xml
 }
@@ -128,6 +635,7 @@ fn build_item_xml(item: &FeedItem, base: &str) -> String {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use chrono::TimeZone;
 
     #[test]
     fn escape_xml_special_chars() {
@@ -153,12 +661,12 @@ mod tests {
             page: 100,
             read: 98,
         };
-        let xml = build_item_xml(&item, "http://localhost:3000");
+        let xml = build_item_xml(&item.to_entry("http://localhost:3000"));
         assert!(xml.contains("<title>Test Novel</title>"));
         assert!(xml.contains("<link>http://localhost:3000/novel/narou/n1234ab/99</link>"),
             "read=98, page=100 should link to 99");
         assert!(xml.contains("<description>100話 / 既読98話</description>"));
-        assert!(xml.contains("<pubDate>2026-03-14T00:00:00</pubDate>"));
+        assert!(xml.contains("<pubDate>Sat, 14 Mar 2026 00:00:00 +0000</pubDate>"));
         assert!(xml.contains("<guid>http://localhost:3000/narou/n1234ab</guid>"));
     }
 
@@ -172,7 +680,7 @@ mod tests {
             page: 50,
             read: 48,
         };
-        let xml = build_item_xml(&item, "http://localhost:3000");
+        let xml = build_item_xml(&item.to_entry("http://localhost:3000"));
         assert!(!xml.contains("<pubDate>"));
     }
 
@@ -181,6 +689,12 @@ mod tests {
             port: 3000,
             base_path: base_path.to_string(),
             db_path: String::new(),
+            vapid_private_key: None,
+            vapid_subject: String::new(),
+            public_url: None,
+            smtp: None,
+            migration_owner_email: None,
+            webhook_allow_private: false,
         }
     }
 
@@ -233,7 +747,7 @@ mod tests {
             page: 10,
             read: 0,
         };
-        let xml = build_item_xml(&item, "http://localhost:3000");
+        let xml = build_item_xml(&item.to_entry("http://localhost:3000"));
         assert!(xml.contains("/n1/1</link>"), "read=0 should link to page 1");
 
         let item2 = FeedItem {
@@ -244,7 +758,7 @@ mod tests {
             page: 10,
             read: 5,
         };
-        let xml2 = build_item_xml(&item2, "http://localhost:3000");
+        let xml2 = build_item_xml(&item2.to_entry("http://localhost:3000"));
         assert!(xml2.contains("/n2/6</link>"), "read=5 should link to page 6");
     }
 
@@ -258,7 +772,7 @@ mod tests {
             page: 100,
             read: 100,
         };
-        let xml = build_item_xml(&item, "http://localhost:3000");
+        let xml = build_item_xml(&item.to_entry("http://localhost:3000"));
         assert!(xml.contains("/n1/100</link>"),
             "read=100, page=100 should clamp to 100, not 101");
     }
@@ -273,7 +787,323 @@ mod tests {
             page: 10,
             read: 9,
         };
-        let xml = build_item_xml(&item, "http://localhost:3000");
+        let xml = build_item_xml(&item.to_entry("http://localhost:3000"));
         assert!(xml.contains("Title &lt;with&gt; &amp; &quot;special&quot; chars"));
     }
+
+    fn item(id: &str, updated: Option<&str>) -> FeedItem {
+        FeedItem {
+            type_str: "narou".into(),
+            id: id.into(),
+            title: "Novel & Co".into(),
+            novelupdated_at: updated.map(str::to_string),
+            page: 10,
+            read: 8,
+        }
+    }
+
+    #[test]
+    fn parse_timestamp_accepts_stored_formats() {
+        let expected = Utc.with_ymd_and_hms(2026, 3, 14, 9, 30, 0).unwrap();
+        assert_eq!(parse_timestamp("2026-03-14 09:30:00"), Some(expected));
+        assert_eq!(parse_timestamp("2026-03-14T09:30:00"), Some(expected));
+        assert_eq!(parse_timestamp("2026-03-14T18:30:00+09:00"), Some(expected));
+        assert_eq!(
+            parse_timestamp("2026-03-14"),
+            Some(Utc.with_ymd_and_hms(2026, 3, 14, 0, 0, 0).unwrap())
+        );
+        assert_eq!(parse_timestamp("yesterday"), None);
+    }
+
+    #[test]
+    fn build_rss_has_rfc822_last_build_date_from_newest_item() {
+        let items = vec![
+            item("n1", Some("2026-03-13 00:00:00")),
+            item("n2", Some("2026-03-14 09:30:00")),
+            item("n3", None),
+        ];
+        let xml = build_rss(
+            &entries(&items, "http://localhost:3000"),
+            "http://localhost:3000",
+        );
+        assert!(xml.contains("<lastBuildDate>Sat, 14 Mar 2026 09:30:00 +0000</lastBuildDate>"));
+        assert_eq!(xml.matches("<item>").count(), 3);
+    }
+
+    #[test]
+    fn build_atom_has_feed_and_entry_updated() {
+        let items = vec![item("n1", Some("2026-03-14 09:30:00")), item("n2", None)];
+        let xml = build_atom(
+            &entries(&items, "https://example.com/novels"),
+            "https://example.com/novels",
+        );
+        assert!(xml.contains(r#"<feed xmlns="http://www.w3.org/2005/Atom">"#));
+        assert!(xml.contains(
+            r#"<link rel="self" type="application/atom+xml" href="https://example.com/novels/api/rss.atom"/>"#
+        ));
+        // Feed-level and the undated entry both use the newest item time
+        assert_eq!(
+            xml.matches("<updated>2026-03-14T09:30:00Z</updated>")
+                .count(),
+            3
+        );
+        assert!(xml.contains("<id>https://example.com/novels/narou/n1</id>"));
+        assert!(xml.contains(r#"<link href="https://example.com/novels/novel/narou/n1/9"/>"#));
+        assert!(xml.contains("<title>Novel &amp; Co</title>"));
+    }
+
+    #[test]
+    fn build_json_feed_follows_version_1_1() {
+        let items = vec![item("n1", Some("2026-03-14 09:30:00")), item("n2", None)];
+        let feed = build_json_feed(
+            &entries(&items, "http://localhost:3000"),
+            "http://localhost:3000",
+        );
+        assert_eq!(feed["version"], "https://jsonfeed.org/version/1.1");
+        assert_eq!(feed["feed_url"], "http://localhost:3000/api/feed.json");
+        assert_eq!(feed["items"][0]["id"], "http://localhost:3000/narou/n1");
+        assert_eq!(
+            feed["items"][0]["url"],
+            "http://localhost:3000/novel/narou/n1/9"
+        );
+        assert_eq!(feed["items"][0]["title"], "Novel & Co");
+        assert_eq!(feed["items"][0]["date_modified"], "2026-03-14T09:30:00Z");
+        assert!(feed["items"][1].get("date_modified").is_none());
+    }
+
+    fn entries(items: &[FeedItem], base: &str) -> Vec<FeedEntry> {
+        items.iter().map(|i| i.to_entry(base)).collect()
+    }
+
+    fn query(
+        min: Option<i64>,
+        max: Option<i64>,
+        site: Option<&str>,
+        mode: Option<&str>,
+    ) -> FeedQuery {
+        FeedQuery {
+            min_unread: min,
+            max_unread: max,
+            site: site.map(str::to_string),
+            mode: mode.map(str::to_string),
+            content: None,
+            novel: None,
+            tag: None,
+            token: None,
+        }
+    }
+
+    #[test]
+    fn feed_query_defaults_and_validation() {
+        let filter = query(None, None, None, None).into_filter().unwrap();
+        assert_eq!((filter.min_unread, filter.max_unread), (1, 9));
+        assert_eq!(filter.mode, FeedMode::Novels);
+        assert!(filter.sites.is_none());
+
+        let filter = query(None, Some(50), Some("narou, kakuyomu"), Some("chapters"))
+            .into_filter()
+            .unwrap();
+        assert_eq!(filter.sites.as_deref(), Some("narou,kakuyomu"));
+        assert_eq!(filter.mode, FeedMode::Chapters);
+
+        assert!(query(Some(5), Some(2), None, None).into_filter().is_err());
+        assert!(query(Some(-1), None, None, None).into_filter().is_err());
+        assert!(query(None, None, Some("pixiv"), None)
+            .into_filter()
+            .is_err());
+        assert!(query(None, None, None, Some("all")).into_filter().is_err());
+    }
+
+    #[test]
+    fn load_items_applies_unread_range_and_site() {
+        let conn = crate::db::open_memory();
+        conn.execute_batch(
+            "INSERT INTO novels (type, id, title, page) VALUES
+                ('narou', 'a', 'A', 20), ('narou', 'b', 'B', 10), ('kakuyomu', 'c', 'C', 5), ('narou', 'd', 'D', 3);
+             INSERT INTO favorites (user_id, type, id, read) VALUES
+                (1, 'narou', 'a', 5), (1, 'narou', 'b', 8), (1, 'kakuyomu', 'c', 4), (1, 'narou', 'd', 3);",
+        )
+        .unwrap();
+        let ids = |q: FeedQuery| {
+            let mut ids: Vec<String> = load_items(&conn, UserId(1), &q.into_filter().unwrap())
+                .unwrap()
+                .into_iter()
+                .map(|i| i.id)
+                .collect();
+            ids.sort();
+            ids
+        };
+        // Default 1..=9: the novel 15 behind is excluded
+        assert_eq!(ids(query(None, None, None, None)), ["b", "c"]);
+        assert_eq!(ids(query(None, Some(20), None, None)), ["a", "b", "c"]);
+        assert_eq!(
+            ids(query(Some(0), Some(20), Some("narou"), None)),
+            ["a", "b", "d"]
+        );
+        assert_eq!(ids(query(Some(2), None, None, None)), ["b"]);
+
+        let mut q = query(Some(0), None, None, None);
+        q.novel = Some("narou:d".into());
+        assert_eq!(ids(q), ["d"]);
+        let mut q = query(None, None, None, None);
+        q.novel = Some("narou:a".into());
+        assert_eq!(ids(q), ["a"]);
+    }
+
+    #[test]
+    fn novel_filter_is_validated() {
+        let mut q = query(None, None, None, None);
+        q.novel = Some("kakuyomu:123".into());
+        assert_eq!(
+            q.into_filter().unwrap().novel,
+            Some(("kakuyomu".into(), "123".into()))
+        );
+        for bad in ["n1234ab", "pixiv:1"] {
+            let mut q = query(None, None, None, None);
+            q.novel = Some(bad.into());
+            assert!(q.into_filter().is_err(), "{}", bad);
+        }
+    }
+
+    #[test]
+    fn chapter_entries_use_toc_titles_and_stable_guids() {
+        let novel = FeedItem {
+            type_str: "narou".into(),
+            id: "n1".into(),
+            title: "Novel".into(),
+            novelupdated_at: Some("2026-03-14 09:30:00".into()),
+            page: 12,
+            read: 9,
+        };
+        let toc = json!({"title": "Novel", "episodes": [
+            {"num": 10, "title": "Chapter Ten"},
+            {"num": 12, "title": "Chapter Twelve"},
+        ]});
+        let entries = novel.chapter_entries("http://localhost:3000", Some(&toc));
+        assert_eq!(entries.len(), 3);
+        assert_eq!(entries[0].title, "Novel - Chapter Twelve");
+        assert_eq!(entries[0].link, "http://localhost:3000/novel/narou/n1/12");
+        assert_eq!(entries[0].guid, "http://localhost:3000/narou/n1/12");
+        assert_eq!(
+            entries[1].title, "Novel - 第11話",
+            "missing TOC entry falls back"
+        );
+        assert_eq!(entries[2].guid, "http://localhost:3000/narou/n1/10");
+
+        let entries = novel.chapter_entries("http://localhost:3000", None);
+        assert_eq!(entries[0].title, "Novel - 第12話");
+    }
+
+    #[tokio::test]
+    async fn token_selects_its_owner_and_rejects_unknown_tokens() {
+        let conn = crate::db::open_memory();
+        conn.execute_batch(
+            "INSERT INTO users (id, email) VALUES (2, 'alice@example.com');
+             INSERT INTO novels (type, id, title, page) VALUES ('narou', 'n1', 'Alice Novel', 5);
+             INSERT INTO favorites (user_id, type, id, read) VALUES (2, 'narou', 'n1', 3);",
+        )
+        .unwrap();
+        conn.execute(
+            "INSERT INTO feed_tokens (user_id, token_hash) VALUES (2, ?1)",
+            [crate::auth::hash_token("secret-token")],
+        )
+        .unwrap();
+        let state = AppState {
+            db: crate::db::Db::from_connection(conn),
+            cache: std::sync::Arc::new(crate::cache::Cache::new()),
+            config: test_config(""),
+            http: reqwest::Client::new(),
+        };
+        let with_token = |token: Option<&str>| {
+            let mut q = query(None, None, None, None);
+            q.token = token.map(str::to_string);
+            q
+        };
+
+        // Guest (header-less) request sees nothing; the token unlocks Alice's feed
+        let guest = load_entries(&state, UserId(1), with_token(None), "http://x")
+            .await
+            .unwrap();
+        assert!(guest.is_empty());
+        let alice = load_entries(
+            &state,
+            UserId(1),
+            with_token(Some("secret-token")),
+            "http://x",
+        )
+        .await
+        .unwrap();
+        assert_eq!(alice.len(), 1);
+        assert_eq!(alice[0].title, "Alice Novel");
+
+        let err = load_entries(&state, UserId(2), with_token(Some("wrong")), "http://x").await;
+        assert!(matches!(err, Err(AppError::Unauthorized(_))));
+    }
+
+    #[test]
+    fn full_content_requires_chapter_mode() {
+        let mut q = query(None, None, None, Some("chapters"));
+        q.content = Some("full".into());
+        assert!(q.into_filter().unwrap().full_content);
+
+        let mut q = query(None, None, None, None);
+        q.content = Some("full".into());
+        assert!(q.into_filter().is_err());
+
+        let mut q = query(None, None, None, Some("chapters"));
+        q.content = Some("everything".into());
+        assert!(q.into_filter().is_err());
+    }
+
+    #[test]
+    fn content_html_is_embedded_in_every_format() {
+        let novel = FeedItem {
+            type_str: "narou".into(),
+            id: "n1".into(),
+            title: "Novel".into(),
+            novelupdated_at: None,
+            page: 2,
+            read: 1,
+        };
+        let mut entries = novel.chapter_entries("http://x", None);
+        entries[0].content_html = Some("<p>本文 ]]> end</p>".into());
+
+        let rss = build_rss(&entries, "http://x");
+        assert!(rss.contains(r#"xmlns:content="http://purl.org/rss/1.0/modules/content/""#));
+        assert!(rss.contains(
+            "<content:encoded><![CDATA[<p>本文 ]]]]><![CDATA[> end</p>]]></content:encoded>"
+        ));
+        let atom = build_atom(&entries, "http://x");
+        assert!(
+            atom.contains(r#"<content type="html">&lt;p&gt;本文 ]]&gt; end&lt;/p&gt;</content>"#)
+        );
+        let json = build_json_feed(&entries, "http://x");
+        assert_eq!(json["items"][0]["content_html"], "<p>本文 ]]> end</p>");
+    }
+
+    #[tokio::test]
+    async fn fill_content_reads_through_page_cache() {
+        let state = AppState {
+            db: crate::db::Db::from_connection(crate::db::open_memory()),
+            cache: std::sync::Arc::new(crate::cache::Cache::new()),
+            config: test_config(""),
+            http: reqwest::Client::new(),
+        };
+        state.cache.set(
+            "novel:narou:n1:page:2",
+            Value::String("<p>cached</p>".into()),
+            None,
+        );
+        let novel = FeedItem {
+            type_str: "narou".into(),
+            id: "n1".into(),
+            title: "Novel".into(),
+            novelupdated_at: None,
+            page: 2,
+            read: 1,
+        };
+        let mut entries = novel.chapter_entries("http://x", None);
+        fill_content(&state, &mut entries).await;
+        assert_eq!(entries[0].content_html.as_deref(), Some("<p>cached</p>"));
+    }
 }
@@ -0,0 +1,425 @@
+use crate::auth::UserId;
+use crate::error::AppError;
+use crate::repo::{
+    Backlog, ChapterLengthsRepo, DayReads, NovelReads, ReadingEventsRepo, SessionTotals,
+};
+use crate::state::AppState;
+use axum::extract::State;
+use axum::routing::get;
+use axum::{Extension, Json, Router};
+use chrono::{Datelike, Days, NaiveDate, Utc};
+use serde_json::{json, Value};
+use std::collections::BTreeMap;
+
+pub fn routes() -> Router<AppState> {
+    Router::new().route("/api/stats", get(get_stats))
+}
+
+const RECENT_DAYS: u64 = 30;
+const RECENT_WEEKS: u64 = 12;
+const RECENT_MONTHS: i32 = 12;
+/// Reads further apart than this start a new session
+const SESSION_GAP_SECS: i64 = 30 * 60;
+/// Typical Japanese reading speed, used until the history has enough timed reading
+const DEFAULT_CHARS_PER_MINUTE: f64 = 500.0;
+/// Timed reading needed before the measured speed replaces the default
+const MIN_MEASURED_MINUTES: f64 = 10.0;
+
+#[utoipa::path(
+    get,
+    path = "/api/stats",
+    tag = "閲覧履歴",
+    summary = "読書統計取得",
+    description = "閲覧履歴と記録済みの話の文字数から読書統計を集計する。日付はUTC。\n\n## 集計\n- 読んだ話数・文字数を日別（直近30日）・週別（直近12週、ISO週）・月別（直近12か月）・サイト別・作品別に集計する。文字数は本文を一度でも取得した話のみ数える\n- 連続読書日数（今日または昨日まで続いている日数と最長記録）\n- セッション（30分以上間隔が空くと別セッション）あたりの平均話数\n\n## 追いつくまでの時間\n未読のあるお気に入りごとに、未読話の文字数を読書速度で割って見積もる。文字数が未記録の話はその作品（なければ全作品）の平均文字数で補う。読書速度はセッション内の話の切り替え間隔から計測し、計測時間が10分に満たない間は毎分500文字とする。",
+    responses(
+        (status = 200, description = "読書統計", body = crate::openapi::StatsResponse),
+        (status = 500, description = "DBエラー", body = crate::openapi::ErrorResponse),
+    ),
+)]
+async fn get_stats(
+    State(state): State<AppState>,
+    Extension(user_id): Extension<UserId>,
+) -> Result<Json<Value>, AppError> {
+    let (days, novels, sessions, backlog, average_chars) = state
+        .db
+        .read(move |conn| {
+            let events = ReadingEventsRepo::new(conn);
+            let lengths = ChapterLengthsRepo::new(conn);
+            Ok::<_, rusqlite::Error>((
+                events.days(user_id)?,
+                events.novels(user_id)?,
+                events.sessions(user_id, SESSION_GAP_SECS)?,
+                lengths.backlog(user_id)?,
+                lengths.average()?,
+            ))
+        })
+        .await?;
+    Ok(Json(compute(
+        &days,
+        &novels,
+        &sessions,
+        &backlog,
+        average_chars,
+        Utc::now().date_naive(),
+    )))
+}
+
+#[derive(Default)]
+struct Tally {
+    chapters: i64,
+    characters: i64,
+}
+
+impl Tally {
+    fn add(&mut self, chapters: i64, characters: i64) {
+        self.chapters += chapters;
+        self.characters += characters;
+    }
+
+    fn to_json(&self) -> Value {
+        json!({ "chapters": self.chapters, "characters": self.characters })
+    }
+}
+
+fn periods_json(periods: BTreeMap<String, Tally>) -> Value {
+    periods
+        .into_iter()
+        .map(|(period, tally)| {
+            json!({
+                "period": period,
+                "chapters": tally.chapters,
+                "characters": tally.characters,
+            })
+        })
+        .collect()
+}
+
+/// `days` must be oldest first, as `ReadingEventsRepo::days` returns them.
+fn compute(
+    days: &[DayReads],
+    novels: &[NovelReads],
+    sessions: &SessionTotals,
+    backlog: &[Backlog],
+    average_chars: Option<f64>,
+    today: NaiveDate,
+) -> Value {
+    let days: Vec<(NaiveDate, &DayReads)> = days
+        .iter()
+        .filter_map(|d| Some((NaiveDate::parse_from_str(&d.date, "%Y-%m-%d").ok()?, d)))
+        .collect();
+
+    let first_day = today - Days::new(RECENT_DAYS - 1);
+    let first_week =
+        today.week(chrono::Weekday::Mon).first_day() - Days::new(7 * (RECENT_WEEKS - 1));
+    let month_index = |d: NaiveDate| d.year() * 12 + d.month0() as i32;
+    let first_month = month_index(today) - (RECENT_MONTHS - 1);
+
+    let mut by_day = BTreeMap::new();
+    let mut by_week = BTreeMap::new();
+    let mut by_month = BTreeMap::new();
+    for &(date, day) in &days {
+        if date >= first_day {
+            by_day
+                .entry(day.date.clone())
+                .or_insert_with(Tally::default)
+                .add(day.chapters, day.characters);
+        }
+        if date >= first_week {
+            let week = date.iso_week();
+            by_week
+                .entry(format!("{}-W{:02}", week.year(), week.week()))
+                .or_insert_with(Tally::default)
+                .add(day.chapters, day.characters);
+        }
+        if month_index(date) >= first_month {
+            by_month
+                .entry(date.format("%Y-%m").to_string())
+                .or_insert_with(Tally::default)
+                .add(day.chapters, day.characters);
+        }
+    }
+
+    let mut total = Tally::default();
+    let mut by_site: BTreeMap<&str, Tally> = BTreeMap::new();
+    for novel in novels {
+        total.add(novel.chapters, novel.characters);
+        by_site
+            .entry(&novel.type_str)
+            .or_default()
+            .add(novel.chapters, novel.characters);
+    }
+
+    // Streaks
+    let mut longest = 0;
+    let mut run = 0;
+    let mut previous: Option<NaiveDate> = None;
+    for &(day, _) in &days {
+        run = match previous {
+            Some(p) if p.succ_opt() == Some(day) => run + 1,
+            _ => 1,
+        };
+        longest = longest.max(run);
+        previous = Some(day);
+    }
+    let read_on = |d: &NaiveDate| days.binary_search_by_key(d, |&(day, _)| day).is_ok();
+    let mut current = 0;
+    let mut day = if read_on(&today) {
+        Some(today)
+    } else {
+        today.pred_opt().filter(read_on)
+    };
+    while let Some(d) = day.filter(read_on) {
+        current += 1;
+        day = d.pred_opt();
+    }
+
+    let timed_minutes = sessions.timed_secs as f64 / 60.0;
+    let measured = timed_minutes >= MIN_MEASURED_MINUTES && sessions.timed_chars > 0;
+    let chars_per_minute = if measured {
+        sessions.timed_chars as f64 / timed_minutes
+    } else {
+        DEFAULT_CHARS_PER_MINUTE
+    };
+
+    let mut total_minutes = 0;
+    let catch_up: Vec<Value> = backlog
+        .iter()
+        .map(|b| {
+            let unread = b.page - b.read;
+            let unknown = unread - b.known_chapters;
+            let characters = if unknown == 0 {
+                Some(b.known_chars)
+            } else {
+                b.average_chars
+                    .or(average_chars)
+                    .map(|avg| b.known_chars + (unknown as f64 * avg).round() as i64)
+            };
+            let minutes = characters.map(|c| (c as f64 / chars_per_minute).ceil() as i64);
+            total_minutes += minutes.unwrap_or(0);
+            json!({
+                "type": b.type_str,
+                "id": b.id,
+                "title": b.title,
+                "unread_chapters": unread,
+                "estimated_characters": characters,
+                "estimated_minutes": minutes,
+            })
+        })
+        .collect();
+
+    json!({
+        "total": total.to_json(),
+        "by_day": periods_json(by_day),
+        "by_week": periods_json(by_week),
+        "by_month": periods_json(by_month),
+        "by_site": by_site
+            .into_iter()
+            .map(|(site, tally)| json!({
+                "type": site,
+                "chapters": tally.chapters,
+                "characters": tally.characters,
+            }))
+            .collect::<Vec<_>>(),
+        "by_novel": novels
+            .iter()
+            .map(|n| json!({
+                "type": n.type_str,
+                "id": n.novel_id,
+                "title": n.title,
+                "chapters": n.chapters,
+                "characters": n.characters,
+                "last_read_at": n.last_read_at,
+            }))
+            .collect::<Vec<_>>(),
+        "streak": { "current": current, "longest": longest },
+        "sessions": {
+            "count": sessions.count,
+            "average_chapters": if sessions.count == 0 {
+                0.0
+            } else {
+                sessions.chapters as f64 / sessions.count as f64
+            },
+        },
+        "reading_speed": {
+            "characters_per_minute": chars_per_minute.round() as i64,
+            "measured": measured,
+        },
+        "catch_up": {
+            "total_minutes": total_minutes,
+            "novels": catch_up,
+        },
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn day(date: &str, chapters: i64, characters: i64) -> DayReads {
+        DayReads {
+            date: date.into(),
+            chapters,
+            characters,
+        }
+    }
+
+    fn novel(id: &str, chapters: i64, characters: i64) -> NovelReads {
+        NovelReads {
+            type_str: "narou".into(),
+            novel_id: id.into(),
+            title: None,
+            chapters,
+            characters,
+            last_read_at: "2026-03-13T09:00:00.000Z".into(),
+        }
+    }
+
+    fn date(s: &str) -> NaiveDate {
+        NaiveDate::parse_from_str(s, "%Y-%m-%d").unwrap()
+    }
+
+    fn from_days(days: &[DayReads], today: &str) -> Value {
+        compute(days, &[], &SessionTotals::default(), &[], None, date(today))
+    }
+
+    #[test]
+    fn tallies_periods_and_novels() {
+        let days = vec![
+            day("2025-12-31", 1, 1000),
+            day("2026-03-13", 1, 2000),
+            day("2026-03-14", 1, 0),
+        ];
+        let novels = vec![novel("n1", 2, 3000), novel("n2", 1, 0)];
+        let stats = compute(
+            &days,
+            &novels,
+            &SessionTotals::default(),
+            &[],
+            None,
+            date("2026-03-14"),
+        );
+        assert_eq!(stats["total"], json!({"chapters": 3, "characters": 3000}));
+        assert_eq!(
+            stats["by_day"],
+            json!([
+                {"period": "2026-03-13", "chapters": 1, "characters": 2000},
+                {"period": "2026-03-14", "chapters": 1, "characters": 0},
+            ])
+        );
+        assert_eq!(
+            stats["by_week"],
+            // New Year's Eve 2025 falls in the first ISO week of 2026
+            json!([
+                {"period": "2026-W01", "chapters": 1, "characters": 1000},
+                {"period": "2026-W11", "chapters": 2, "characters": 2000},
+            ])
+        );
+        assert_eq!(
+            stats["by_month"],
+            json!([
+                {"period": "2025-12", "chapters": 1, "characters": 1000},
+                {"period": "2026-03", "chapters": 2, "characters": 2000},
+            ])
+        );
+        assert_eq!(stats["by_novel"][0]["id"], "n1");
+        assert_eq!(
+            stats["by_novel"][0]["last_read_at"],
+            "2026-03-13T09:00:00.000Z"
+        );
+        assert_eq!(
+            stats["by_site"],
+            json!([{"type": "narou", "chapters": 3, "characters": 3000}])
+        );
+    }
+
+    #[test]
+    fn streaks_count_consecutive_days() {
+        let days = vec![
+            day("2026-03-01", 1, 0),
+            day("2026-03-02", 1, 0),
+            day("2026-03-03", 1, 0),
+            day("2026-03-12", 1, 0),
+            day("2026-03-13", 1, 0),
+        ];
+        // Not read yet today, but the streak through yesterday still counts
+        assert_eq!(
+            from_days(&days, "2026-03-14")["streak"],
+            json!({"current": 2, "longest": 3})
+        );
+        assert_eq!(from_days(&days, "2026-03-15")["streak"]["current"], 0);
+    }
+
+    #[test]
+    fn sessions_measure_reading_speed() {
+        let sessions = SessionTotals {
+            count: 2,
+            chapters: 4,
+            timed_chars: 12000,
+            timed_secs: 1200,
+        };
+        let stats = compute(&[], &[], &sessions, &[], None, date("2026-03-14"));
+        assert_eq!(
+            stats["sessions"],
+            json!({"count": 2, "average_chapters": 2.0})
+        );
+        assert_eq!(
+            stats["reading_speed"],
+            json!({"characters_per_minute": 600, "measured": true})
+        );
+    }
+
+    #[test]
+    fn catch_up_fills_unknown_lengths_with_averages() {
+        let backlog = vec![
+            Backlog {
+                type_str: "narou".into(),
+                id: "n1".into(),
+                title: "One".into(),
+                page: 10,
+                read: 6,
+                known_chapters: 2,
+                known_chars: 4000,
+                average_chars: Some(1500.0),
+            },
+            Backlog {
+                type_str: "narou".into(),
+                id: "n2".into(),
+                title: "Two".into(),
+                page: 3,
+                read: 0,
+                known_chapters: 0,
+                known_chars: 0,
+                average_chars: None,
+            },
+        ];
+        let stats = compute(
+            &[],
+            &[],
+            &SessionTotals::default(),
+            &backlog,
+            Some(1000.0),
+            date("2026-03-14"),
+        );
+        let novels = &stats["catch_up"]["novels"];
+        assert_eq!(novels[0]["unread_chapters"], 4);
+        assert_eq!(novels[0]["estimated_characters"], 7000);
+        assert_eq!(novels[0]["estimated_minutes"], 14);
+        assert_eq!(novels[1]["estimated_characters"], 3000);
+        assert_eq!(novels[1]["estimated_minutes"], 6);
+        assert_eq!(stats["catch_up"]["total_minutes"], 20);
+        assert_eq!(stats["reading_speed"]["measured"], false);
+
+        let stats = compute(
+            &[],
+            &[],
+            &SessionTotals::default(),
+            &backlog,
+            None,
+            date("2026-03-14"),
+        );
+        assert_eq!(
+            stats["catch_up"]["novels"][1]["estimated_minutes"],
+            Value::Null
+        );
+    }
+}
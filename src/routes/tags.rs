@@ -0,0 +1,182 @@
+use crate::auth::UserId;
+use crate::error::AppError;
+use crate::repo::TagsRepo;
+use crate::state::AppState;
+use axum::extract::{Path, State};
+use axum::routing::{delete, get, patch};
+use axum::{Extension, Json, Router};
+use serde::Deserialize;
+use serde_json::{json, Value};
+
+pub fn routes() -> Router<AppState> {
+    Router::new()
+        .route("/api/tags", get(get_tags).post(post_tag))
+        .route("/api/tags/{id}", patch(patch_tag))
+        .route("/api/tags/{id}", delete(delete_tag))
+}
+
+const MAX_TAG_LEN: usize = 50;
+
+#[derive(Deserialize)]
+struct TagBody {
+    name: Option<String>,
+}
+
+/// Trim and check a tag name from a request.
+pub(super) fn tag_name(name: &str) -> Result<String, AppError> {
+    let name = name.trim();
+    if name.is_empty() || name.chars().count() > MAX_TAG_LEN {
+        return Err(AppError::BadRequest(format!(
+            "tag name must be 1 to {} characters",
+            MAX_TAG_LEN
+        )));
+    }
+    // Commas separate tags in backups
+    if name.chars().any(|c| c.is_control() || c == ',') {
+        return Err(AppError::BadRequest(
+            "tag name must not contain commas or control characters".into(),
+        ));
+    }
+    Ok(name.to_string())
+}
+
+fn body_name(body: TagBody) -> Result<String, AppError> {
+    let name = body
+        .name
+        .ok_or_else(|| AppError::BadRequest("name is required".into()))?;
+    tag_name(&name)
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/tags",
+    tag = "お気に入り",
+    summary = "タグ一覧取得",
+    description = "お気に入りの分類に使うタグを名前順に取得する。`count` はタグが付いたお気に入りの数。",
+    responses(
+        (status = 200, description = "タグ一覧", body = Vec<crate::openapi::Tag>,
+            example = json!([{"id": 1, "name": "読書中", "count": 12}])),
+        (status = 500, description = "DBエラー", body = crate::openapi::ErrorResponse),
+    ),
+)]
+async fn get_tags(
+    State(state): State<AppState>,
+    Extension(user_id): Extension<UserId>,
+) -> Result<Json<Value>, AppError> {
+    let tags = state
+        .db
+        .read(move |conn| TagsRepo::new(conn).list(user_id))
+        .await?;
+    Ok(Json(tags.iter().map(|t| t.to_json()).collect()))
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/tags",
+    tag = "お気に入り",
+    summary = "タグ作成",
+    description = "タグを作成する。お気に入りへのタグ付け（`PUT /api/favorites/{type}/{id}/tags`）でも未作成のタグは自動で作られる。",
+    request_body(content = crate::openapi::TagRequest, description = "タグ名（50文字まで、カンマは使用不可）",
+        example = json!({"name": "読書中"})),
+    responses(
+        (status = 200, description = "作成されたタグ", body = crate::openapi::Tag),
+        (status = 400, description = "タグ名が不正、または同名のタグが存在する", body = crate::openapi::ErrorResponse,
+            example = json!({"error": "tag already exists"})),
+        (status = 500, description = "DBエラー", body = crate::openapi::ErrorResponse),
+    ),
+)]
+async fn post_tag(
+    State(state): State<AppState>,
+    Extension(user_id): Extension<UserId>,
+    Json(body): Json<TagBody>,
+) -> Result<Json<Value>, AppError> {
+    let name = body_name(body)?;
+    let tag = state
+        .db
+        .write(move |conn| TagsRepo::new(conn).create(user_id, &name))
+        .await?
+        .ok_or_else(|| AppError::BadRequest("tag already exists".into()))?;
+    Ok(Json(tag.to_json()))
+}
+
+#[utoipa::path(
+    patch,
+    path = "/api/tags/{id}",
+    tag = "お気に入り",
+    summary = "タグ名変更",
+    description = "タグの名前を変更する。タグ付けされたお気に入りはそのまま。",
+    params(
+        ("id" = i64, Path, description = "タグID", example = 1),
+    ),
+    request_body(content = crate::openapi::TagRequest, description = "新しいタグ名",
+        example = json!({"name": "積読"})),
+    responses(
+        (status = 200, description = "変更成功", body = crate::openapi::OkResponse,
+            example = json!({"ok": true})),
+        (status = 400, description = "タグ名が不正、または同名のタグが存在する", body = crate::openapi::ErrorResponse),
+        (status = 404, description = "タグが存在しない", body = crate::openapi::ErrorResponse),
+        (status = 500, description = "DBエラー", body = crate::openapi::ErrorResponse),
+    ),
+)]
+async fn patch_tag(
+    State(state): State<AppState>,
+    Extension(user_id): Extension<UserId>,
+    Path(id): Path<i64>,
+    Json(body): Json<TagBody>,
+) -> Result<Json<Value>, AppError> {
+    let name = body_name(body)?;
+    match state
+        .db
+        .write(move |conn| TagsRepo::new(conn).rename(user_id, id, &name))
+        .await?
+    {
+        None => Err(AppError::NotFound("Not found".into())),
+        Some(false) => Err(AppError::BadRequest("tag already exists".into())),
+        Some(true) => Ok(Json(json!({ "ok": true }))),
+    }
+}
+
+#[utoipa::path(
+    delete,
+    path = "/api/tags/{id}",
+    tag = "お気に入り",
+    summary = "タグ削除",
+    description = "タグを削除する。お気に入りからもタグが外れる（お気に入り自体は削除されない）。",
+    params(
+        ("id" = i64, Path, description = "タグID", example = 1),
+    ),
+    responses(
+        (status = 200, description = "削除成功", body = crate::openapi::OkResponse,
+            example = json!({"ok": true})),
+        (status = 404, description = "タグが存在しない", body = crate::openapi::ErrorResponse),
+        (status = 500, description = "DBエラー", body = crate::openapi::ErrorResponse),
+    ),
+)]
+async fn delete_tag(
+    State(state): State<AppState>,
+    Extension(user_id): Extension<UserId>,
+    Path(id): Path<i64>,
+) -> Result<Json<Value>, AppError> {
+    let deleted = state
+        .db
+        .write(move |conn| TagsRepo::new(conn).delete(user_id, id))
+        .await?;
+    if !deleted {
+        return Err(AppError::NotFound("Not found".into()));
+    }
+    Ok(Json(json!({ "ok": true })))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tag_name_is_trimmed_and_bounded() {
+        assert_eq!(tag_name("  読書中 ").unwrap(), "読書中");
+        assert!(tag_name("   ").is_err());
+        assert!(tag_name(&"あ".repeat(MAX_TAG_LEN + 1)).is_err());
+        assert!(tag_name("a\nb").is_err());
+        assert!(tag_name("a,b").is_err());
+    }
+}
@@ -0,0 +1,249 @@
+use crate::auth::UserId;
+use crate::error::AppError;
+use crate::modules::ModuleType;
+use crate::repo::{FavoritesRepo, NewNovel};
+use crate::state::AppState;
+use axum::extract::State;
+use axum::routing::post;
+use axum::{Extension, Json, Router};
+use regex_lite::Regex;
+use rusqlite::Connection;
+use serde_json::{json, Value};
+use std::collections::HashSet;
+use std::sync::LazyLock;
+
+/// Distinct novels accepted per import. Kakuyomu metadata is fetched one work at a time.
+const MAX_IMPORT_NOVELS: usize = 1000;
+
+/// Novel URLs with or without a scheme, as found in bookmark pages or pasted lists
+static NOVEL_URL: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(
+        r#"(?:https?:)?(?://)?(?:www\.)?(?:ncode\.syosetu\.com|novel18\.syosetu\.com|kakuyomu\.jp)/[^\s"'<>()]*"#,
+    )
+    .unwrap()
+});
+
+pub fn routes() -> Router<AppState> {
+    Router::new().route("/api/favorites/import-urls", post(post_import_urls))
+}
+
+/// Every distinct novel referenced by a URL in `text`, in order of first appearance.
+fn extract_novels(text: &str) -> Vec<(ModuleType, String)> {
+    let mut seen = HashSet::new();
+    NOVEL_URL
+        .find_iter(text)
+        .filter_map(|m| {
+            let url = m.as_str();
+            let url = url
+                .strip_prefix("http:")
+                .or_else(|| url.strip_prefix("https:"))
+                .unwrap_or(url);
+            let url = url.strip_prefix("//").unwrap_or(url);
+            ModuleType::parse_url(&format!("https://{}", url))
+        })
+        .filter(|novel| seen.insert(novel.clone()))
+        .collect()
+}
+
+/// Create novels and favorites for fetched data. Returns `(added, existing)`.
+/// Novels already known are left to sync; only the favorite is added.
+fn save_found(
+    conn: &mut Connection,
+    user_id: UserId,
+    module: ModuleType,
+    data: &[Value],
+) -> rusqlite::Result<(usize, usize)> {
+    let (mut added, mut existing) = (0, 0);
+    let tx = conn.transaction()?;
+    let favorites = FavoritesRepo::new(&tx);
+    for datum in data {
+        let Some(id) = datum["id"].as_str() else {
+            continue;
+        };
+        let upserted = favorites.upsert(
+            user_id,
+            &NewNovel {
+                type_str: module.as_str(),
+                id,
+                title: datum["title"].as_str().unwrap_or(id),
+                page: datum["pages"].as_array().map_or(0, |p| p.len() as i64),
+                novelupdated_at: datum["novelupdated_at"].as_str(),
+            },
+        )?;
+        if upserted.favorite_added {
+            added += 1;
+        } else {
+            existing += 1;
+        }
+    }
+    tx.commit()?;
+    Ok((added, existing))
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/favorites/import-urls",
+    tag = "お気に入り",
+    summary = "URLからお気に入り一括登録",
+    description = "テキスト中の作品URLからお気に入りを一括登録する。各サイトのブックマーク一覧ページのHTMLや、URLを改行区切りで貼り付けたテキストをそのまま送る。\n\n`ncode.syosetu.com`・`novel18.syosetu.com`・`kakuyomu.jp/works/` のURLを認識する（話のURLやスキームなしも可）。同じ作品は1件にまとめる。\n\nメタデータは登録前に各サイトから一括取得する（カクヨムは1作品ずつ取得するため件数に比例して時間がかかる）。サイトに存在しない作品は `not_found`、取得に失敗した作品は `failed` に返し、登録しない。登録済みの作品は `existing` として数える。",
+    request_body(content = String, description = "ブックマークページのHTML、またはURLの一覧", content_type = "text/plain",
+        example = "https://ncode.syosetu.com/n1234ab/\nhttps://kakuyomu.jp/works/1177354054881234567"),
+    responses(
+        (status = 200, description = "登録結果", body = crate::openapi::UrlImportResult,
+            example = json!({"found": 3, "added": 1, "existing": 1, "not_found": [{"type": "narou", "id": "n0000zz"}], "failed": []})),
+        (status = 400, description = "作品URLが見つからない、または件数超過", body = crate::openapi::ErrorResponse,
+            example = json!({"error": "no novel URLs found"})),
+        (status = 500, description = "DBエラー", body = crate::openapi::ErrorResponse),
+    ),
+)]
+async fn post_import_urls(
+    State(state): State<AppState>,
+    Extension(user_id): Extension<UserId>,
+    body: String,
+) -> Result<Json<Value>, AppError> {
+    let novels = extract_novels(&body);
+    if novels.is_empty() {
+        return Err(AppError::BadRequest("no novel URLs found".into()));
+    }
+    if novels.len() > MAX_IMPORT_NOVELS {
+        return Err(AppError::BadRequest(format!(
+            "too many novels (max {})",
+            MAX_IMPORT_NOVELS
+        )));
+    }
+
+    let (mut added, mut existing) = (0, 0);
+    let mut not_found = Vec::new();
+    let mut failed = Vec::new();
+    for module in [
+        ModuleType::Narou,
+        ModuleType::Nocturne,
+        ModuleType::Kakuyomu,
+    ] {
+        let ids: Vec<String> = novels
+            .iter()
+            .filter(|(m, _)| *m == module)
+            .map(|(_, id)| id.clone())
+            .collect();
+        if ids.is_empty() {
+            continue;
+        }
+        let type_str = module.as_str();
+        let bulk = match module.fetch_data(&state.http, &ids).await {
+            Ok(bulk) => bulk,
+            Err(e) => {
+                tracing::error!("[import] {} fetch failed: {}", type_str, e);
+                failed.extend(ids.iter().map(|id| json!({"type": type_str, "id": id})));
+                continue;
+            }
+        };
+        let returned: HashSet<String> = bulk
+            .data
+            .iter()
+            .filter_map(|d| d["id"].as_str())
+            .map(str::to_lowercase)
+            .collect();
+        for id in &ids {
+            let entry = json!({"type": type_str, "id": id});
+            if bulk.missing.contains(id) {
+                not_found.push(entry);
+            } else if !returned.contains(&id.to_lowercase()) {
+                // Neither returned nor reported missing: its chunk failed
+                failed.push(entry);
+            }
+        }
+
+        let (a, e) = state
+            .db
+            .write(move |db| save_found(db, user_id, module, &bulk.data))
+            .await?;
+        added += a;
+        existing += e;
+    }
+
+    Ok(Json(json!({
+        "found": novels.len(),
+        "added": added,
+        "existing": existing,
+        "not_found": not_found,
+        "failed": failed,
+    })))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn extract_novels_from_bookmark_html() {
+        let html = r#"
+            <ul class="favnovel">
+              <li><a href="https://ncode.syosetu.com/n1234ab/">作品A</a>
+                  <a href="https://ncode.syosetu.com/n1234ab/57/">最新話</a></li>
+              <li><a href="//novel18.syosetu.com/n5678cd/">作品B</a></li>
+              <li><a href="https://kakuyomu.jp/works/1177354054881234567/episodes/1177354054889999999">作品C</a></li>
+              <li><a href="https://kakuyomu.jp/users/someone">作者</a></li>
+              <li><a href="https://syosetu.com/favnovelmain/list/">一覧</a></li>
+            </ul>"#;
+        assert_eq!(
+            extract_novels(html),
+            [
+                (ModuleType::Narou, "n1234ab".to_string()),
+                (ModuleType::Nocturne, "n5678cd".to_string()),
+                (ModuleType::Kakuyomu, "1177354054881234567".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn extract_novels_from_pasted_list() {
+        let text = "ncode.syosetu.com/N1111AA\n\
+                    http://www.kakuyomu.jp/works/42 (読みかけ)\n\
+                    https://example.com/n2222bb/\n\
+                    https://ncode.syosetu.com/n1111aa/3/";
+        assert_eq!(
+            extract_novels(text),
+            [
+                (ModuleType::Narou, "n1111aa".to_string()),
+                (ModuleType::Kakuyomu, "42".to_string()),
+            ]
+        );
+        assert!(extract_novels("nothing here").is_empty());
+    }
+
+    #[test]
+    fn save_found_creates_novels_and_favorites() {
+        let mut conn = crate::db::open_memory();
+        conn.execute_batch(
+            "INSERT INTO novels (type, id, title, page) VALUES ('narou', 'n1', 'Known', 10);
+             INSERT INTO favorites (user_id, type, id, read) VALUES (1, 'narou', 'n1', 4);",
+        )
+        .unwrap();
+        let data = [
+            json!({"id": "n1", "title": "Known (new title)", "pages": [{}, {}]}),
+            json!({"id": "n2", "title": "Fresh", "pages": [{}, {}, {}], "novelupdated_at": "2026-03-01 10:00:00"}),
+        ];
+        let result = save_found(&mut conn, UserId(1), ModuleType::Narou, &data).unwrap();
+        assert_eq!(result, (1, 1));
+
+        let rows: Vec<(String, String, i64, i64)> = conn
+            .prepare(
+                "SELECT n.id, n.title, n.page, f.read FROM favorites f
+                 JOIN novels n ON n.type = f.type AND n.id = f.id ORDER BY n.id",
+            )
+            .unwrap()
+            .query_map([], |row| {
+                Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?))
+            })
+            .unwrap()
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert_eq!(
+            rows,
+            [
+                ("n1".to_string(), "Known".to_string(), 10, 4),
+                ("n2".to_string(), "Fresh".to_string(), 3, 0),
+            ]
+        );
+    }
+}
@@ -0,0 +1,180 @@
+use crate::auth::UserId;
+use crate::error::AppError;
+use crate::repo::WebhooksRepo;
+use crate::state::AppState;
+use crate::webhook;
+use axum::extract::{Path, State};
+use axum::routing::{delete, get};
+use axum::{Extension, Json, Router};
+use serde::Deserialize;
+use serde_json::{json, Value};
+
+const DELIVERIES_LIMIT: i64 = 50;
+
+pub fn routes() -> Router<AppState> {
+    Router::new()
+        .route("/api/webhooks", get(get_webhooks).post(post_webhook))
+        .route("/api/webhooks/{id}", delete(delete_webhook))
+        .route("/api/webhooks/{id}/deliveries", get(get_deliveries))
+}
+
+#[derive(Deserialize)]
+struct WebhookBody {
+    url: Option<String>,
+    secret: Option<String>,
+    events: Option<Vec<String>>,
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/webhooks",
+    tag = "Webhook",
+    summary = "Webhook一覧取得",
+    description = "登録済みのWebhookを登録日時の昇順で取得する。署名用シークレットは含まれない。",
+    responses(
+        (status = 200, description = "Webhook一覧", body = Vec<crate::openapi::Webhook>,
+            example = json!([{"id": 1, "url": "https://example.com/hook", "events": ["new_chapters"], "created_at": "2026-03-14 00:00:00"}])),
+        (status = 500, description = "DBエラー", body = crate::openapi::ErrorResponse),
+    ),
+)]
+async fn get_webhooks(
+    State(state): State<AppState>,
+    Extension(user_id): Extension<UserId>,
+) -> Result<Json<Value>, AppError> {
+    let rows = state
+        .db
+        .read(move |conn| WebhooksRepo::new(conn).list(user_id))
+        .await?;
+    Ok(Json(Value::Array(rows)))
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/webhooks",
+    tag = "Webhook",
+    summary = "Webhook登録",
+    description = "Webhookを登録する。同期でお気に入り小説の新着話が検出されると、指定URLへJSONをPOSTする。\n\n## 署名\nリクエストボディをシークレットで HMAC-SHA256 した値が `X-Novel-Signature: sha256=<hex>` ヘッダーに付与される。イベント種別は `X-Novel-Event` ヘッダー。シークレット省略時はサーバーが生成し、このレスポンスでのみ返す。\n\n## リトライ\nネットワークエラー・タイムアウト・429・5xx の場合は最大4回まで送信する（2秒・4秒・8秒のバックオフ）。それ以外の4xxはリトライしない。リダイレクトには従わない。結果は配信ログに記録される。\n\n## 送信先の制限\nループバック・プライベート（RFC 1918 など）・リンクローカルのアドレスに解決されるURLは登録できず、配信時にも再確認する。サーバー設定 `WEBHOOK_ALLOW_PRIVATE=true` で許可できる。",
+    request_body(content = crate::openapi::WebhookRequest, description = "送信先URL・シークレット・イベント種別",
+        example = json!({"url": "https://example.com/hook", "events": ["new_chapters"]})),
+    responses(
+        (status = 200, description = "登録されたWebhook（シークレットを含む）", body = crate::openapi::Webhook,
+            example = json!({"id": 1, "url": "https://example.com/hook", "events": ["new_chapters"], "created_at": "2026-03-14 00:00:00", "secret": "9f86d081884c7d65..."})),
+        (status = 400, description = "URLまたはイベント種別が不正、または送信先が公開アドレスでない", body = crate::openapi::ErrorResponse,
+            example = json!({"error": "url must be an http(s) URL"})),
+        (status = 500, description = "DBエラー", body = crate::openapi::ErrorResponse),
+    ),
+)]
+async fn post_webhook(
+    State(state): State<AppState>,
+    Extension(user_id): Extension<UserId>,
+    Json(body): Json<WebhookBody>,
+) -> Result<Json<Value>, AppError> {
+    let url = body
+        .url
+        .filter(|u| {
+            reqwest::Url::parse(u)
+                .map(|u| matches!(u.scheme(), "http" | "https"))
+                .unwrap_or(false)
+        })
+        .ok_or_else(|| AppError::BadRequest("url must be an http(s) URL".into()))?;
+    webhook::resolve_target(&url, state.config.webhook_allow_private)
+        .await
+        .map_err(AppError::BadRequest)?;
+    let events = body
+        .events
+        .unwrap_or_else(|| vec![webhook::EVENT_NEW_CHAPTERS.to_string()]);
+    if events.is_empty()
+        || events
+            .iter()
+            .any(|e| !webhook::EVENTS.contains(&e.as_str()))
+    {
+        return Err(AppError::BadRequest(format!(
+            "events must be a non-empty subset of: {}",
+            webhook::EVENTS.join(", ")
+        )));
+    }
+    let secret = match body.secret {
+        Some(s) if s.len() < 16 => {
+            return Err(AppError::BadRequest(
+                "secret must be at least 16 characters".into(),
+            ))
+        }
+        Some(s) => s,
+        None => crate::auth::random_token(),
+    };
+
+    let mut webhook = {
+        let secret = secret.clone();
+        state
+            .db
+            .write(move |conn| WebhooksRepo::new(conn).create(user_id, &url, &secret, &events))
+            .await?
+    };
+    webhook["secret"] = json!(secret);
+    Ok(Json(webhook))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/api/webhooks/{id}",
+    tag = "Webhook",
+    summary = "Webhook削除",
+    description = "Webhookと配信ログを削除する。",
+    params(
+        ("id" = i64, Path, description = "Webhook ID", example = 1),
+    ),
+    responses(
+        (status = 200, description = "削除成功", body = crate::openapi::OkResponse,
+            example = json!({"ok": true})),
+        (status = 404, description = "Webhookが存在しない", body = crate::openapi::ErrorResponse),
+        (status = 500, description = "DBエラー", body = crate::openapi::ErrorResponse),
+    ),
+)]
+async fn delete_webhook(
+    State(state): State<AppState>,
+    Extension(user_id): Extension<UserId>,
+    Path(id): Path<i64>,
+) -> Result<Json<Value>, AppError> {
+    let deleted = state
+        .db
+        .write(move |conn| WebhooksRepo::new(conn).delete(user_id, id))
+        .await?;
+    if !deleted {
+        return Err(AppError::NotFound("Not found".into()));
+    }
+    Ok(Json(json!({ "ok": true })))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/webhooks/{id}/deliveries",
+    tag = "Webhook",
+    summary = "配信ログ取得",
+    description = "Webhookの配信ログを新しい順に最大50件取得する。ログはWebhookごとに直近100件まで保持される。",
+    params(
+        ("id" = i64, Path, description = "Webhook ID", example = 1),
+    ),
+    responses(
+        (status = 200, description = "配信ログ", body = Vec<crate::openapi::WebhookDelivery>,
+            example = json!([{"id": 10, "event": "new_chapters", "payload": "{\"event\":\"new_chapters\"}", "status": 200, "attempts": 1, "success": true, "error": null, "created_at": "2026-03-14 00:00:00"}])),
+        (status = 404, description = "Webhookが存在しない", body = crate::openapi::ErrorResponse),
+        (status = 500, description = "DBエラー", body = crate::openapi::ErrorResponse),
+    ),
+)]
+async fn get_deliveries(
+    State(state): State<AppState>,
+    Extension(user_id): Extension<UserId>,
+    Path(id): Path<i64>,
+) -> Result<Json<Value>, AppError> {
+    let rows = state
+        .db
+        .read(move |conn| {
+            let webhooks = WebhooksRepo::new(conn);
+            if !webhooks.is_owned_by(user_id, id)? {
+                return Err(AppError::NotFound("Not found".into()));
+            }
+            Ok(webhooks.deliveries(id, DELIVERIES_LIMIT)?)
+        })
+        .await?;
+    Ok(Json(Value::Array(rows)))
+}
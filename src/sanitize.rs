@@ -31,10 +31,144 @@ pub fn clean(html: &str) -> String {
     SANITIZER.clean(html).to_string()
 }
 
+/// Characters a reader actually reads in sanitized HTML: tags, whitespace and
+/// ruby readings (`rt`/`rp`) are not counted, and an entity counts as the
+/// character it stands for (so `&nbsp;` is whitespace).
+pub fn text_length(html: &str) -> usize {
+    Tokens::new(html)
+        .filter(|t| matches!(t, Token::Text(c) if !c.is_whitespace()))
+        .count()
+}
+
+/// Text of each `<p>` in sanitized HTML, in order, as a reader sees it: tags
+/// and ruby readings dropped, entities decoded. Paragraph `i` is the `i`th
+/// `<p>` on the page. A page without `<p>` is one paragraph.
+pub fn paragraphs(html: &str) -> Vec<String> {
+    let mut paragraphs = Vec::new();
+    let mut outside = String::new();
+    let mut current: Option<String> = None;
+    for token in Tokens::new(html) {
+        match token {
+            Token::Tag { name, closing } if name == "p" => {
+                paragraphs.extend(current.take());
+                if !closing {
+                    current = Some(String::new());
+                }
+            }
+            Token::Tag { .. } => {}
+            Token::Text(c) => current.as_mut().unwrap_or(&mut outside).push(c),
+        }
+    }
+    paragraphs.extend(current);
+    if paragraphs.is_empty() {
+        paragraphs.push(outside);
+    }
+    paragraphs
+}
+
+enum Token {
+    Tag {
+        /// Lowercased element name
+        name: String,
+        closing: bool,
+    },
+    Text(char),
+}
+
+/// Scans sanitized HTML into tags and the characters a reader sees: ruby
+/// readings are skipped and entities decoded.
+struct Tokens<'a> {
+    chars: std::str::Chars<'a>,
+    ruby_depth: usize,
+}
+
+impl<'a> Tokens<'a> {
+    fn new(html: &'a str) -> Self {
+        Self {
+            chars: html.chars(),
+            ruby_depth: 0,
+        }
+    }
+}
+
+impl Iterator for Tokens<'_> {
+    type Item = Token;
+
+    fn next(&mut self) -> Option<Token> {
+        loop {
+            match self.chars.next()? {
+                '<' => {
+                    let tag: String = self.chars.by_ref().take_while(|&c| c != '>').collect();
+                    let closing = tag.starts_with('/');
+                    let name = tag
+                        .trim_start_matches('/')
+                        .split(|c: char| c.is_whitespace() || c == '/')
+                        .next()
+                        .unwrap_or_default()
+                        .to_ascii_lowercase();
+                    if name == "rt" || name == "rp" {
+                        if closing {
+                            self.ruby_depth = self.ruby_depth.saturating_sub(1);
+                        } else {
+                            self.ruby_depth += 1;
+                        }
+                    }
+                    return Some(Token::Tag { name, closing });
+                }
+                _ if self.ruby_depth > 0 => {}
+                '&' => {
+                    let entity: String = self.chars.by_ref().take_while(|&c| c != ';').collect();
+                    return Some(Token::Text(decode_entity(&entity).unwrap_or('\u{fffd}')));
+                }
+                c => return Some(Token::Text(c)),
+            }
+        }
+    }
+}
+
+fn decode_entity(entity: &str) -> Option<char> {
+    match entity {
+        "amp" => Some('&'),
+        "lt" => Some('<'),
+        "gt" => Some('>'),
+        "quot" => Some('"'),
+        "apos" => Some('\''),
+        "nbsp" => Some('\u{a0}'),
+        _ => {
+            let code = match entity
+                .strip_prefix("#x")
+                .or_else(|| entity.strip_prefix("#X"))
+            {
+                Some(hex) => u32::from_str_radix(hex, 16).ok()?,
+                None => entity.strip_prefix('#')?.parse().ok()?,
+            };
+            char::from_u32(code)
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn text_length_counts_what_is_read() {
+        let html = "<p>吾輩は<ruby>猫<rp>(</rp><rt>ねこ</rt><rp>)</rp></ruby>である。</p>\n<p>&lt;名前&gt; はまだ無い</p>";
+        assert_eq!(
+            text_length(html),
+            "吾輩は猫である。<名前>はまだ無い".chars().count()
+        );
+        assert_eq!(text_length(""), 0);
+        assert_eq!(text_length("<p class=\"x\">A&nbsp;B</p><RT>x</RT>"), 2);
+    }
+
+    #[test]
+    fn paragraphs_split_on_p_elements() {
+        let html = "<p>「<ruby>勇者<rp>(</rp><rt>ゆうしゃ</rt><rp>)</rp></ruby>だ」</p>\n<p><br></p><p>A &amp; B&#x21;</p>";
+        assert_eq!(paragraphs(html), ["「勇者だ」", "", "A & B!"]);
+        assert_eq!(paragraphs("first<br>second"), ["firstsecond"]);
+    }
+
     #[test]
     fn preserves_allowed_tags() {
         let html = "<p>text</p><br><hr><div>d</div><span>s</span>";
@@ -1,13 +1,13 @@
 use crate::cache::Cache;
 use crate::config::Config;
-use rusqlite::Connection;
-use std::sync::{Arc, Mutex};
+use crate::db::Db;
+use std::sync::Arc;
 
 #[derive(Clone)]
 pub struct AppState {
-    /// No async pool needed — SQLite is fast enough with a simple Mutex.
-    /// Keep the guard within a {} block; holding it across .await violates Send.
-    pub db: Arc<Mutex<Connection>>,
+    /// Queries run on the blocking pool: `state.db.read(|conn| ...).await?`.
+    /// SQL lives in `crate::repo`, not in handlers.
+    pub db: Db,
     pub cache: Arc<Cache>,
     pub config: Config,
     pub http: reqwest::Client,
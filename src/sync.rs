@@ -1,9 +1,10 @@
+use crate::db::Db;
+use crate::error::AppError;
 use crate::modules::ModuleType;
+use crate::repo::FavoritesRepo;
 use crate::state::AppState;
 use chrono::Utc;
-use rusqlite::Connection;
 use serde_json::Value;
-use std::sync::{Arc, Mutex};
 use std::time::Duration;
 
 /// Periodically sync favorite metadata in the background.
@@ -22,47 +23,125 @@ pub fn start_sync(state: AppState) {
     start_kakuyomu_sync(state);
 }
 
-fn get_ids(db: &Arc<Mutex<Connection>>, type_str: &str) -> Vec<String> {
-    let conn = db.lock().unwrap();
-    let mut stmt = match conn.prepare("SELECT DISTINCT id FROM favorites WHERE type = ?1") {
-        Ok(s) => s,
-        Err(e) => {
-            tracing::error!("[sync] {} db error: {}", type_str, e);
-            return Vec::new();
-        }
-    };
-    let result = match stmt.query_map(rusqlite::params![type_str], |row| row.get::<_, String>(0)) {
-        Ok(rows) => rows.filter_map(|r| r.ok()).collect(),
+async fn get_ids(db: &Db, type_str: &str) -> Vec<String> {
+    let type_owned = type_str.to_string();
+    let result = db
+        .read(move |conn| FavoritesRepo::new(conn).novel_ids(&type_owned))
+        .await;
+    result.unwrap_or_else(|e| {
+        tracing::error!("[sync] {} db error: {}", type_str, e);
+        Vec::new()
+    })
+}
+
+/// New chapters detected for a novel during sync.
+#[derive(Debug, Clone)]
+pub struct NovelUpdate {
+    pub type_str: String,
+    pub id: String,
+    pub title: String,
+    pub old_page: i64,
+    pub new_page: i64,
+}
+
+/// Update the shared novel record with fetched datum (once per novel, not per user).
+/// Only updates `novelupdated_at` when `page` has increased (new chapters detected).
+pub async fn update_novel_from_datum(db: &Db, type_str: &str, datum: Value) -> Option<NovelUpdate> {
+    match apply_bulk(db, type_str, vec![datum], Vec::new()).await {
+        Ok((_, mut updates)) => updates.pop(),
         Err(e) => {
-            tracing::error!("[sync] {} query error: {}", type_str, e);
-            Vec::new()
+            tracing::error!("[sync] {} update error: {}", type_str, e);
+            None
         }
-    };
-    result
+    }
 }
 
-/// Update a single favorite record with fetched datum.
-/// Only updates `novelupdated_at` when `page` has increased (new chapters detected).
-pub fn update_favorite_from_datum(db: &Arc<Mutex<Connection>>, type_str: &str, datum: &Value) {
-    let id = datum["id"].as_str().unwrap_or_default();
-    let title = datum["title"].as_str();
-    let new_page = datum["pages"].as_array().map(|a| a.len() as i64);
+/// Apply fetched data in one transaction and extend the "not found" streak of
+/// `missing` novels. Returns the number of changed rows and the detected updates.
+async fn apply_bulk(
+    db: &Db,
+    type_str: &str,
+    data: Vec<Value>,
+    missing: Vec<String>,
+) -> Result<(usize, Vec<NovelUpdate>), AppError> {
+    let type_str = type_str.to_string();
+    db.write(move |conn| {
+        let tx = conn.transaction()?;
+        let favorites = FavoritesRepo::new(&tx);
+        let now = Utc::now().format("%Y-%m-%d %H:%M:%S").to_string();
+        let mut changed = 0usize;
+        let mut updates = Vec::new();
+        for datum in &data {
+            match favorites.apply_datum(&type_str, datum, &now) {
+                Ok((n, update)) => {
+                    changed += n;
+                    updates.extend(update);
+                }
+                Err(e) => tracing::error!("[sync] {} update error: {}", type_str, e),
+            }
+        }
+        for id in &missing {
+            let _ = favorites.mark_missing(&type_str, id);
+        }
+        tx.commit()?;
+        Ok::<_, rusqlite::Error>((changed, updates))
+    })
+    .await
+}
 
-    if title.is_none() && new_page.is_none() {
+/// Hand detected updates to the notification channels.
+pub async fn notify_updates(state: &AppState, updates: Vec<NovelUpdate>) {
+    if updates.is_empty() {
         return;
     }
+    let recorded = updates.clone();
+    if let Err(e) = state
+        .db
+        .write(move |conn| crate::digest::record_updates(conn, &recorded, Utc::now()))
+        .await
+    {
+        tracing::error!("[sync] failed to record chapter updates: {}", e);
+    }
+    crate::webhook::dispatch(state, &updates).await;
+    crate::push::dispatch(state, &updates).await;
+}
 
-    let conn = db.lock().unwrap();
-    let now = Utc::now().format("%Y-%m-%d %H:%M:%S").to_string();
-    let _ = conn.execute(
-        "UPDATE favorites SET
-            title = COALESCE(?1, title),
-            page = COALESCE(?2, page),
-            novelupdated_at = CASE WHEN ?2 > page THEN ?3 ELSE novelupdated_at END
-         WHERE type = ?4 AND id = ?5
-            AND (?2 IS NOT NULL AND ?2 != page OR ?1 IS NOT NULL AND ?1 != title)",
-        rusqlite::params![title, new_page, now, type_str, id],
-    );
+/// Fetch metadata for newly added favorites in the background so they don't wait for
+/// the next sync round. A novel still at page 0 is a placeholder (e.g. from an import),
+/// so filling it in is not reported as new chapters.
+pub fn spawn_initial_fetch(state: AppState, module: ModuleType, ids: Vec<String>) {
+    if ids.is_empty() {
+        return;
+    }
+    tokio::spawn(async move {
+        let type_str = module.as_str();
+        match module.fetch_data(&state.http, &ids).await {
+            Ok(bulk) => {
+                for id in &bulk.missing {
+                    tracing::warn!("[sync] initial fetch: {}/{} not found", type_str, id);
+                }
+                let count = bulk.data.len();
+                let updates = match apply_bulk(&state.db, type_str, bulk.data, Vec::new()).await {
+                    Ok((_, updates)) => updates,
+                    Err(e) => {
+                        tracing::error!("[sync] initial fetch for {}: {}", type_str, e);
+                        return;
+                    }
+                };
+                let updates = updates.into_iter().filter(|u| u.old_page > 0).collect();
+                notify_updates(&state, updates).await;
+                tracing::info!("[sync] initial fetch for {} {} novel(s)", count, type_str);
+            }
+            Err(e) => {
+                tracing::error!(
+                    "[sync] initial fetch failed for {} {} novel(s): {}",
+                    ids.len(),
+                    type_str,
+                    e
+                );
+            }
+        }
+    });
 }
 
 fn start_syosetu_sync(state: AppState, module: ModuleType, interval: Duration) {
@@ -81,43 +160,31 @@ fn start_syosetu_sync(state: AppState, module: ModuleType, interval: Duration) {
 }
 
 async fn sync_syosetu(state: &AppState, module: &ModuleType, type_str: &str) {
-    let ids = get_ids(&state.db, type_str);
+    let ids = get_ids(&state.db, type_str).await;
     if ids.is_empty() {
         return;
     }
 
     match module.fetch_data(&state.http, &ids).await {
-        Ok(data) => {
-            let mut changed = 0usize;
-            {
-                let conn = state.db.lock().unwrap();
-                let tx = match conn.unchecked_transaction() {
-                    Ok(tx) => tx,
+        Ok(bulk) => {
+            // The bulk API silently omits deleted or hidden novels
+            let missing = bulk.missing.len();
+            let (changed, updates) =
+                match apply_bulk(&state.db, type_str, bulk.data, bulk.missing).await {
+                    Ok(result) => result,
                     Err(e) => {
                         tracing::error!("[sync] {} transaction error: {}", type_str, e);
                         return;
                     }
                 };
-                let now = Utc::now().format("%Y-%m-%d %H:%M:%S").to_string();
-                for datum in &data {
-                    let id = datum["id"].as_str().unwrap_or_default();
-                    let title = datum["title"].as_str();
-                    let new_page = datum["pages"].as_array().map(|a| a.len() as i64);
-
-                    if title.is_some() || new_page.is_some() {
-                        changed += tx.execute(
-                            "UPDATE favorites SET
-                                title = COALESCE(?1, title),
-                                page = COALESCE(?2, page),
-                                novelupdated_at = CASE WHEN ?2 > page THEN ?3 ELSE novelupdated_at END
-                             WHERE type = ?4 AND id = ?5 AND (?2 IS NOT NULL AND ?2 != page OR ?1 IS NOT NULL AND ?1 != title)",
-                            rusqlite::params![title, new_page, now, type_str, id],
-                        ).unwrap_or(0);
-                    }
-                }
-                let _ = tx.commit();
-            }
-            tracing::info!("[sync] {}: checked {} items, {} changed", type_str, data.len(), changed);
+            tracing::info!(
+                "[sync] {}: checked {} items, {} changed, {} missing",
+                type_str,
+                ids.len(),
+                changed,
+                missing
+            );
+            notify_updates(state, updates).await;
         }
         Err(e) => {
             tracing::error!("[sync] {} error: {}", type_str, e);
@@ -132,7 +199,7 @@ fn start_kakuyomu_sync(state: AppState) {
         let mut index: usize = 0;
 
         loop {
-            let ids = get_ids(&state.db, type_str);
+            let ids = get_ids(&state.db, type_str).await;
             let count = ids.len();
             if count == 0 {
                 tokio::time::sleep(Duration::from_secs(60)).await;
@@ -144,12 +211,26 @@ fn start_kakuyomu_sync(state: AppState) {
 
             match module.fetch_datum(&state.http, &id).await {
                 Ok(datum) => {
-                    update_favorite_from_datum(&state.db, type_str, &datum);
+                    let update = update_novel_from_datum(&state.db, type_str, datum).await;
+                    notify_updates(&state, update.into_iter().collect()).await;
                     tracing::info!("[sync] kakuyomu: updated {} ({}/{})", id, index + 1, count);
                     index += 1;
                     let interval_ms = 3_600_000u64 / count as u64;
                     tokio::time::sleep(Duration::from_millis(interval_ms)).await;
                 }
+                Err(AppError::NotFound(_)) => {
+                    let missing = vec![id.clone()];
+                    let _ = apply_bulk(&state.db, type_str, Vec::new(), missing).await;
+                    tracing::warn!(
+                        "[sync] kakuyomu: {} not found ({}/{})",
+                        id,
+                        index + 1,
+                        count
+                    );
+                    index += 1;
+                    let interval_ms = 3_600_000u64 / count as u64;
+                    tokio::time::sleep(Duration::from_millis(interval_ms)).await;
+                }
                 Err(e) => {
                     tracing::error!("[sync] kakuyomu error: {}", e);
                     tokio::time::sleep(Duration::from_secs(60)).await;
@@ -158,3 +239,68 @@ fn start_kakuyomu_sync(state: AppState) {
         }
     });
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rusqlite::Connection;
+
+    fn insert_favorite(conn: &Connection, user_id: i64, id: &str) {
+        conn.execute(
+            "INSERT OR IGNORE INTO users (id, email) VALUES (?1, ?2)",
+            rusqlite::params![user_id, format!("u{}@example.com", user_id)],
+        )
+        .unwrap();
+        conn.execute(
+            "INSERT OR IGNORE INTO novels (type, id, title, page) VALUES ('narou', ?1, 'Novel', 10)",
+            [id],
+        )
+        .unwrap();
+        conn.execute(
+            "INSERT INTO favorites (user_id, type, id) VALUES (?1, 'narou', ?2)",
+            rusqlite::params![user_id, id],
+        )
+        .unwrap();
+    }
+
+    #[tokio::test]
+    async fn update_novel_from_datum_bumps_updated_at_on_new_pages() {
+        let conn = crate::db::open_memory();
+        insert_favorite(&conn, 1, "n1");
+        insert_favorite(&conn, 2, "n1");
+        let db = Db::from_connection(conn);
+
+        let datum = serde_json::json!({
+            "id": "n1",
+            "title": "Renamed",
+            "pages": (1..=12).collect::<Vec<_>>(),
+        });
+        let update = update_novel_from_datum(&db, "narou", datum).await.unwrap();
+        assert_eq!((update.old_page, update.new_page), (10, 12));
+        assert_eq!(update.title, "Renamed");
+
+        let (title, page, updated): (String, i64, Option<String>) = db
+            .read(|conn| {
+                conn.query_row(
+                    "SELECT title, page, novelupdated_at FROM novels WHERE id = 'n1'",
+                    [],
+                    |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+                )
+            })
+            .await
+            .unwrap();
+        assert_eq!(title, "Renamed");
+        assert_eq!(page, 12);
+        assert!(updated.is_some(), "page increase must set novelupdated_at");
+    }
+
+    #[tokio::test]
+    async fn update_novel_from_datum_without_new_pages_is_not_an_update() {
+        let conn = crate::db::open_memory();
+        insert_favorite(&conn, 1, "n1");
+        let db = Db::from_connection(conn);
+
+        let datum = serde_json::json!({"id": "n1", "title": "Novel", "pages": [1, 2, 3]});
+        assert!(update_novel_from_datum(&db, "narou", datum).await.is_none());
+    }
+}
@@ -0,0 +1,406 @@
+use crate::repo::WebhooksRepo;
+use crate::state::AppState;
+use crate::sync::NovelUpdate;
+use chrono::Utc;
+use hmac::{Hmac, Mac};
+use serde_json::json;
+use sha2::Sha256;
+use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+use std::time::Duration;
+
+pub const EVENT_NEW_CHAPTERS: &str = "new_chapters";
+/// Event types a webhook can subscribe to
+pub const EVENTS: &[&str] = &[EVENT_NEW_CHAPTERS];
+
+const MAX_ATTEMPTS: u32 = 4;
+/// Delay before the first retry; doubled on each further attempt (2s, 4s, 8s).
+const BACKOFF_BASE: Duration = Duration::from_secs(2);
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+#[derive(Debug)]
+pub struct DeliveryOutcome {
+    /// Last HTTP status received (None when the request itself failed)
+    pub status: Option<u16>,
+    pub attempts: u32,
+    pub error: Option<String>,
+}
+
+impl DeliveryOutcome {
+    pub fn success(&self) -> bool {
+        self.status.is_some_and(|s| (200..300).contains(&s))
+    }
+}
+
+/// Send `new_chapters` events to every webhook whose owner has the novel in favorites.
+/// Deliveries run in background tasks so sync is never held up by slow endpoints.
+pub async fn dispatch(state: &AppState, updates: &[NovelUpdate]) {
+    let now = Utc::now().to_rfc3339();
+    let lookup = updates.to_vec();
+    let batches = state
+        .db
+        .read(move |conn| {
+            lookup
+                .into_iter()
+                .map(|u| {
+                    WebhooksRepo::new(conn)
+                        .targets(&u, EVENT_NEW_CHAPTERS)
+                        .map(|t| (u, t))
+                })
+                .collect::<rusqlite::Result<Vec<_>>>()
+        })
+        .await;
+    let batches = match batches {
+        Ok(b) => b,
+        Err(e) => {
+            tracing::error!("[webhook] target lookup failed: {}", e);
+            return;
+        }
+    };
+    for (update, targets) in batches {
+        for target in targets {
+            let payload = json!({
+                "event": EVENT_NEW_CHAPTERS,
+                "timestamp": now,
+                "novel": {
+                    "type": update.type_str,
+                    "id": update.id,
+                    "title": update.title,
+                },
+                "previous_page": update.old_page,
+                "page": update.new_page,
+                "read": target.read,
+            })
+            .to_string();
+            let allow_private = state.config.webhook_allow_private;
+            let db = state.db.clone();
+            tokio::spawn(async move {
+                let outcome = match target_client(&target.url, allow_private).await {
+                    Ok(client) => {
+                        deliver(
+                            &client,
+                            &target.url,
+                            &target.secret,
+                            EVENT_NEW_CHAPTERS,
+                            &payload,
+                            BACKOFF_BASE,
+                        )
+                        .await
+                    }
+                    Err(e) => DeliveryOutcome {
+                        status: None,
+                        attempts: 0,
+                        error: Some(e),
+                    },
+                };
+                if !outcome.success() {
+                    tracing::warn!(
+                        "[webhook] delivery to {} failed after {} attempts: {}",
+                        target.url,
+                        outcome.attempts,
+                        outcome.error.as_deref().unwrap_or("unknown error")
+                    );
+                }
+                let webhook_id = target.webhook_id;
+                let result = db
+                    .write(move |conn| {
+                        WebhooksRepo::new(conn).log_delivery(
+                            webhook_id,
+                            EVENT_NEW_CHAPTERS,
+                            &payload,
+                            &outcome,
+                        )
+                    })
+                    .await;
+                // The webhook may have been deleted while the delivery was in flight
+                if let Err(e) = result {
+                    tracing::warn!("[webhook] failed to log delivery for {}: {}", webhook_id, e);
+                }
+            });
+        }
+    }
+}
+
+/// Whether `ip` is on the public internet, i.e. not loopback, private, link-local,
+/// shared (CGNAT), multicast or otherwise reserved.
+fn is_public(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(v4) => is_public_v4(v4),
+        IpAddr::V6(v6) => match v6.to_ipv4_mapped() {
+            Some(v4) => is_public_v4(v4),
+            None => {
+                let [first, second, ..] = v6.segments();
+                !(v6.is_unspecified()
+                    || v6.is_loopback()
+                    || v6.is_multicast()
+                    || (first & 0xfe00) == 0xfc00 // unique local
+                    || (first & 0xffc0) == 0xfe80 // link-local
+                    || (first == 0x2001 && second == 0x0db8)) // documentation
+            }
+        },
+    }
+}
+
+fn is_public_v4(ip: Ipv4Addr) -> bool {
+    let [a, b, ..] = ip.octets();
+    !(a == 0
+        || ip.is_loopback()
+        || ip.is_private()
+        || ip.is_link_local()
+        || ip.is_multicast()
+        || ip.is_documentation()
+        || (a == 100 && (64..128).contains(&b)) // shared address space
+        || (a == 198 && (b == 18 || b == 19)) // benchmarking
+        || a >= 240) // reserved and broadcast
+}
+
+/// Resolve a webhook URL's host, refusing non-public addresses unless `allow_private`.
+/// Returns the host name with the addresses to connect to.
+pub async fn resolve_target(
+    url: &str,
+    allow_private: bool,
+) -> Result<(String, Vec<SocketAddr>), String> {
+    let url = reqwest::Url::parse(url).map_err(|e| e.to_string())?;
+    let port = url.port_or_known_default().unwrap_or(80);
+    let host = url.host_str().ok_or("url has no host")?.to_string();
+    // IP literals come back as-is; IPv6 ones are bracketed in URLs
+    let name = host.trim_start_matches('[').trim_end_matches(']');
+    let addrs: Vec<SocketAddr> = tokio::net::lookup_host((name, port))
+        .await
+        .map_err(|e| format!("cannot resolve {}: {}", host, e))?
+        .collect();
+    if addrs.is_empty() {
+        return Err(format!("cannot resolve {}", host));
+    }
+    if !allow_private {
+        if let Some(addr) = addrs.iter().find(|a| !is_public(a.ip())) {
+            return Err(format!(
+                "{} resolves to non-public address {}",
+                host,
+                addr.ip()
+            ));
+        }
+    }
+    Ok((host, addrs))
+}
+
+/// Client for one delivery. It only connects to the addresses checked by
+/// `resolve_target` and doesn't follow redirects, so neither a DNS change after the
+/// check nor a redirect can point the request at an internal service.
+pub async fn target_client(url: &str, allow_private: bool) -> Result<reqwest::Client, String> {
+    let (host, addrs) = resolve_target(url, allow_private).await?;
+    reqwest::Client::builder()
+        .redirect(reqwest::redirect::Policy::none())
+        .resolve_to_addrs(&host, &addrs)
+        .build()
+        .map_err(|e| e.to_string())
+}
+
+/// `X-Novel-Signature` header value: hex HMAC-SHA256 of the raw body keyed by the webhook secret.
+pub fn sign(secret: &str, body: &[u8]) -> String {
+    let mut mac =
+        Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC accepts any key length");
+    mac.update(body);
+    format!("sha256={}", hex::encode(mac.finalize().into_bytes()))
+}
+
+/// POST the payload, retrying network errors, timeouts, 429 and 5xx with exponential backoff.
+/// Other 4xx responses mean the receiver rejected the payload, so they are not retried.
+pub async fn deliver(
+    client: &reqwest::Client,
+    url: &str,
+    secret: &str,
+    event: &str,
+    body: &str,
+    backoff: Duration,
+) -> DeliveryOutcome {
+    let signature = sign(secret, body.as_bytes());
+    let mut outcome = DeliveryOutcome {
+        status: None,
+        attempts: 0,
+        error: None,
+    };
+
+    for attempt in 0..MAX_ATTEMPTS {
+        outcome.attempts = attempt + 1;
+        let res = client
+            .post(url)
+            .timeout(REQUEST_TIMEOUT)
+            .header(reqwest::header::CONTENT_TYPE, "application/json")
+            .header("X-Novel-Event", event)
+            .header("X-Novel-Signature", &signature)
+            .body(body.to_string())
+            .send()
+            .await;
+        match res {
+            Ok(res) => {
+                let status = res.status();
+                outcome.status = Some(status.as_u16());
+                if status.is_success() {
+                    outcome.error = None;
+                    return outcome;
+                }
+                outcome.error = Some(format!("HTTP {}", status));
+                let retryable = status.is_server_error()
+                    || status == reqwest::StatusCode::TOO_MANY_REQUESTS
+                    || status == reqwest::StatusCode::REQUEST_TIMEOUT;
+                if !retryable {
+                    return outcome;
+                }
+            }
+            Err(e) => {
+                outcome.status = None;
+                outcome.error = Some(e.to_string());
+            }
+        }
+        if attempt + 1 < MAX_ATTEMPTS {
+            tokio::time::sleep(backoff * 2u32.pow(attempt)).await;
+        }
+    }
+    outcome
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::extract::State;
+    use axum::http::{HeaderMap, StatusCode};
+    use axum::routing::post;
+    use axum::Router;
+    use std::sync::{Arc, Mutex};
+
+    type Received = Arc<Mutex<Vec<(HeaderMap, String)>>>;
+
+    /// Local HTTP stand-in that answers with `statuses` in order (repeating the last one).
+    async fn spawn_receiver(statuses: Vec<u16>) -> (String, Received) {
+        let received: Received = Arc::new(Mutex::new(Vec::new()));
+        let app = Router::new()
+            .route(
+                "/hook",
+                post(
+                    |State((received, statuses)): State<(Received, Arc<Vec<u16>>)>,
+                     headers: HeaderMap,
+                     body: String| async move {
+                        let mut received = received.lock().unwrap();
+                        received.push((headers, body));
+                        let i = (received.len() - 1).min(statuses.len() - 1);
+                        StatusCode::from_u16(statuses[i]).unwrap()
+                    },
+                ),
+            )
+            .with_state((received.clone(), Arc::new(statuses)));
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+        (format!("http://{}/hook", addr), received)
+    }
+
+    #[test]
+    fn sign_matches_known_hmac_sha256() {
+        assert_eq!(
+            sign("key", b"The quick brown fox jumps over the lazy dog"),
+            "sha256=f7bc83f430538424b13298e6aa6fb143ef4d59a14946175997479dbc2d1a3cd8"
+        );
+    }
+
+    #[tokio::test]
+    async fn deliver_retries_server_errors_and_signs_body() {
+        let (url, received) = spawn_receiver(vec![500, 503, 200]).await;
+        let client = reqwest::Client::new();
+        let body = r#"{"event":"new_chapters"}"#;
+
+        let outcome = deliver(
+            &client,
+            &url,
+            "s3cret",
+            EVENT_NEW_CHAPTERS,
+            body,
+            Duration::from_millis(1),
+        )
+        .await;
+        assert!(outcome.success());
+        assert_eq!(outcome.attempts, 3);
+
+        let received = received.lock().unwrap();
+        assert_eq!(received.len(), 3);
+        let (headers, got_body) = &received[2];
+        assert_eq!(got_body, body);
+        assert_eq!(headers["x-novel-event"], EVENT_NEW_CHAPTERS);
+        assert_eq!(
+            headers["x-novel-signature"].to_str().unwrap(),
+            sign("s3cret", body.as_bytes())
+        );
+    }
+
+    #[test]
+    fn is_public_rejects_internal_ranges() {
+        for ip in [
+            "127.0.0.1",
+            "10.1.2.3",
+            "172.16.0.1",
+            "192.168.1.1",
+            "169.254.169.254",
+            "100.64.0.1",
+            "0.0.0.0",
+            "255.255.255.255",
+            "::1",
+            "fe80::1",
+            "fd00::1",
+            "::ffff:127.0.0.1",
+        ] {
+            assert!(!is_public(ip.parse().unwrap()), "{}", ip);
+        }
+        for ip in ["93.184.216.34", "8.8.8.8", "2606:4700::1111"] {
+            assert!(is_public(ip.parse().unwrap()), "{}", ip);
+        }
+    }
+
+    #[tokio::test]
+    async fn resolve_target_refuses_private_hosts_unless_allowed() {
+        for url in [
+            "http://127.0.0.1:8080/hook",
+            "http://169.254.169.254/latest/meta-data",
+            "http://[::1]/hook",
+            "http://localhost/hook",
+        ] {
+            assert!(resolve_target(url, false).await.is_err(), "{}", url);
+        }
+        let (host, addrs) = resolve_target("http://127.0.0.1:8080/hook", true)
+            .await
+            .unwrap();
+        assert_eq!(host, "127.0.0.1");
+        assert_eq!(addrs, vec!["127.0.0.1:8080".parse().unwrap()]);
+    }
+
+    #[tokio::test]
+    async fn deliver_through_target_client() {
+        let (url, received) = spawn_receiver(vec![200]).await;
+        let client = target_client(&url, true).await.unwrap();
+
+        let outcome = deliver(&client, &url, "s", EVENT_NEW_CHAPTERS, "{}", Duration::ZERO).await;
+        assert!(outcome.success());
+        assert_eq!(received.lock().unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn deliver_does_not_retry_client_errors() {
+        let (url, received) = spawn_receiver(vec![404]).await;
+        let client = reqwest::Client::new();
+
+        let outcome = deliver(&client, &url, "s", EVENT_NEW_CHAPTERS, "{}", Duration::ZERO).await;
+        assert!(!outcome.success());
+        assert_eq!(outcome.status, Some(404));
+        assert_eq!(outcome.attempts, 1);
+        assert_eq!(received.lock().unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn deliver_gives_up_after_max_attempts() {
+        let (url, received) = spawn_receiver(vec![502]).await;
+        let client = reqwest::Client::new();
+
+        let outcome = deliver(&client, &url, "s", EVENT_NEW_CHAPTERS, "{}", Duration::ZERO).await;
+        assert!(!outcome.success());
+        assert_eq!(outcome.attempts, MAX_ATTEMPTS);
+        assert_eq!(received.lock().unwrap().len(), MAX_ATTEMPTS as usize);
+    }
+}